@@ -0,0 +1,80 @@
+//! Compares the memory used by the transitions of a large model, and the time
+//! taken to query them, between a frozen model and its succinct form.
+//!
+//! Run with `cargo run --release --example succinct_benchmark`.
+
+use std::time::Instant;
+
+const SEQUENCES: u64 = 200_000;
+const SEQUENCE_LEN: u64 = 50;
+const VOCABULARY: u64 = 5_000;
+const QUERIES: u64 = 1_000_000;
+
+// A small xorshift generator, so that the corpus is the same on every run
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// Draws symbols with a skewed distribution, as in natural text
+fn symbol(state: &mut u64) -> u32 {
+    let a = next(state) % VOCABULARY;
+    let b = next(state) % VOCABULARY;
+    a.min(b) as u32
+}
+
+fn main() {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let corpus = (0..SEQUENCES).map(|_| {
+        let mut state = next(&mut state);
+        (0..SEQUENCE_LEN)
+            .map(|_| symbol(&mut state))
+            .collect::<Vec<_>>()
+    });
+
+    let started = Instant::now();
+    let frozen = markov::Model::train_from(1, corpus).freeze();
+    println!("Trained in {:?}", started.elapsed());
+
+    let contexts: Vec<(u32, u32)> = (0..QUERIES)
+        .map(|_| (symbol(&mut state), symbol(&mut state)))
+        .collect();
+
+    let started = Instant::now();
+    let mut frozen_total = 0.0;
+    for (context, next_symbol) in &contexts {
+        frozen_total += frozen.probability(&[*context], Some(*next_symbol));
+    }
+    let frozen_time = started.elapsed();
+    let frozen_bytes = frozen.transition_bytes();
+
+    let succinct = frozen.into_succinct();
+
+    let started = Instant::now();
+    let mut succinct_total = 0.0;
+    for (context, next_symbol) in &contexts {
+        succinct_total += succinct.probability(&[*context], Some(*next_symbol));
+    }
+    let succinct_time = started.elapsed();
+    let succinct_bytes = succinct.transition_bytes();
+
+    assert!((frozen_total - succinct_total).abs() < 1e-6 * frozen_total.max(1.0));
+
+    println!(
+        "{} contexts, {} transitions",
+        succinct.context_count(),
+        succinct.transition_count()
+    );
+    println!(
+        "Frozen:   {} bytes for transitions, {:?} per query",
+        frozen_bytes,
+        frozen_time / QUERIES as u32
+    );
+    println!(
+        "Succinct: {} bytes for transitions, {:?} per query",
+        succinct_bytes,
+        succinct_time / QUERIES as u32
+    );
+}
@@ -1,28 +1,163 @@
+/// The weight given to each observation at the start of a half-life when
+/// recency weighting is enabled, chosen to keep rounding error small.
+const RECENCY_BASE_WEIGHT: f64 = 256.0;
+
 /// An Accumulator for updating a [Model](struct.Model.html) with training data.
-pub struct Accumulator<'a, TSymbol>
+pub struct Accumulator<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
 {
-    model: &'a mut crate::Model<TSymbol>,
-    current_sequence: crate::Sequence<TSymbol>,
+    model: &'a mut crate::Model<TSymbol, THasher>,
+    current_sequence: crate::Window<TSymbol>,
+    skip: usize,
+    history: Vec<TSymbol>,
+    half_life: Option<usize>,
+    observations: usize,
+    symbols_added: usize,
+    sequences_ended: usize,
+    progress_interval: usize,
+    progress_callback: Option<&'a mut dyn FnMut(&crate::Progress) -> bool>,
+    stopped: bool,
 }
 
-impl<'a, TSymbol> Accumulator<'a, TSymbol>
+impl<'a, TSymbol, THasher> Accumulator<'a, TSymbol, THasher>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
 {
     /// Creates an Accumulator to update the specified model.
     ///
     /// # Arguments
     ///
     /// `model` - The Markov model to update.
-    pub fn new(model: &mut crate::Model<TSymbol>) -> Accumulator<TSymbol> {
+    pub fn new(model: &mut crate::Model<TSymbol, THasher>) -> Accumulator<'_, TSymbol, THasher> {
+        Accumulator::with_skip(model, 0)
+    }
+
+    /// Creates an Accumulator which also trains on non-contiguous contexts,
+    /// making models trained on small corpora more robust.
+    ///
+    /// In addition to the usual context of the most recent symbols, each
+    /// symbol is also counted as following every ordered selection of prior
+    /// symbols which skips no more than `skip` positions. For example, with a
+    /// second order model and a skip of 1, the symbol following "a b c" is
+    /// counted as following each of "b c", "a c", and "a b".
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to update.
+    ///
+    /// `skip` - The maximum number of positions a context may skip.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(2);
+    ///
+    /// let mut acc = markov::Accumulator::with_skip(&mut model, 1);
+    /// acc.train_sequence("the quick brown fox".split(' '));
+    /// ```
+    pub fn with_skip(
+        model: &mut crate::Model<TSymbol, THasher>,
+        skip: usize,
+    ) -> Accumulator<'_, TSymbol, THasher> {
+        let order = model.order();
         Accumulator {
             model,
-            current_sequence: crate::Sequence::empty(),
+            current_sequence: crate::Window::empty(order),
+            skip,
+            history: vec![],
+            half_life: None,
+            observations: 0,
+            symbols_added: 0,
+            sequences_ended: 0,
+            progress_interval: 0,
+            progress_callback: None,
+            stopped: false,
         }
     }
 
+    /// Registers a callback which is periodically given the progress of
+    /// training, such as for driving a progress bar.
+    ///
+    /// The callback returns whether training should continue. Once it returns
+    /// `false`, this Accumulator stops: further symbols and sequence ends are
+    /// ignored, and bulk training methods such as
+    /// [`train_lines`](#method.train_lines) return early.
+    ///
+    /// # Arguments
+    ///
+    /// `interval` - The number of symbols to add between each invocation of
+    /// the callback.
+    ///
+    /// `callback` - The function to invoke with the current progress.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut report = |p: &markov::Progress| {
+    ///     println!("{} symbols, {} contexts", p.symbols, p.contexts);
+    ///     p.contexts < 50_000
+    /// };
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.on_progress(1000, &mut report);
+    /// acc.train_sequence("the quick brown fox".split(' '));
+    /// ```
+    pub fn on_progress(
+        &mut self,
+        interval: usize,
+        callback: &'a mut dyn FnMut(&crate::Progress) -> bool,
+    ) {
+        self.progress_interval = interval;
+        self.progress_callback = Some(callback);
+    }
+
+    /// Returns the progress of training so far.
+    pub fn progress(&self) -> crate::Progress {
+        crate::Progress {
+            symbols: self.symbols_added,
+            sequences: self.sequences_ended,
+            contexts: self.model.context_count(),
+        }
+    }
+
+    /// Returns whether training has been stopped by a progress callback.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Enables or disables recency weighting, so that the model tracks a
+    /// changing stream of training data.
+    ///
+    /// When enabled, the influence of each observation halves after the
+    /// specified number of further observations have been added. This is
+    /// achieved by giving newer observations exponentially greater weights
+    /// and periodically halving all of the model's counts, so counts in the
+    /// model no longer correspond directly to numbers of observations.
+    ///
+    /// # Arguments
+    ///
+    /// `half_life` - The number of observations (symbols and sequence ends)
+    /// after which an observation's influence is halved, or `None` to weight
+    /// all observations equally.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.set_half_life(Some(1000));
+    /// acc.add_all("keystrokes".chars());
+    /// ```
+    pub fn set_half_life(&mut self, half_life: Option<usize>) {
+        self.half_life = half_life.filter(|h| *h > 0);
+        self.observations = 0;
+    }
+
     /// Adds a symbol to the current sequence.
     ///
     /// # Arguments
@@ -42,15 +177,199 @@ where
     /// acc.end();
     /// ```
     pub fn add(&mut self, symbol: TSymbol) {
-        self.model.add(&self.current_sequence, Some(symbol));
-        self.current_sequence = self.model.advance_sequence(&self.current_sequence, symbol);
+        self.add_weighted(symbol, 1);
+    }
+
+    /// Adds a symbol to the current sequence, counting it as though it had
+    /// been observed `weight` times.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The next symbol in the current sequence.
+    ///
+    /// `weight` - The number of observations the symbol counts for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add_weighted("curated", 5);
+    /// acc.end_weighted(5);
+    /// acc.add("scraped");
+    /// acc.end();
+    /// ```
+    pub fn add_weighted(&mut self, symbol: TSymbol, weight: usize) {
+        if self.stopped {
+            return;
+        }
+
+        let symbol = self.model.normalize(symbol);
+        self.observe(Some(symbol), weight);
+        self.current_sequence.push(symbol);
+
+        if self.skip > 0 {
+            if self.history.len() == self.model.order() + self.skip {
+                self.history.remove(0);
+            }
+            self.history.push(symbol);
+        }
+
+        self.symbols_added += 1;
+        if self.progress_interval > 0 && self.symbols_added % self.progress_interval == 0 {
+            self.report_progress();
+        }
+    }
+
+    /// Adds several symbols, in order, to the current sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - The next symbols in the current sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add_all("the quick brown fox".split(' '));
+    /// acc.end();
+    /// ```
+    pub fn add_all<I>(&mut self, symbols: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        for symbol in symbols {
+            if self.stopped {
+                break;
+            }
+            self.add(symbol);
+        }
+    }
+
+    /// Adds a complete sequence of symbols and then indicates its end,
+    /// resetting this Accumulator for a new sequence.
+    ///
+    /// Any symbols previously added to the current sequence are treated as
+    /// its beginning.
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - The remaining symbols of the current sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.train_sequence("the quick brown fox".split(' '));
+    /// acc.train_sequence("the lazy dog".split(' '));
+    /// ```
+    pub fn train_sequence<I>(&mut self, symbols: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        self.add_all(symbols);
+        self.end();
+    }
+
+    /// Trains each line read from the specified reader as a complete
+    /// sequence, without reading the entire input into memory.
+    ///
+    /// Line endings are not included in the text passed to the tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// `reader` - The source of training text.
+    ///
+    /// `tokenize` - A function which splits a line of text into symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(2);
+    ///
+    /// let corpus = "alice\nbob\ncarol\n";
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.train_lines(corpus.as_bytes(), |line| line.chars().collect::<Vec<_>>())
+    ///     .unwrap();
+    /// ```
+    pub fn train_lines<R, F, I>(&mut self, mut reader: R, mut tokenize: F) -> std::io::Result<()>
+    where
+        R: std::io::BufRead,
+        F: FnMut(&str) -> I,
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut line = String::new();
+
+        while !self.stopped && reader.read_line(&mut line)? > 0 {
+            let text = line.trim_end_matches('\n').trim_end_matches('\r');
+            self.train_sequence(tokenize(text));
+            line.clear();
+        }
+
+        Ok(())
     }
 
     /// Indicates the end of the current sequence and resets this Accumulator
     /// for a new sequence.
     pub fn end(&mut self) {
-        self.model.add(&self.current_sequence, None);
-        self.current_sequence = crate::Sequence::empty();
+        self.end_weighted(1);
+    }
+
+    /// Indicates the end of the current sequence, counting it as though it
+    /// had been observed `weight` times, and resets this Accumulator for a new
+    /// sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `weight` - The number of observations the end counts for.
+    pub fn end_weighted(&mut self, weight: usize) {
+        if self.stopped {
+            return;
+        }
+
+        self.observe(None, weight);
+        self.current_sequence.clear();
+        self.history.clear();
+        self.sequences_ended += 1;
+    }
+
+    fn report_progress(&mut self) {
+        let progress = self.progress();
+
+        if let Some(callback) = &mut self.progress_callback {
+            if !callback(&progress) {
+                self.stopped = true;
+            }
+        }
+    }
+
+    fn observe(&mut self, next_symbol: Option<TSymbol>, weight: usize) {
+        let weight = match self.half_life {
+            Some(half_life) => {
+                if self.observations == half_life {
+                    self.model.halve_counts();
+                    self.observations = 0;
+                }
+                self.observations += 1;
+
+                let growth = (self.observations as f64 / half_life as f64).exp2();
+                weight.saturating_mul((RECENCY_BASE_WEIGHT * growth).round() as usize)
+            }
+            None => weight,
+        };
+
+        self.model
+            .add_weighted(&self.current_sequence, next_symbol, weight);
+
+        for seq in crate::Sequence::skip_grams(&self.history, self.model.order()) {
+            self.model.add_weighted(&seq, next_symbol, weight);
+        }
     }
 
     /// Predicts and returns the most probable next symbol based on previous
@@ -62,10 +381,44 @@ where
     }
 }
 
+impl<'a, TSymbol, THasher> Extend<TSymbol> for Accumulator<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Adds symbols to the current sequence, as per
+    /// [`add_all`](#method.add_all).
+    fn extend<I>(&mut self, symbols: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        self.add_all(symbols);
+    }
+}
+
+impl<'a, TSymbol, THasher> Extend<Vec<TSymbol>> for Accumulator<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Adds complete sequences, each of which is ended as per
+    /// [`train_sequence`](#method.train_sequence).
+    fn extend<I>(&mut self, sequences: I)
+    where
+        I: IntoIterator<Item = Vec<TSymbol>>,
+    {
+        for sequence in sequences {
+            self.train_sequence(sequence);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::accumulator::Accumulator;
     use crate::model::Model;
+    use crate::progress::Progress;
+    use crate::read_model;
     use crate::sequence::Sequence;
 
     #[test]
@@ -82,17 +435,208 @@ mod test {
         acc.add('e');
 
         let seq = Sequence::empty();
-        assert_eq!(model.sample(&seq, 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
         let seq = model.advance_sequence(&seq, 'a');
-        assert_eq!(model.sample(&seq, 0.0), Some(&'b'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'b'));
         let seq = model.advance_sequence(&seq, 'b');
-        assert_eq!(model.sample(&seq, 0.0), Some(&'c'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'c'));
 
         let seq = Sequence::empty();
-        assert_eq!(model.sample(&seq, 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
         let seq = model.advance_sequence(&seq, 'a');
-        assert_eq!(model.sample(&seq, 0.5), Some(&'d'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.5), Some(&'d'));
         let seq = model.advance_sequence(&seq, 'd');
-        assert_eq!(model.sample(&seq, 0.0), Some(&'e'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'e'));
+    }
+
+    #[test]
+    fn it_accumulates_all_symbols_from_an_iterator() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.add_all(vec!['a', 'b']);
+        acc.add_all("c".chars());
+        acc.end();
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
+        let seq = model.advance_sequence(&seq, 'a');
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'b'));
+        let seq = model.advance_sequence(&seq, 'b');
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'c'));
+        assert_eq!(model.end_probability(&['c']), Some(1.0));
+    }
+
+    #[test]
+    fn it_trains_and_terminates_whole_sequences() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.train_sequence(vec!['a', 'b']);
+        acc.train_sequence(vec!['c']);
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.5), Some(&'c'));
+        assert_eq!(model.end_probability(&['b']), Some(1.0));
+        assert_eq!(model.end_probability(&['c']), Some(1.0));
+    }
+
+    #[test]
+    fn it_extends_with_symbols_and_whole_sequences() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.extend("ab".chars());
+        acc.end();
+        acc.extend(vec![vec!['c', 'd'], vec!['e']]);
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.34), Some(&'c'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.67), Some(&'e'));
+        assert_eq!(model.end_probability(&['b']), Some(1.0));
+        assert_eq!(model.end_probability(&['d']), Some(1.0));
+        assert_eq!(model.end_probability(&['e']), Some(1.0));
+    }
+
+    #[test]
+    fn it_accumulates_weighted_symbols_into_model() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.add('a');
+        acc.end();
+        acc.add_weighted('b', 3);
+        acc.end_weighted(3);
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.25), Some(&'b'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.99), Some(&'b'));
+        assert_eq!(model.end_probability(&['b']), Some(1.0));
+    }
+
+    #[test]
+    fn it_trains_lines_from_a_reader() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.train_lines("ab\r\nc\n\nd".as_bytes(), |line| {
+            line.chars().collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'a'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.25), Some(&'c'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.5), None);
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.75), Some(&'d'));
+        assert_eq!(model.end_probability(&['b']), Some(1.0));
+        assert_eq!(model.end_probability(&['d']), Some(1.0));
+    }
+
+    #[test]
+    fn it_accumulates_skip_gram_contexts() {
+        let mut model = Model::empty(1);
+
+        let mut acc = Accumulator::with_skip(&mut model, 1);
+        acc.train_sequence(vec!['a', 'b', 'c']);
+
+        let seq = Sequence::empty();
+        let seq = model.advance_sequence(&seq, 'a');
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'b'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.5), Some(&'c'));
+        let seq = model.advance_sequence(&seq, 'b');
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.0), Some(&'c'));
+        assert_eq!(read_model::sample(&model, seq.symbols(), 0.5), None);
+        assert_eq!(model.end_probability(&['c']), Some(1.0));
+        assert_eq!(model.end_probability(&['b']), Some(0.5));
+    }
+
+    #[test]
+    fn it_weights_recent_observations_more_heavily() {
+        let mut model = Model::empty(0);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.set_half_life(Some(2));
+        acc.add('a');
+        acc.add('a');
+        acc.add('b');
+        acc.add('b');
+
+        let seq = Sequence::empty();
+        assert_eq!(model.predict(&seq), Some(&'b'));
+
+        let p_a = model.probability(&seq, &Some('a'));
+        assert!((p_a - 1.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn it_saturates_large_recency_weights() {
+        let mut model = Model::empty(0);
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.set_half_life(Some(2));
+        acc.add_weighted('a', usize::MAX);
+        acc.add('b');
+
+        let seq = Sequence::empty();
+        assert_eq!(model.predict(&seq), Some(&'a'));
+        assert!(model.probability(&seq, &Some('b')) < 1e-6);
+    }
+
+    #[test]
+    fn it_normalizes_symbols_before_counting() {
+        let mut model = Model::empty(1);
+        model.set_normalizer(|c: char| c.to_ascii_lowercase());
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.train_sequence("Ab".chars());
+        acc.train_sequence("aB".chars());
+
+        let seq = Sequence::empty();
+        assert_eq!(model.probability(&seq, &Some('a')), 1.0);
+        let seq = model.advance_sequence(&seq, 'a');
+        assert_eq!(model.probability(&seq, &Some('b')), 1.0);
+    }
+
+    #[test]
+    fn it_reports_progress_and_stops_when_requested() {
+        let mut model = Model::empty(1);
+        let mut reports = vec![];
+
+        let mut callback = |p: &Progress| {
+            reports.push(*p);
+            p.symbols < 4
+        };
+
+        let mut acc = Accumulator::new(&mut model);
+        acc.on_progress(2, &mut callback);
+        acc.train_lines("ab\ncd\nef\n".as_bytes(), |line| {
+            line.chars().collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        assert!(acc.is_stopped());
+        assert_eq!(acc.progress().symbols, 4);
+        assert_eq!(acc.progress().sequences, 1);
+
+        assert_eq!(
+            reports,
+            vec![
+                Progress {
+                    symbols: 2,
+                    sequences: 0,
+                    contexts: 2,
+                },
+                Progress {
+                    symbols: 4,
+                    sequences: 1,
+                    contexts: 4,
+                },
+            ]
+        );
+        assert_eq!(model.end_probability(&['d']), None);
     }
 }
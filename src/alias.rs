@@ -0,0 +1,115 @@
+// Alias tables (Vose's method) over fixed sets of counts, allowing an index to
+// be drawn in proportion to its count in O(1). The tables of any number of sets
+// are laid out one after another, so that each draws from its own range of
+// indices.
+#[derive(Clone, Default)]
+pub(crate) struct AliasTable {
+    probabilities: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl AliasTable {
+    // Appends a table over the specified counts, whose indices follow those of
+    // the tables before it
+    pub(crate) fn push<I>(&mut self, counts: I)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let start = self.probabilities.len();
+        let counts: Vec<usize> = counts.into_iter().collect();
+        let n = counts.len();
+        let total: usize = counts.iter().sum();
+
+        let mut probabilities: Vec<f64> = counts
+            .iter()
+            .map(|&c| c as f64 * n as f64 / total as f64)
+            .collect();
+        let mut aliases: Vec<usize> = (0..n).collect();
+
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| probabilities[i] < 1.0);
+
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            aliases[s] = l;
+            probabilities[l] -= 1.0 - probabilities[s];
+            if probabilities[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+
+        // Whatever remains is full, barring rounding error
+        for i in small.into_iter().chain(large) {
+            probabilities[i] = 1.0;
+        }
+
+        self.probabilities.extend(probabilities);
+        self.aliases.extend(aliases.into_iter().map(|a| start + a));
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.probabilities.len() * std::mem::size_of::<f64>()
+            + self.aliases.len() * std::mem::size_of::<usize>()
+    }
+
+    // Draws an index from the table over the specified range using a single
+    // value in the [0, 1) range, whose integer part after scaling selects a
+    // column and whose fraction selects between the column and its alias
+    pub(crate) fn sample(&self, range: std::ops::Range<usize>, sample_value: f64) -> Option<usize> {
+        if range.is_empty() {
+            return None;
+        }
+
+        let offset = crate::random::scale(sample_value, range.len());
+        let fraction = (sample_value * range.len() as f64 - offset as f64).clamp(0.0, 1.0);
+        let column = range.start + offset;
+
+        if fraction < self.probabilities[column] {
+            Some(column)
+        } else {
+            Some(self.aliases[column])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::alias::AliasTable;
+
+    #[test]
+    fn it_samples_in_proportion_to_counts() {
+        let mut table = AliasTable::default();
+        table.push(vec![1, 0, 3, 4]);
+
+        let steps = 8000;
+        let mut drawn = [0; 4];
+        for i in 0..steps {
+            drawn[table.sample(0..4, i as f64 / steps as f64).unwrap()] += 1;
+        }
+
+        assert_eq!(drawn, [1000, 0, 3000, 4000]);
+    }
+
+    #[test]
+    fn it_samples_nothing_from_no_counts() {
+        let mut table = AliasTable::default();
+        table.push(vec![]);
+        assert_eq!(table.sample(0..0, 0.5), None);
+    }
+
+    #[test]
+    fn it_samples_from_tables_laid_out_one_after_another() {
+        let mut table = AliasTable::default();
+        table.push(vec![1, 1]);
+        table.push(vec![0, 2, 6]);
+
+        let steps = 800;
+        let mut drawn = [0; 5];
+        for i in 0..steps {
+            drawn[table.sample(2..5, i as f64 / steps as f64).unwrap()] += 1;
+        }
+
+        assert_eq!(drawn, [0, 0, 0, 200, 600]);
+        assert_eq!(table.sample(0..2, 0.75), Some(1));
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+
+/// The result of observing a single symbol with an
+/// [AnomalyDetector](struct.AnomalyDetector.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Observation {
+    /// The surprisal, in bits, of the observed symbol.
+    pub surprisal: f64,
+
+    /// The mean surprisal, in bits, of the symbols in the current window,
+    /// including the observed symbol.
+    pub window_surprisal: f64,
+
+    /// Whether the surprisal of the observed symbol exceeds the threshold.
+    pub anomalous: bool,
+
+    /// Whether the window is full and its mean surprisal exceeds the
+    /// threshold.
+    pub window_anomalous: bool,
+}
+
+/// An AnomalyDetector for flagging symbols, or windows of symbols, in a stream
+/// which are unusually surprising under a [Model](struct.Model.html), such as
+/// to detect intrusions in a stream of system events.
+///
+/// Rolling statistics of the surprisal of all observed symbols are kept, so
+/// that a suitable threshold can be chosen relative to typical behaviour.
+pub struct AnomalyDetector<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    scorer: crate::Scorer<'a, TSymbol, THasher>,
+    threshold: f64,
+    window_len: usize,
+    window: VecDeque<f64>,
+    count: usize,
+    mean: f64,
+    sum_squares: f64,
+}
+
+impl<'a, TSymbol, THasher> AnomalyDetector<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates an AnomalyDetector which uses the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model of normal behaviour.
+    ///
+    /// `threshold` - The surprisal, in bits, above which a symbol or the mean
+    /// of a window is considered anomalous.
+    ///
+    /// `window_len` - The number of most recent symbols whose mean surprisal
+    /// is considered, which must be at least 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec![
+    ///     vec!["open", "read", "close"],
+    ///     vec!["open", "write", "close"],
+    /// ]);
+    ///
+    /// let mut detector = markov::AnomalyDetector::new(&model, 4.0, 2);
+    /// assert!(!detector.observe("open").anomalous);
+    /// assert!(!detector.observe("read").anomalous);
+    /// assert!(detector.observe("exec").anomalous);
+    /// ```
+    pub fn new(
+        model: &'a crate::Model<TSymbol, THasher>,
+        threshold: f64,
+        window_len: usize,
+    ) -> AnomalyDetector<'a, TSymbol, THasher> {
+        AnomalyDetector {
+            scorer: crate::Scorer::new(model),
+            threshold,
+            window_len: window_len.max(1),
+            window: VecDeque::new(),
+            count: 0,
+            mean: 0.0,
+            sum_squares: 0.0,
+        }
+    }
+
+    /// Observes the next symbol in the stream, returning its surprisal and
+    /// whether it, or the window ending with it, is anomalous.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The next observed symbol.
+    pub fn observe(&mut self, symbol: TSymbol) -> crate::Observation {
+        let surprisal = self.scorer.score(symbol);
+
+        if self.window.len() == self.window_len {
+            self.window.pop_front();
+        }
+        self.window.push_back(surprisal);
+        let window_surprisal = self.window.iter().sum::<f64>() / self.window.len() as f64;
+
+        if surprisal.is_finite() {
+            self.count += 1;
+            let delta = surprisal - self.mean;
+            self.mean += delta / self.count as f64;
+            self.sum_squares += delta * (surprisal - self.mean);
+        }
+
+        crate::Observation {
+            surprisal,
+            window_surprisal,
+            anomalous: surprisal > self.threshold,
+            window_anomalous: self.window.len() == self.window_len
+                && window_surprisal > self.threshold,
+        }
+    }
+
+    /// Ends the current sequence, so that the next symbol observed is scored
+    /// as the beginning of a new one. Rolling statistics are kept.
+    pub fn end(&mut self) {
+        self.scorer.end();
+        self.window.clear();
+    }
+
+    /// Returns the mean surprisal, in bits, of all observed symbols which the
+    /// model considered possible.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the standard deviation of the surprisal, in bits, of all
+    /// observed symbols which the model considered possible.
+    pub fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.sum_squares / (self.count - 1) as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::anomaly_detector::AnomalyDetector;
+    use crate::model::Model;
+
+    #[test]
+    fn it_flags_surprising_symbols_and_windows() {
+        let model = Model::train_from(
+            1,
+            vec![
+                "abab".chars(),
+                "abac".chars(),
+                "abab".chars(),
+                "abab".chars(),
+            ],
+        );
+
+        let mut detector = AnomalyDetector::new(&model, 1.0, 2);
+
+        let o = detector.observe('a');
+        assert_eq!(o.surprisal, 0.0);
+        assert!(!o.anomalous);
+        assert!(!o.window_anomalous);
+
+        let b = detector.observe('b').surprisal;
+        let a = detector.observe('a').surprisal;
+
+        let o = detector.observe('c');
+        assert_eq!(o.surprisal, 3.0);
+        assert_eq!(o.window_surprisal, (a + 3.0) / 2.0);
+        assert!(o.anomalous);
+        assert!(o.window_anomalous);
+
+        let o = detector.observe('z');
+        assert_eq!(o.surprisal, f64::INFINITY);
+        assert!(o.anomalous);
+
+        let mean = (b + a + 3.0) / 4.0;
+        let variance =
+            (mean.powi(2) + (b - mean).powi(2) + (a - mean).powi(2) + (3.0 - mean).powi(2)) / 3.0;
+        assert!((detector.mean() - mean).abs() < 1e-12);
+        assert!((detector.std_dev() - variance.sqrt()).abs() < 1e-12);
+
+        detector.end();
+        let o = detector.observe('a');
+        assert!(!o.window_anomalous);
+        assert_eq!(o.window_surprisal, 0.0);
+    }
+}
@@ -0,0 +1,266 @@
+// Surprisals are summed in this many independent lanes, which the compiler
+// can vectorize, unlike a single running total
+const LANES: usize = 8;
+
+/// A Scorer for measuring the surprisal of many sequences, or of very long
+/// ones, under a [Model](struct.Model.html) at high throughput.
+///
+/// The surprisal of every transition of the model, and the context which it
+/// leads to, are computed once when the BatchScorer is created. Scoring a
+/// symbol then takes a single lookup of the symbol in the current context,
+/// rather than a lookup of the whole context as a [Scorer](struct.Scorer.html)
+/// makes, and surprisals are summed in vectorized form. Contexts are only
+/// looked up in full again after a symbol which the model considers
+/// impossible. This suits anomaly-scoring
+/// pipelines where the overhead of a Scorer for each sequence dominates.
+///
+/// Surprisals are the same as those returned by a Scorer, although totals may
+/// differ from those summed one symbol at a time by rounding.
+pub struct BatchScorer<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    model: &'a crate::Model<TSymbol, THasher>,
+    // The surprisal of every transition, indexed by the ID of its context and
+    // then by the position of its symbol in the context's table
+    surprisals: Vec<Vec<f64>>,
+    // The ID of the context which follows each transition, indexed in the same
+    // way, or None where the model has no such context
+    successors: Vec<Vec<Option<usize>>>,
+}
+
+impl<'a, TSymbol, THasher> BatchScorer<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates a BatchScorer which uses the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to score sequences against.
+    pub fn new(model: &crate::Model<TSymbol, THasher>) -> BatchScorer<'_, TSymbol, THasher> {
+        let mut next = vec![];
+        let successors = model
+            .tables_by_id()
+            .iter()
+            .enumerate()
+            .map(|(id, t)| {
+                let context = model.context_symbols(id as u32).unwrap_or(&[]);
+                t.stored_symbols()
+                    .iter()
+                    .map(|s| {
+                        let s = (*s)?;
+                        next.clear();
+                        next.extend_from_slice(context);
+                        next.push(s);
+                        let start = next.len().saturating_sub(model.order());
+                        model.table_with_id(&next[start..]).map(|(id, _)| id as usize)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        BatchScorer {
+            model,
+            surprisals: model
+                .tables_by_id()
+                .iter()
+                .map(|t| t.surprisals())
+                .collect(),
+            successors,
+        }
+    }
+
+    /// Returns the surprisal (-log2 P), in bits, of each symbol of a complete
+    /// sequence given the symbols before it, followed by that of its end.
+    ///
+    /// Symbols which the model considers impossible have infinite surprisal.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let scorer = markov::BatchScorer::new(&model);
+    /// assert_eq!(scorer.surprisals(&['a', 'b']), vec![0.0, 1.0, 0.0]);
+    /// ```
+    pub fn surprisals(&self, sequence: &[TSymbol]) -> Vec<f64> {
+        let mut surprisals = vec![];
+        self.surprisals_into(sequence, &mut vec![], &mut surprisals);
+        surprisals
+    }
+
+    /// Returns the total surprisal, in bits, of a complete sequence including
+    /// its end.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    pub fn score(&self, sequence: &[TSymbol]) -> f64 {
+        sum(&self.surprisals(sequence))
+    }
+
+    /// Returns the total surprisal, in bits, of each of several complete
+    /// sequences, as per [`score`](#method.score).
+    ///
+    /// # Arguments
+    ///
+    /// `sequences` - The sequences to score.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let scorer = markov::BatchScorer::new(&model);
+    /// let scores = scorer.score_batch(&[vec!['a', 'b'], vec!['a', 'd']]);
+    /// assert_eq!(scores, vec![1.0, f64::INFINITY]);
+    /// ```
+    pub fn score_batch<S>(&self, sequences: &[S]) -> Vec<f64>
+    where
+        S: AsRef<[TSymbol]>,
+    {
+        let mut symbols = vec![];
+        let mut surprisals = vec![];
+
+        sequences
+            .iter()
+            .map(|sequence| {
+                self.surprisals_into(sequence.as_ref(), &mut symbols, &mut surprisals);
+                sum(&surprisals)
+            })
+            .collect()
+    }
+
+    /// Returns the total surprisal of each of several complete sequences, as
+    /// per [`score_batch`](#method.score_batch), scoring them on multiple
+    /// threads.
+    ///
+    /// # Arguments
+    ///
+    /// `sequences` - The sequences to score.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    /// let sequences = vec![vec!['a', 'b'], vec!['a', 'c'], vec!['b']];
+    ///
+    /// let scorer = markov::BatchScorer::new(&model);
+    /// assert_eq!(scorer.score_batch_par(&sequences), scorer.score_batch(&sequences));
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn score_batch_par<S>(&self, sequences: &[S]) -> Vec<f64>
+    where
+        S: AsRef<[TSymbol]> + Sync,
+        TSymbol: Send + Sync,
+        THasher: Sync,
+    {
+        use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+        sequences
+            .par_iter()
+            .map_init(
+                || (vec![], vec![]),
+                |(symbols, surprisals), sequence| {
+                    self.surprisals_into(sequence.as_ref(), symbols, surprisals);
+                    sum(surprisals)
+                },
+            )
+            .collect()
+    }
+
+    // Normalizes a sequence into `symbols` and writes the surprisal of each of
+    // its symbols and its end into `surprisals`, reusing both buffers
+    fn surprisals_into(
+        &self,
+        sequence: &[TSymbol],
+        symbols: &mut Vec<TSymbol>,
+        surprisals: &mut Vec<f64>,
+    ) {
+        symbols.clear();
+        symbols.extend(sequence.iter().map(|s| self.model.normalize(*s)));
+
+        let order = self.model.order();
+        let tables = self.model.tables_by_id();
+        let mut context = None;
+        surprisals.clear();
+        surprisals.extend((0..=symbols.len()).map(|i| {
+            // After an impossible symbol, the context is looked up in full
+            let id = context.or_else(|| {
+                let context = &symbols[i.saturating_sub(order)..i];
+                self.model.table_with_id(context).map(|(id, _)| id as usize)
+            });
+            let transition = id.and_then(|id| {
+                let index = tables[id].index_of(&symbols.get(i).copied())?;
+                Some((id, index))
+            });
+
+            context = transition.and_then(|(id, index)| self.successors[id][index]);
+            transition.map_or(f64::INFINITY, |(id, index)| self.surprisals[id][index])
+        }));
+    }
+}
+
+fn sum(values: &[f64]) -> f64 {
+    let mut lanes = [0.0; LANES];
+
+    let chunks = values.chunks_exact(LANES);
+    let remainder: f64 = chunks.remainder().iter().sum();
+    for chunk in chunks {
+        for (lane, value) in lanes.iter_mut().zip(chunk) {
+            *lane += value;
+        }
+    }
+
+    lanes.iter().sum::<f64>() + remainder
+}
+
+#[cfg(test)]
+mod test {
+    use crate::batch_scorer::BatchScorer;
+    use crate::model::Model;
+    use crate::scorer::Scorer;
+
+    #[test]
+    fn it_matches_a_scorer() {
+        let corpus = vec!["abcabd".chars(), "abdcab".chars(), "cabbad".chars()];
+        let model = Model::train_from(2, corpus);
+        let batch = BatchScorer::new(&model);
+
+        let sequences = vec![
+            "abcabdcab".chars().collect::<Vec<_>>(),
+            "cab".chars().collect(),
+            "abx".chars().collect(),
+            "abxcabdca".chars().collect(),
+            vec![],
+        ];
+        for sequence in &sequences {
+            let mut scorer = Scorer::new(&model);
+            let mut expected: Vec<f64> = sequence.iter().map(|s| scorer.score(*s)).collect();
+            expected.push(scorer.end());
+
+            assert_eq!(batch.surprisals(sequence), expected);
+        }
+
+        let scores = batch.score_batch(&sequences);
+        assert!((scores[0] - batch.surprisals(&sequences[0]).iter().sum::<f64>()).abs() < 1e-9);
+        assert_eq!(scores[2], f64::INFINITY);
+        assert_eq!(scores[4], batch.score(&[]));
+    }
+
+    #[test]
+    fn it_normalizes_scored_symbols() {
+        let mut model = Model::empty(1);
+        model.set_normalizer(|c: char| c.to_ascii_lowercase());
+        crate::Accumulator::new(&mut model).train_sequence("ab".chars());
+
+        let batch = BatchScorer::new(&model);
+        assert_eq!(batch.surprisals(&['A', 'B']), vec![0.0, 0.0, 0.0]);
+    }
+}
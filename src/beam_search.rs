@@ -0,0 +1,451 @@
+/// A BeamSearch for finding the most probable whole sequences based on a
+/// [Model](struct.Model.html).
+///
+/// Unlike a [Predictor](struct.Predictor.html), which greedily commits to the
+/// most probable next symbol at each step, a beam search keeps track of
+/// several partial sequences at once, so that a locally probable but globally
+/// unlikely path does not crowd out better alternatives.
+///
+/// BeamSearches do not modify the underlying model.
+pub struct BeamSearch<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    model: &'a crate::Model<TSymbol, THasher>,
+    current_sequence: crate::Sequence<TSymbol>,
+    width: usize,
+}
+
+impl<'a, TSymbol, THasher> BeamSearch<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates a BeamSearch which uses the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to base the search on.
+    ///
+    /// `width` - The number of partial sequences to keep at each step, which
+    /// is also the maximum number of sequences found.
+    pub fn new(
+        model: &crate::Model<TSymbol, THasher>,
+        width: usize,
+    ) -> BeamSearch<'_, TSymbol, THasher> {
+        BeamSearch {
+            model,
+            current_sequence: crate::Sequence::empty(),
+            width,
+        }
+    }
+
+    /// Resets this BeamSearch so that sequences found will start from the
+    /// beginning of a sequence.
+    pub fn end(&mut self) {
+        self.current_sequence = crate::Sequence::empty();
+    }
+
+    /// Specifies a prior symbol upon which found sequences will be based.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The most recent past symbol on which to base the search.
+    pub fn given(&mut self, symbol: TSymbol) {
+        let symbol = self.model.normalize(symbol);
+        self.current_sequence = self.model.advance_sequence(&self.current_sequence, symbol);
+    }
+
+    /// Searches for the most probable complete sequences following the
+    /// symbols specified via [`given`](#method.given).
+    ///
+    /// Returns up to `width` sequences, each paired with its joint natural
+    /// log-probability (including that of the sequence ending), ordered from
+    /// most to least probable. Sequences which do not end within `max_len`
+    /// symbols are not returned.
+    ///
+    /// # Arguments
+    ///
+    /// `max_len` - The maximum length of the sequences to search for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(
+    ///     1,
+    ///     vec!["xa".chars(), "xb".chars(), "xc".chars(), "y".chars(), "y".chars()],
+    /// );
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// assert_eq!(pre.next(), Some(&'x'));
+    ///
+    /// let search = markov::BeamSearch::new(&model, 2);
+    /// let results = search.search(10);
+    ///
+    /// assert_eq!(results[0].0, vec!['y']);
+    /// assert!((results[0].1 - 0.4f64.ln()).abs() < 1e-12);
+    /// ```
+    pub fn search(&self, max_len: usize) -> Vec<(Vec<TSymbol>, f64)> {
+        let mut beams = vec![(self.current_sequence.clone(), vec![], 0.0)];
+        let mut completed: Vec<(Vec<TSymbol>, f64)> = vec![];
+
+        for len in 0..=max_len {
+            let mut candidates = vec![];
+
+            for (sequence, symbols, log_p) in &beams {
+                let distribution = match self.model.distribution(sequence) {
+                    Some(d) => d,
+                    None => continue,
+                };
+
+                for (symbol, p) in distribution.probabilities() {
+                    let log_p = log_p + p.ln();
+
+                    match symbol {
+                        None => completed.push((symbols.clone(), log_p)),
+                        Some(s) if len < max_len => {
+                            let mut next_symbols = symbols.clone();
+                            next_symbols.push(s);
+                            candidates.push((
+                                self.model.advance_sequence(sequence, s),
+                                next_symbols,
+                                log_p,
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            sort_descending(&mut completed, |c| c.1);
+            completed.truncate(self.width);
+
+            sort_descending(&mut candidates, |c| c.2);
+            candidates.truncate(self.width);
+
+            // Probabilities only shrink as sequences grow, so no remaining
+            // candidate can displace a full set of completed sequences
+            let beaten = completed.len() == self.width
+                && candidates
+                    .first()
+                    .map_or(true, |c| c.2 <= completed[completed.len() - 1].1);
+
+            if candidates.is_empty() || beaten {
+                break;
+            }
+            beams = candidates;
+        }
+
+        completed
+    }
+
+    /// Finds the `n` most probable distinct complete sequences following the
+    /// symbols specified via [`given`](#method.given).
+    ///
+    /// Unlike [`search`](#method.search), which may miss sequences whose
+    /// prefixes fall outside the beam, this explores partial sequences in
+    /// best-first order and so always returns the true `n` most probable
+    /// sequences. This is more expensive, and is not limited by the beam
+    /// width.
+    ///
+    /// Returns up to `n` sequences, each paired with its joint natural
+    /// log-probability (including that of the sequence ending), ordered from
+    /// most to least probable.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The maximum number of sequences to return.
+    ///
+    /// `max_len` - The maximum length of the sequences to search for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ann".chars(), "anna".chars(), "bo".chars()]);
+    ///
+    /// let search = markov::BeamSearch::new(&model, 1);
+    /// let names: Vec<String> = search
+    ///     .n_best(3, 10)
+    ///     .into_iter()
+    ///     .map(|(name, _)| name.into_iter().collect())
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec!["bo", "a", "an"]);
+    /// ```
+    pub fn n_best(&self, n: usize, max_len: usize) -> Vec<(Vec<TSymbol>, f64)> {
+        self.best_first(&[], n, max_len)
+    }
+
+    /// Finds the `n` most probable ways to fill in the middle of a sequence
+    /// which begins with the symbols specified via [`given`](#method.given)
+    /// and ends with the specified suffix, so that the generated middle
+    /// connects plausibly to both ends.
+    ///
+    /// Like [`n_best`](#method.n_best), partial sequences are explored in
+    /// best-first order, with each candidate middle scored by its own
+    /// probability together with that of the suffix (and the end of the
+    /// sequence) following it.
+    ///
+    /// Returns up to `n` middles, each paired with the joint natural
+    /// log-probability of the middle, the suffix, and the end of the
+    /// sequence, ordered from most to least probable.
+    ///
+    /// # Arguments
+    ///
+    /// `suffix` - The symbols with which the sequence must end.
+    ///
+    /// `n` - The maximum number of middles to return.
+    ///
+    /// `max_len` - The maximum length of the middles to search for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(
+    ///     1,
+    ///     vec!["the cat sat down".split(' '), "the dog ran away".split(' ')],
+    /// );
+    ///
+    /// let mut search = markov::BeamSearch::new(&model, 1);
+    /// search.given("the");
+    ///
+    /// let results = search.fill_middle(&["away"], 1, 5);
+    /// assert_eq!(results[0].0, vec!["dog", "ran"]);
+    /// ```
+    pub fn fill_middle(
+        &self,
+        suffix: &[TSymbol],
+        n: usize,
+        max_len: usize,
+    ) -> Vec<(Vec<TSymbol>, f64)> {
+        let suffix: Vec<TSymbol> = suffix.iter().map(|s| self.model.normalize(*s)).collect();
+        self.best_first(&suffix, n, max_len)
+    }
+
+    fn best_first(&self, suffix: &[TSymbol], n: usize, max_len: usize) -> Vec<(Vec<TSymbol>, f64)> {
+        let mut results = vec![];
+        let mut queue = std::collections::BinaryHeap::new();
+        let mut pushed = 0;
+
+        queue.push(Partial {
+            log_p: 0.0,
+            order: pushed,
+            sequence: Some(self.current_sequence.clone()),
+            symbols: vec![],
+        });
+
+        while results.len() < n {
+            let partial = match queue.pop() {
+                Some(p) => p,
+                None => break,
+            };
+
+            let sequence = match partial.sequence {
+                Some(s) => s,
+                None => {
+                    results.push((partial.symbols, partial.log_p));
+                    continue;
+                }
+            };
+
+            let p_ending = self.ending_probability(&sequence, suffix);
+            if p_ending > 0.0 {
+                pushed += 1;
+                queue.push(Partial {
+                    log_p: partial.log_p + p_ending.ln(),
+                    order: pushed,
+                    sequence: None,
+                    symbols: partial.symbols.clone(),
+                });
+            }
+
+            if partial.symbols.len() >= max_len {
+                continue;
+            }
+
+            let distribution = match self.model.distribution(&sequence) {
+                Some(d) => d,
+                None => continue,
+            };
+
+            for (symbol, p) in distribution.probabilities() {
+                if let Some(s) = symbol {
+                    let mut symbols = partial.symbols.clone();
+                    symbols.push(s);
+
+                    pushed += 1;
+                    queue.push(Partial {
+                        log_p: partial.log_p + p.ln(),
+                        order: pushed,
+                        sequence: Some(self.model.advance_sequence(&sequence, s)),
+                        symbols,
+                    });
+                }
+            }
+        }
+
+        results
+    }
+
+    // Returns the probability of the suffix followed by the end of the
+    // sequence, given the specified context.
+    fn ending_probability(&self, sequence: &crate::Sequence<TSymbol>, suffix: &[TSymbol]) -> f64 {
+        let mut sequence = sequence.clone();
+        let mut p = 1.0;
+
+        for symbol in suffix {
+            p *= self.model.probability(&sequence, &Some(*symbol));
+            if p == 0.0 {
+                return 0.0;
+            }
+            sequence = self.model.advance_sequence(&sequence, *symbol);
+        }
+
+        p * self.model.probability(&sequence, &None)
+    }
+}
+
+// A partial sequence explored by a best-first search, which is complete once
+// it has no further context to extend.
+struct Partial<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    log_p: f64,
+    order: usize,
+    sequence: Option<crate::Sequence<TSymbol>>,
+    symbols: Vec<TSymbol>,
+}
+
+impl<TSymbol> Ord for Partial<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn cmp(&self, other: &Partial<TSymbol>) -> std::cmp::Ordering {
+        // Most probable first, with ties broken in favour of the earliest found
+        self.log_p
+            .total_cmp(&other.log_p)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl<TSymbol> PartialOrd for Partial<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn partial_cmp(&self, other: &Partial<TSymbol>) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<TSymbol> PartialEq for Partial<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn eq(&self, other: &Partial<TSymbol>) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<TSymbol> Eq for Partial<TSymbol> where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq
+{
+}
+
+fn sort_descending<T, F>(items: &mut [T], key: F)
+where
+    F: Fn(&T) -> f64,
+{
+    items.sort_by(|a, b| {
+        key(b)
+            .partial_cmp(&key(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::beam_search::BeamSearch;
+    use crate::model::Model;
+
+    fn model() -> Model<char> {
+        Model::train_from(
+            1,
+            vec![
+                "xa".chars(),
+                "xb".chars(),
+                "xc".chars(),
+                "y".chars(),
+                "y".chars(),
+            ],
+        )
+    }
+
+    #[test]
+    fn it_finds_the_most_probable_whole_sequences() {
+        let model = model();
+
+        let results = BeamSearch::new(&model, 2).search(10);
+        let sequences: Vec<_> = results.iter().map(|r| r.0.clone()).collect();
+
+        assert_eq!(sequences, vec![vec!['y'], vec!['x', 'a']]);
+        assert!((results[0].1 - 0.4f64.ln()).abs() < 1e-12);
+        assert!((results[1].1 - 0.2f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_searches_from_given_symbols() {
+        let model = model();
+
+        let mut search = BeamSearch::new(&model, 5);
+        search.given('x');
+        let sequences: Vec<_> = search.search(10).into_iter().map(|r| r.0).collect();
+        assert_eq!(sequences, vec![vec!['a'], vec!['b'], vec!['c']]);
+
+        search.end();
+        search.given('z');
+        assert!(search.search(10).is_empty());
+    }
+
+    #[test]
+    fn it_excludes_sequences_exceeding_the_maximum_length() {
+        let model = model();
+
+        let sequences: Vec<_> = BeamSearch::new(&model, 5)
+            .search(1)
+            .into_iter()
+            .map(|r| r.0)
+            .collect();
+        assert_eq!(sequences, vec![vec!['y']]);
+    }
+
+    #[test]
+    fn it_finds_the_n_best_distinct_sequences() {
+        let model = model();
+
+        let results = BeamSearch::new(&model, 1).n_best(3, 10);
+        let sequences: Vec<_> = results.iter().map(|r| r.0.clone()).collect();
+
+        assert_eq!(sequences, vec![vec!['y'], vec!['x', 'a'], vec!['x', 'b']]);
+        assert!((results[2].1 - 0.2f64.ln()).abs() < 1e-12);
+
+        assert_eq!(BeamSearch::new(&model, 1).n_best(10, 10).len(), 4);
+        assert_eq!(BeamSearch::new(&model, 1).n_best(10, 1).len(), 1);
+    }
+
+    #[test]
+    fn it_fills_in_the_middle_between_a_prefix_and_suffix() {
+        let model = Model::train_from(1, vec!["abcd".chars(), "abxd".chars(), "ayz".chars()]);
+
+        let mut search = BeamSearch::new(&model, 1);
+        search.given('a');
+
+        let results = search.fill_middle(&['d'], 5, 10);
+        let middles: Vec<_> = results.iter().map(|r| r.0.clone()).collect();
+        assert_eq!(middles, vec![vec!['b', 'c'], vec!['b', 'x']]);
+        assert!((results[0].1 - (2.0f64 / 3.0 * 0.5).ln()).abs() < 1e-12);
+
+        assert!(search.fill_middle(&['d'], 5, 1).is_empty());
+        assert!(search.fill_middle(&['q'], 5, 10).is_empty());
+    }
+}
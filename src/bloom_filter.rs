@@ -0,0 +1,94 @@
+// A Bloom filter over precomputed 64-bit hashes, sized for a number of values
+// and false positive rate. Each value sets several bits, found by double
+// hashing from the two halves of its hash.
+#[derive(Clone)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    hash_count: u32,
+    capacity: usize,
+    len: usize,
+    false_positive_rate: f64,
+}
+
+impl BloomFilter {
+    pub(crate) fn with_capacity(capacity: usize, false_positive_rate: f64) -> BloomFilter {
+        let capacity = capacity.max(64);
+        let ln2 = std::f64::consts::LN_2;
+
+        let bit_count = (-(capacity as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil();
+        let words = (bit_count as usize).div_ceil(64);
+        let hash_count = (words as f64 * 64.0 / capacity as f64 * ln2).round();
+
+        BloomFilter {
+            bits: vec![0; words.max(1)],
+            hash_count: (hash_count as u32).clamp(1, 16),
+            capacity,
+            len: 0,
+            false_positive_rate,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, hash: u64) {
+        for bit in self.bit_indices(hash) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+
+    pub(crate) fn may_contain(&self, hash: u64) -> bool {
+        self.bit_indices(hash)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    // Whether more values have been inserted than the filter was sized for,
+    // such that its false positive rate is no longer assured
+    pub(crate) fn is_full(&self) -> bool {
+        self.len >= self.capacity
+    }
+
+    pub(crate) fn false_positive_rate(&self) -> f64 {
+        self.false_positive_rate
+    }
+
+    fn bit_indices(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let bit_count = self.bits.len() as u64 * 64;
+        let step = hash.rotate_left(32) | 1;
+
+        (0..self.hash_count as u64)
+            .map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) % bit_count) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bloom_filter::BloomFilter;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn it_never_rejects_inserted_values() {
+        let hasher = std::collections::hash_map::RandomState::new();
+        let mut f = BloomFilter::with_capacity(1000, 0.01);
+
+        for i in 0..1000 {
+            f.insert(hasher.hash_one(i));
+        }
+
+        assert!((0..1000).all(|i| f.may_contain(hasher.hash_one(i))));
+        assert!(f.is_full());
+    }
+
+    #[test]
+    fn it_rejects_most_other_values() {
+        let hasher = std::collections::hash_map::RandomState::new();
+        let mut f = BloomFilter::with_capacity(1000, 0.01);
+
+        for i in 0..1000 {
+            f.insert(hasher.hash_one(i));
+        }
+
+        let false_positives = (1000..11000)
+            .filter(|i| f.may_contain(hasher.hash_one(i)))
+            .count();
+        assert!(false_positives < 300);
+    }
+}
@@ -0,0 +1,140 @@
+/// A Classifier for labelling sequences according to which of several
+/// [Models](struct.Model.html) most likely produced them, such as to identify
+/// the language of a text using character models trained on each language.
+pub struct Classifier<'a, TLabel, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    models: Vec<(TLabel, &'a crate::Model<TSymbol, THasher>)>,
+    unseen: crate::Unseen,
+}
+
+impl<'a, TLabel, TSymbol, THasher> Classifier<'a, TLabel, TSymbol, THasher>
+where
+    TLabel: std::clone::Clone,
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates a Classifier with no labelled models.
+    ///
+    /// # Arguments
+    ///
+    /// `unseen` - How to treat symbols to which a model assigns no
+    /// probability. [`Unseen::Floor`](enum.Unseen.html#variant.Floor) is
+    /// usually appropriate, as otherwise a single unseen symbol rules a model
+    /// out entirely.
+    pub fn new(unseen: crate::Unseen) -> Classifier<'a, TLabel, TSymbol, THasher> {
+        Classifier {
+            models: vec![],
+            unseen,
+        }
+    }
+
+    /// Adds a model for the specified label.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The label assigned to sequences best explained by the model.
+    ///
+    /// `model` - The Markov model trained on sequences with this label.
+    pub fn add(&mut self, label: TLabel, model: &'a crate::Model<TSymbol, THasher>) {
+        self.models.push((label, model));
+    }
+
+    /// Returns the log-likelihood, in bits, of the specified sequence
+    /// (including its end) under each labelled model, in the order in which
+    /// the models were added.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The sequence to score.
+    pub fn scores(&self, sequence: &[TSymbol]) -> Vec<(TLabel, f64)> {
+        self.models
+            .iter()
+            .map(|(label, model)| {
+                let evaluation = model.evaluate(vec![sequence.iter().copied()], self.unseen);
+                (label.clone(), -evaluation.total_bits)
+            })
+            .collect()
+    }
+
+    /// Returns the label of the model under which the specified sequence is
+    /// most likely, along with the log-odds, in bits, of that label against
+    /// the next most likely one. The log-odds are infinite if only one model
+    /// was added.
+    ///
+    /// `None` is returned if no models were added, or if every model considers
+    /// the sequence impossible.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The sequence to classify.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let english = markov::Model::train_from(2, vec!["the cat sat on the mat".chars()]);
+    /// let german = markov::Model::train_from(2, vec!["die katze sitzt auf der matte".chars()]);
+    ///
+    /// let mut classifier = markov::Classifier::new(markov::Unseen::Floor(0.001));
+    /// classifier.add("en", &english);
+    /// classifier.add("de", &german);
+    ///
+    /// let text: Vec<char> = "the mat".chars().collect();
+    /// let (label, log_odds) = classifier.classify(&text).unwrap();
+    /// assert_eq!(label, "en");
+    /// assert!(log_odds > 0.0);
+    /// ```
+    pub fn classify(&self, sequence: &[TSymbol]) -> Option<(TLabel, f64)> {
+        let mut scores = self.scores(sequence);
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut scores = scores.into_iter();
+        let (label, best) = scores.next()?;
+        if best == f64::NEG_INFINITY {
+            return None;
+        }
+
+        let log_odds = match scores.next() {
+            Some((_, second)) => best - second,
+            None => f64::INFINITY,
+        };
+        Some((label, log_odds))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::classifier::Classifier;
+    use crate::evaluation::Unseen;
+    use crate::model::Model;
+
+    #[test]
+    fn it_classifies_by_most_likely_model() {
+        let a = Model::train_from(1, vec!["ab".chars(), "ab".chars()]);
+        let b = Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+
+        let mut classifier = Classifier::new(Unseen::Skip);
+        assert_eq!(classifier.classify(&['a', 'b']), None);
+
+        classifier.add("a", &a);
+        assert_eq!(classifier.classify(&['a', 'b']), Some(("a", f64::INFINITY)));
+        assert_eq!(classifier.classify(&['a', 'c']), Some(("a", f64::INFINITY)));
+
+        classifier.add("b", &b);
+        assert_eq!(
+            classifier.scores(&['a', 'b']),
+            vec![("a", 0.0), ("b", -1.0)]
+        );
+        assert_eq!(classifier.classify(&['a', 'b']), Some(("a", 1.0)));
+    }
+
+    #[test]
+    fn it_declines_to_classify_impossible_sequences() {
+        let a = Model::train_from(1, vec!["ab".chars()]);
+
+        let mut classifier = Classifier::new(Unseen::Floor(0.0));
+        classifier.add("a", &a);
+        assert_eq!(classifier.classify(&['x']), None);
+    }
+}
@@ -0,0 +1,196 @@
+/// An accumulator which many threads can use at once to train one logical
+/// [Model](struct.Model.html).
+///
+/// Contexts are sharded by their hash across a number of models, each behind
+/// its own lock, so that threads training different contexts rarely contend.
+/// As no context is held by more than one shard, the shards are consolidated
+/// into a single model by moving their tables into it, without recounting.
+pub struct ConcurrentAccumulator<TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    order: usize,
+    shards: Vec<std::sync::Mutex<crate::Model<TSymbol, THasher>>>,
+    hasher: THasher,
+}
+
+impl<TSymbol> ConcurrentAccumulator<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Creates a ConcurrentAccumulator for training a new model.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    ///
+    /// `shard_count` - The number of shards to divide contexts between. A few
+    /// times the number of training threads keeps contention low.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shard count is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let acc = markov::ConcurrentAccumulator::new(1, 16);
+    /// let corpus = vec!["the quick brown fox", "the lazy dog"];
+    ///
+    /// std::thread::scope(|s| {
+    ///     for line in &corpus {
+    ///         let acc = &acc;
+    ///         s.spawn(move || acc.train_sequence(line.split(' ')));
+    ///     }
+    /// });
+    ///
+    /// let model = acc.into_model();
+    /// assert_eq!(model.end_probability(&["fox"]), Some(1.0));
+    /// ```
+    pub fn new(order: usize, shard_count: usize) -> ConcurrentAccumulator<TSymbol> {
+        ConcurrentAccumulator::with_hasher(order, shard_count, Default::default())
+    }
+}
+
+impl<TSymbol, THasher> ConcurrentAccumulator<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates a ConcurrentAccumulator for training a new model which uses
+    /// the specified hasher, as per
+    /// [`Model::with_hasher`](struct.Model.html#method.with_hasher).
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model.
+    ///
+    /// `shard_count` - The number of shards to divide contexts between, as
+    /// per [`new`](#method.new).
+    ///
+    /// `hasher` - The hasher with which contexts and symbols are hashed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shard count is zero.
+    pub fn with_hasher(
+        order: usize,
+        shard_count: usize,
+        hasher: THasher,
+    ) -> ConcurrentAccumulator<TSymbol, THasher> {
+        assert!(shard_count > 0, "at least one shard is required");
+
+        ConcurrentAccumulator {
+            order,
+            shards: (0..shard_count)
+                .map(|_| std::sync::Mutex::new(crate::Model::with_hasher(order, hasher.clone())))
+                .collect(),
+            hasher,
+        }
+    }
+
+    /// Trains the model on a complete sequence, including its end, as per
+    /// [`Accumulator::train_sequence`](struct.Accumulator.html#method.train_sequence).
+    ///
+    /// Each shard touched by the sequence is locked only once, so sequences
+    /// may be trained from any number of threads at once.
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - The symbols of the sequence.
+    pub fn train_sequence<I>(&self, symbols: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut pending: Vec<Vec<(crate::Sequence<TSymbol>, Option<TSymbol>)>> =
+            (0..self.shards.len()).map(|_| vec![]).collect();
+
+        let mut seq = crate::Sequence::empty();
+        for symbol in symbols {
+            let next_seq = seq.with_next(symbol, self.order);
+            pending[self.shard_of(&seq)].push((seq, Some(symbol)));
+            seq = next_seq;
+        }
+        pending[self.shard_of(&seq)].push((seq, None));
+
+        for (shard, observations) in self.shards.iter().zip(pending) {
+            if observations.is_empty() {
+                continue;
+            }
+
+            // A panic while training leaves counts which are merely
+            // incomplete, so a poisoned shard is still usable
+            let mut model = shard.lock().unwrap_or_else(|e| e.into_inner());
+            for (seq, next_symbol) in &observations {
+                model.add_weighted(seq, *next_symbol, 1);
+            }
+        }
+    }
+
+    /// Consolidates the shards into a single model, once training is done.
+    pub fn into_model(self) -> crate::Model<TSymbol, THasher> {
+        let mut shards = self
+            .shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap_or_else(|e| e.into_inner()));
+
+        let mut model = shards.next().unwrap();
+        for shard in shards {
+            model.absorb(shard);
+        }
+        model
+    }
+
+    fn shard_of(&self, seq: &crate::Sequence<TSymbol>) -> usize {
+        (self.hasher.hash_one(seq.symbols()) % self.shards.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::concurrent_accumulator::ConcurrentAccumulator;
+    use crate::model::Model;
+
+    #[test]
+    fn it_trains_from_many_threads_at_once() {
+        let corpus: Vec<String> = (0..200).map(|i| format!("seq{}", i % 37)).collect();
+
+        let acc = ConcurrentAccumulator::new(2, 4);
+        std::thread::scope(|s| {
+            for chunk in corpus.chunks(25) {
+                let acc = &acc;
+                s.spawn(move || {
+                    for line in chunk {
+                        acc.train_sequence(line.chars());
+                    }
+                });
+            }
+        });
+        let m = acc.into_model();
+
+        let expected = Model::train_from(2, corpus.iter().map(|l| l.chars()));
+        assert_eq!(m.context_count(), expected.context_count());
+        for (seq, t) in expected.tables() {
+            let actual = m.table(seq).unwrap();
+            assert_eq!(actual.total(), t.total());
+            for (symbol, frequency) in t.iter() {
+                assert_eq!(actual.frequency_of(symbol), frequency);
+            }
+        }
+    }
+
+    #[test]
+    fn it_keeps_each_context_in_one_shard() {
+        let acc = ConcurrentAccumulator::new(1, 3);
+        acc.train_sequence("abcabc".chars());
+        acc.train_sequence("cab".chars());
+
+        let total: usize = acc
+            .shards
+            .iter()
+            .map(|s| s.lock().unwrap().context_count())
+            .sum();
+        assert_eq!(total, acc.into_model().context_count());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+type Context<'a, TSymbol, THasher> = (
+    &'a crate::Sequence<TSymbol>,
+    &'a crate::Table<TSymbol, THasher>,
+);
+
+/// An index over the contexts of a [Model](struct.Model.html) which supports
+/// queries for contexts containing wildcard positions, such as
+/// `("the", *, "fox")`, aggregating the observations of every matching
+/// context.
+///
+/// The index reflects the model at the time it was built, and borrows it so
+/// that the model cannot be trained further while the index exists.
+pub struct ContextIndex<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    model: &'a crate::Model<TSymbol, THasher>,
+    by_position: HashMap<(usize, TSymbol), Vec<Context<'a, TSymbol, THasher>>>,
+    by_len: HashMap<usize, Vec<Context<'a, TSymbol, THasher>>>,
+}
+
+impl<'a, TSymbol, THasher> ContextIndex<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Builds an index over the contexts of the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to index.
+    pub fn new(model: &'a crate::Model<TSymbol, THasher>) -> ContextIndex<'a, TSymbol, THasher> {
+        let mut by_position: HashMap<_, Vec<_>> = HashMap::new();
+        let mut by_len: HashMap<_, Vec<_>> = HashMap::new();
+
+        for (seq, table) in model.tables() {
+            for (i, s) in seq.symbols().iter().enumerate() {
+                by_position.entry((i, *s)).or_default().push((seq, table));
+            }
+            by_len
+                .entry(seq.symbols().len())
+                .or_default()
+                .push((seq, table));
+        }
+
+        ContextIndex {
+            model,
+            by_position,
+            by_len,
+        }
+    }
+
+    /// Returns the probabilities of all symbols observed following any context
+    /// which matches the specified pattern, ordered from most to least
+    /// probable. Observations from every matching context are pooled, so that
+    /// more frequently observed contexts contribute more.
+    ///
+    /// The end of a sequence is represented by `None`. An empty distribution
+    /// is returned if no context matches.
+    ///
+    /// # Arguments
+    ///
+    /// `pattern` - The context to match, oldest symbol first, where `None`
+    /// matches any symbol. Only contexts of the same length as the pattern are
+    /// matched, so patterns shorter than the model's order match only the
+    /// beginnings of sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(3, vec![
+    ///     "the quick fox jumps".split(' '),
+    ///     "the lazy fox sleeps".split(' '),
+    ///     "the lazy fox sleeps".split(' '),
+    ///     "a quick fox runs".split(' '),
+    /// ]);
+    ///
+    /// let index = markov::ContextIndex::new(&model);
+    /// let distribution = index.distribution(&[Some("the"), None, Some("fox")]);
+    /// assert_eq!(distribution, vec![(Some("sleeps"), 2.0 / 3.0), (Some("jumps"), 1.0 / 3.0)]);
+    /// ```
+    pub fn distribution(&self, pattern: &[Option<TSymbol>]) -> Vec<(Option<TSymbol>, f64)> {
+        let pattern: Vec<_> = pattern
+            .iter()
+            .map(|s| s.map(|s| self.model.normalize(s)))
+            .collect();
+
+        // Scan the fewest candidates, using the rarest fixed symbol if any
+        let candidates = pattern
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.map(|s| self.by_position.get(&(i, s))))
+            .map(|c| c.map_or(&[][..], |c| &c[..]))
+            .min_by_key(|c| c.len())
+            .or_else(|| self.by_len.get(&pattern.len()).map(|c| &c[..]))
+            .unwrap_or(&[]);
+
+        let parts: Vec<_> = candidates
+            .iter()
+            .filter(|(seq, _)| Self::matches(&pattern, seq.symbols()))
+            .map(|(_, table)| (crate::Distribution::from_table(table), table.total() as f64))
+            .collect();
+
+        if parts.is_empty() {
+            return vec![];
+        }
+
+        let mut distribution: Vec<_> = crate::Distribution::mixture(parts)
+            .probabilities()
+            .collect();
+        distribution.sort_by(|a, b| b.1.total_cmp(&a.1));
+        distribution
+    }
+
+    fn matches(pattern: &[Option<TSymbol>], symbols: &[TSymbol]) -> bool {
+        pattern.len() == symbols.len()
+            && pattern
+                .iter()
+                .zip(symbols)
+                .all(|(p, s)| p.map_or(true, |p| p == *s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::context_index::ContextIndex;
+    use crate::model::Model;
+
+    #[test]
+    fn it_aggregates_over_contexts_matching_wildcards() {
+        let model = Model::train_from(
+            2,
+            vec!["abx".chars(), "cbx".chars(), "cby".chars(), "cdz".chars()],
+        );
+        let index = ContextIndex::new(&model);
+
+        assert_eq!(
+            index.distribution(&[Some('a'), Some('b')]),
+            vec![(Some('x'), 1.0)]
+        );
+        assert_eq!(
+            index.distribution(&[None, Some('b')]),
+            vec![(Some('x'), 2.0 / 3.0), (Some('y'), 1.0 / 3.0)]
+        );
+        let distribution = index.distribution(&[Some('c'), None]);
+        assert_eq!(distribution.len(), 3);
+        assert!(distribution.iter().all(|&(_, p)| p == 1.0 / 3.0));
+
+        assert_eq!(index.distribution(&[None, None])[0], (None, 0.5));
+        assert_eq!(index.distribution(&[Some('a')]), vec![(Some('b'), 1.0)]);
+        assert!(index.distribution(&[Some('q'), None]).is_empty());
+        assert!(index.distribution(&[None, None, None]).is_empty());
+    }
+}
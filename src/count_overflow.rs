@@ -0,0 +1,15 @@
+/// Strategies for handling a symbol's count exceeding the largest count a
+/// [Model](struct.Model.html) can hold.
+///
+/// Counts are held as 32-bit integers to save memory, unless the
+/// `wide-counts` feature is enabled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CountOverflow {
+    /// The count stays at the largest value, and further observations of the
+    /// symbol in that context are discarded.
+    Saturate,
+
+    /// All counts in the affected context are halved (discarding any which
+    /// reach zero) until the new count fits, preserving their proportions.
+    Halve,
+}
@@ -0,0 +1,12 @@
+/// Strategies for handling a [Generator](struct.Generator.html) which repeats
+/// the same run of symbols more often than its cap allows, as low-order models
+/// readily fall into cycles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cycle {
+    /// The sequence ends instead of repeating the run of symbols again.
+    Stop,
+
+    /// Symbols which would repeat the run of symbols again are excluded from
+    /// sampling, with the probabilities of the remaining symbols renormalized.
+    Resample,
+}
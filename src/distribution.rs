@@ -0,0 +1,273 @@
+pub(crate) struct Distribution<TSymbol> {
+    weights: Vec<(Option<TSymbol>, f64)>,
+}
+
+impl<TSymbol> Distribution<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    pub(crate) fn from_table<THasher>(
+        table: &crate::Table<TSymbol, THasher>,
+    ) -> Distribution<TSymbol>
+    where
+        THasher: std::hash::BuildHasher,
+    {
+        Distribution {
+            weights: table.iter().map(|(s, f)| (*s, f as f64)).collect(),
+        }
+    }
+
+    pub(crate) fn from_context<M>(model: &M, id: u32) -> Distribution<TSymbol>
+    where
+        M: crate::ReadModel<TSymbol> + ?Sized,
+    {
+        Distribution {
+            weights: (0..model.symbol_count(id))
+                .map(|rank| {
+                    let (s, f) = model.ranked_symbol(id, rank);
+                    (*s, f as f64)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn mixture(parts: Vec<(Distribution<TSymbol>, f64)>) -> Distribution<TSymbol> {
+        let mut weights: Vec<(Option<TSymbol>, f64)> = vec![];
+        let mut indices = std::collections::HashMap::new();
+
+        for (part, weight) in parts {
+            for (symbol, p) in part.probabilities() {
+                let index = *indices.entry(symbol).or_insert_with(|| {
+                    weights.push((symbol, 0.0));
+                    weights.len() - 1
+                });
+                weights[index].1 += weight * p;
+            }
+        }
+
+        Distribution { weights }
+    }
+
+    pub(crate) fn total(&self) -> f64 {
+        self.weights.iter().map(|(_, w)| w).sum()
+    }
+
+    pub(crate) fn probabilities(&self) -> impl Iterator<Item = (Option<TSymbol>, f64)> + '_ {
+        let total = self.total();
+        self.weights.iter().map(move |(s, w)| (*s, w / total))
+    }
+
+    pub(crate) fn probability_of(&self, symbol: &Option<TSymbol>) -> f64 {
+        let weight: f64 = self
+            .weights
+            .iter()
+            .filter(|(s, _)| s == symbol)
+            .map(|(_, w)| w)
+            .sum();
+        weight / self.total()
+    }
+
+    pub(crate) fn apply_temperature(&mut self, temperature: f64) {
+        if temperature <= 0.0 {
+            let max = self.weights.iter().map(|(_, w)| *w).fold(0.0, f64::max);
+            self.weights.retain(|(_, w)| *w >= max);
+            self.weights.truncate(1);
+            return;
+        }
+
+        for (_, w) in &mut self.weights {
+            *w = w.powf(1.0 / temperature);
+        }
+    }
+
+    pub(crate) fn truncate_top_k(&mut self, k: usize) {
+        self.weights
+            .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        self.weights.truncate(k);
+    }
+
+    pub(crate) fn truncate_top_p(&mut self, p: f64) {
+        self.weights
+            .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let threshold = p * self.total();
+        let mut cumulative = 0.0;
+
+        for i in 0..self.weights.len() {
+            cumulative += self.weights[i].1;
+            if cumulative >= threshold {
+                self.weights.truncate(i + 1);
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn penalize<F>(&mut self, penalty: f64, is_penalized: F)
+    where
+        F: Fn(&TSymbol) -> bool,
+    {
+        for (symbol, w) in &mut self.weights {
+            if let Some(s) = symbol {
+                if is_penalized(s) {
+                    *w /= penalty;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn restrict<F>(&mut self, is_allowed: F) -> bool
+    where
+        F: Fn(&Option<TSymbol>) -> bool,
+    {
+        if !self.weights.iter().any(|(s, w)| *w > 0.0 && is_allowed(s)) {
+            return false;
+        }
+
+        self.weights.retain(|(s, _)| is_allowed(s));
+        true
+    }
+
+    pub(crate) fn sample(&self, sample_value: f64) -> Option<Option<TSymbol>> {
+        let mut remaining = sample_value * self.total();
+
+        for (symbol, weight) in &self.weights {
+            if remaining < *weight {
+                return Some(*symbol);
+            }
+            remaining -= weight;
+        }
+
+        // Guard against rounding error leaving a sliver of the range unmatched
+        self.weights
+            .iter()
+            .rev()
+            .find(|(_, w)| *w > 0.0)
+            .map(|(s, _)| *s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::distribution::Distribution;
+    use crate::table::Table;
+
+    fn table() -> Table<char> {
+        let mut t = Table::empty();
+        t.add_weighted(Some('a'), 4);
+        t.add_weighted(Some('b'), 1);
+        t.add_weighted(None, 1);
+        t
+    }
+
+    #[test]
+    fn it_samples_in_proportion_to_weights() {
+        let d = Distribution::from_table(&table());
+
+        assert_eq!(d.total(), 6.0);
+        assert_eq!(d.sample(0.0), Some(Some('a')));
+        assert_eq!(d.sample(0.7), Some(Some('b')));
+        assert_eq!(d.sample(0.9), Some(None));
+
+        let empty = Distribution::<char> { weights: vec![] };
+        assert_eq!(empty.sample(0.5), None);
+    }
+
+    #[test]
+    fn it_reshapes_weights_by_temperature() {
+        let mut d = Distribution::from_table(&table());
+        d.apply_temperature(0.5);
+        assert_eq!(d.total(), 18.0);
+        assert_eq!(d.sample(0.9), Some(Some('b')));
+
+        let mut d = Distribution::from_table(&table());
+        d.apply_temperature(2.0);
+        assert_eq!(d.total(), 4.0);
+        assert_eq!(d.sample(0.5), Some(Some('b')));
+
+        let mut d = Distribution::from_table(&table());
+        d.apply_temperature(0.0);
+        assert_eq!(d.sample(0.99), Some(Some('a')));
+    }
+
+    #[test]
+    fn it_truncates_to_most_probable_symbols() {
+        let mut t = table();
+        t.add_weighted(Some('c'), 2);
+
+        let mut d = Distribution::from_table(&t);
+        d.truncate_top_k(2);
+
+        assert_eq!(d.total(), 6.0);
+        assert_eq!(d.sample(0.0), Some(Some('a')));
+        assert_eq!(d.sample(0.9), Some(Some('c')));
+    }
+
+    #[test]
+    fn it_truncates_to_smallest_set_reaching_cumulative_probability() {
+        let mut t = table();
+        t.add_weighted(Some('c'), 2);
+
+        let mut d = Distribution::from_table(&t);
+        d.truncate_top_p(0.5);
+        assert_eq!(d.total(), 4.0);
+
+        let mut d = Distribution::from_table(&t);
+        d.truncate_top_p(0.6);
+        assert_eq!(d.total(), 6.0);
+        assert_eq!(d.sample(0.9), Some(Some('c')));
+
+        let mut d = Distribution::from_table(&t);
+        d.truncate_top_p(0.0);
+        assert_eq!(d.total(), 4.0);
+
+        let mut d = Distribution::from_table(&t);
+        d.truncate_top_p(1.0);
+        assert_eq!(d.total(), 8.0);
+    }
+
+    #[test]
+    fn it_penalizes_matching_symbols() {
+        let mut d = Distribution::from_table(&table());
+        d.penalize(4.0, |s| *s == 'a');
+
+        assert_eq!(d.total(), 3.0);
+        assert_eq!(d.sample(0.5), Some(Some('b')));
+    }
+
+    #[test]
+    fn it_restricts_to_allowed_symbols_only_if_any_remain() {
+        let mut d = Distribution::from_table(&table());
+        assert!(!d.restrict(|s| *s == Some('c')));
+        assert_eq!(d.total(), 6.0);
+
+        assert!(d.restrict(|s| s.is_none()));
+        assert_eq!(d.total(), 1.0);
+        assert_eq!(d.sample(0.0), Some(None));
+    }
+
+    #[test]
+    fn it_mixes_weighted_distributions() {
+        let mut other = Table::empty();
+        other.add_weighted(Some('c'), 1);
+        other.add_weighted(Some('a'), 1);
+
+        let d = Distribution::mixture(vec![
+            (Distribution::from_table(&table()), 0.75),
+            (Distribution::from_table(&other), 0.25),
+        ]);
+
+        assert!((d.total() - 1.0).abs() < 1e-12);
+        assert_eq!(d.sample(0.0), Some(Some('a')));
+        assert_eq!(d.sample(0.7), Some(Some('b')));
+        assert_eq!(d.sample(0.95), Some(Some('c')));
+    }
+
+    #[test]
+    fn it_exposes_probability_of_symbols() {
+        let d = Distribution::from_table(&table());
+
+        assert_eq!(d.probability_of(&Some('b')), 1.0 / 6.0);
+        assert_eq!(d.probability_of(&None), 1.0 / 6.0);
+        assert_eq!(d.probability_of(&Some('c')), 0.0);
+    }
+}
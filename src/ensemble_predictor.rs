@@ -0,0 +1,209 @@
+/// A predictor which combines the next symbol probabilities of several
+/// [Models](struct.Model.html) with weights before ranking, such as to blend a
+/// per-user model with a global one, or models of different orders.
+///
+/// Unlike a [Predictor](struct.Predictor.html), predicted symbols are returned
+/// by value as they may not originate from any single model.
+pub struct EnsemblePredictor<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    models: Vec<(&'a crate::Model<TSymbol, THasher>, f64)>,
+    history: Vec<TSymbol>,
+}
+
+impl<'a, TSymbol, THasher> EnsemblePredictor<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates an EnsemblePredictor which combines the specified models.
+    ///
+    /// # Arguments
+    ///
+    /// `models` - The Markov models to base predictions on, each paired with
+    /// its mixture weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no models are specified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let global = markov::Model::train_from(1, vec!["the cat sat".split(' '), "the cat ran".split(' ')]);
+    /// let user = markov::Model::train_from(1, vec!["the dog sat".split(' ')]);
+    ///
+    /// let mut pre = markov::EnsemblePredictor::new(&[(&global, 0.25), (&user, 0.75)]);
+    /// pre.given("the");
+    /// assert_eq!(pre.predict(), Some("dog"));
+    /// ```
+    pub fn new(
+        models: &[(&'a crate::Model<TSymbol, THasher>, f64)],
+    ) -> EnsemblePredictor<'a, TSymbol, THasher> {
+        assert!(!models.is_empty(), "at least one model must be specified");
+
+        EnsemblePredictor {
+            models: models.to_vec(),
+            history: vec![],
+        }
+    }
+
+    /// Resets this EnsemblePredictor so that the next symbol predicted will be
+    /// the beginning of a sequence.
+    pub fn end(&mut self) {
+        self.history.clear();
+    }
+
+    /// Specifies a prior symbol upon which future predictions will be based.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The most recent past symbol on which to base predictions.
+    pub fn given(&mut self, symbol: TSymbol) {
+        self.history.push(symbol);
+
+        let max_order = self
+            .models
+            .iter()
+            .map(|(m, _)| m.order())
+            .max()
+            .unwrap_or(0);
+        if self.history.len() > max_order {
+            self.history.drain(..(self.history.len() - max_order));
+        }
+    }
+
+    /// Predicts and returns the most probable next symbol, and advances to it
+    /// as though it had been specified via [`given`](#method.given).
+    ///
+    /// `None` is returned when the end of a sequence is reached, or when no
+    /// model has observed the current context.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<TSymbol> {
+        let symbol = self.predict();
+        if let Some(s) = symbol {
+            self.given(s);
+        }
+        symbol
+    }
+
+    /// Predicts and returns the most probable next symbol, without advancing
+    /// to the next symbol in the sequence.
+    ///
+    /// `None` is returned when the end of a sequence is reached, or when no
+    /// model has observed the current context.
+    pub fn predict(&self) -> Option<TSymbol> {
+        match self.distribution().first() {
+            Some(&(s, _)) => s,
+            None => None,
+        }
+    }
+
+    /// Predicts the `n` most probable next symbols along with their combined
+    /// probabilities, ordered from most to least probable. The end of a
+    /// sequence is represented by `None`.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The maximum number of candidates to return.
+    pub fn predict_top(&self, n: usize) -> Vec<(Option<TSymbol>, f64)> {
+        let mut distribution = self.distribution();
+        distribution.truncate(n);
+        distribution
+    }
+
+    /// Returns the combined probabilities of all possible next symbols,
+    /// ordered from most to least probable. The end of a sequence is
+    /// represented by `None`.
+    ///
+    /// Models which have not observed the current context are left out, with
+    /// the weights of the remaining models renormalized. An empty distribution
+    /// is returned if no model has observed the current context.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let a = markov::Model::train_from(1, vec!["xa".chars()]);
+    /// let b = markov::Model::train_from(1, vec!["xb".chars()]);
+    ///
+    /// let mut pre = markov::EnsemblePredictor::new(&[(&a, 1.0), (&b, 3.0)]);
+    /// pre.given('x');
+    /// assert_eq!(pre.distribution(), vec![(Some('b'), 0.75), (Some('a'), 0.25)]);
+    /// ```
+    pub fn distribution(&self) -> Vec<(Option<TSymbol>, f64)> {
+        let parts: Vec<_> = self
+            .models
+            .iter()
+            .filter_map(|(model, weight)| {
+                let context = crate::Sequence::from_slice(&self.history, model.order())
+                    .map(|s| model.normalize(s));
+                model.distribution(&context).map(|d| (d, *weight))
+            })
+            .collect();
+
+        if parts.is_empty() {
+            return vec![];
+        }
+
+        let mut distribution: Vec<_> = crate::Distribution::mixture(parts)
+            .probabilities()
+            .collect();
+        distribution.sort_by(|a, b| b.1.total_cmp(&a.1));
+        distribution
+    }
+
+    /// Returns the combined probability of the specified symbol being next.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The candidate next symbol.
+    pub fn probability_of(&self, symbol: TSymbol) -> f64 {
+        let symbol = Some(symbol);
+        self.distribution()
+            .into_iter()
+            .find(|(s, _)| *s == symbol)
+            .map_or(0.0, |(_, p)| p)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ensemble_predictor::EnsemblePredictor;
+    use crate::model::Model;
+
+    #[test]
+    fn it_combines_distributions_of_several_models() {
+        let first = Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+        let second = Model::train_from(2, vec!["xac".chars()]);
+
+        let mut pre = EnsemblePredictor::new(&[(&first, 0.5), (&second, 0.5)]);
+        pre.given('x');
+        pre.given('a');
+        assert_eq!(
+            pre.distribution(),
+            vec![(Some('c'), 0.75), (Some('b'), 0.25)]
+        );
+        assert_eq!(pre.probability_of('b'), 0.25);
+        assert_eq!(pre.probability_of('z'), 0.0);
+        assert_eq!(pre.predict_top(1), vec![(Some('c'), 0.75)]);
+
+        assert_eq!(pre.next(), Some('c'));
+        assert_eq!(pre.next(), None);
+    }
+
+    #[test]
+    fn it_ignores_models_which_have_not_observed_the_context() {
+        let first = Model::train_from(1, vec!["ab".chars()]);
+        let second = Model::train_from(1, vec!["xy".chars()]);
+
+        let mut pre = EnsemblePredictor::new(&[(&first, 0.1), (&second, 0.9)]);
+        pre.given('a');
+        assert_eq!(pre.distribution(), vec![(Some('b'), 1.0)]);
+
+        pre.end();
+        pre.given('z');
+        assert!(pre.distribution().is_empty());
+        assert_eq!(pre.predict(), None);
+    }
+}
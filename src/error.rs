@@ -0,0 +1,17 @@
+/// Errors which can occur when operating on a [Model](struct.Model.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A sequence could not be removed from a model because it was not part
+    /// of the model's training data.
+    NotTrained,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotTrained => write!(f, "sequence is not part of the training data"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,103 @@
+/// Strategies for handling symbols to which a model assigns no probability,
+/// either because their context was never observed or because they never
+/// followed it during training.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Unseen {
+    /// Unseen symbols are excluded from the evaluation.
+    Skip,
+
+    /// Unseen symbols are assigned the specified probability.
+    Floor(f64),
+}
+
+/// The result of evaluating a [Model](struct.Model.html) over a corpus of test
+/// sequences.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Evaluation {
+    /// The number of symbols (including sequence ends) which were scored.
+    pub scored: usize,
+
+    /// The number of symbols which were skipped because the model assigned
+    /// them no probability.
+    pub skipped: usize,
+
+    /// The total surprisal, in bits, of all scored symbols.
+    pub total_bits: f64,
+}
+
+impl Evaluation {
+    pub(crate) fn empty() -> Evaluation {
+        Evaluation {
+            scored: 0,
+            skipped: 0,
+            total_bits: 0.0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, probability: f64, unseen: Unseen) {
+        let p = if probability > 0.0 {
+            probability
+        } else {
+            match unseen {
+                Unseen::Skip => {
+                    self.skipped += 1;
+                    return;
+                }
+                Unseen::Floor(p) => p,
+            }
+        };
+
+        self.scored += 1;
+        self.total_bits -= p.log2();
+    }
+
+    /// Returns the average cross-entropy, in bits per symbol, of the scored
+    /// symbols.
+    ///
+    /// Zero is returned if no symbols were scored.
+    pub fn cross_entropy(&self) -> f64 {
+        if self.scored == 0 {
+            return 0.0;
+        }
+        self.total_bits / self.scored as f64
+    }
+
+    /// Returns the perplexity of the model over the scored symbols.
+    ///
+    /// Lower perplexities indicate that the model better predicts the test
+    /// sequences.
+    pub fn perplexity(&self) -> f64 {
+        self.cross_entropy().exp2()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evaluation::{Evaluation, Unseen};
+
+    #[test]
+    fn it_averages_surprisal_over_scored_symbols() {
+        let mut e = Evaluation::empty();
+
+        e.add(0.5, Unseen::Skip);
+        e.add(0.25, Unseen::Skip);
+        e.add(0.0, Unseen::Skip);
+
+        assert_eq!(e.scored, 2);
+        assert_eq!(e.skipped, 1);
+        assert_eq!(e.total_bits, 3.0);
+        assert_eq!(e.cross_entropy(), 1.5);
+        assert_eq!(e.perplexity(), 1.5f64.exp2());
+    }
+
+    #[test]
+    fn it_applies_floor_probability_to_unseen_symbols() {
+        let mut e = Evaluation::empty();
+
+        e.add(0.0, Unseen::Floor(0.125));
+
+        assert_eq!(e.scored, 1);
+        assert_eq!(e.skipped, 0);
+        assert_eq!(e.cross_entropy(), 3.0);
+    }
+}
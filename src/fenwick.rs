@@ -0,0 +1,109 @@
+// A Fenwick tree (binary indexed tree) of counts, supporting updates and
+// searches of cumulative counts in O(log n). Sums saturate rather than overflow,
+// as do the totals of the tables which use it.
+#[derive(Clone, Default)]
+pub(crate) struct Fenwick {
+    tree: Vec<usize>,
+}
+
+impl Fenwick {
+    pub(crate) fn from_counts<I>(counts: I) -> Fenwick
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut tree: Vec<usize> = counts.into_iter().collect();
+        for i in 0..tree.len() {
+            let parent = i | (i + 1);
+            if parent < tree.len() {
+                tree[parent] = tree[parent].saturating_add(tree[i]);
+            }
+        }
+        Fenwick { tree }
+    }
+
+    pub(crate) fn push(&mut self, count: usize) {
+        let i = self.tree.len();
+        let lowest = i & (i + 1);
+        let covered = self.prefix_sum(i) - self.prefix_sum(lowest);
+        self.tree.push(count.saturating_add(covered));
+    }
+
+    pub(crate) fn add(&mut self, index: usize, delta: usize) {
+        let mut i = index;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i].saturating_add(delta);
+            i |= i + 1;
+        }
+    }
+
+    pub(crate) fn subtract(&mut self, index: usize, delta: usize) {
+        let mut i = index;
+        while i < self.tree.len() {
+            self.tree[i] -= delta;
+            i |= i + 1;
+        }
+    }
+
+    // Returns the sum of the counts before the specified index
+    pub(crate) fn prefix_sum(&self, index: usize) -> usize {
+        let mut sum = 0;
+        let mut i = index;
+        while i > 0 {
+            sum = usize::saturating_add(sum, self.tree[i - 1]);
+            i &= i - 1;
+        }
+        sum
+    }
+
+    // Returns the index of the count containing the specified position within
+    // the cumulative counts, or the number of counts if it lies beyond them
+    pub(crate) fn find(&self, position: usize) -> usize {
+        let mut remaining = position;
+        let mut index = 0;
+        let mut step = self.tree.len().next_power_of_two();
+
+        while step > 0 {
+            let next = index + step;
+            if next <= self.tree.len() && self.tree[next - 1] <= remaining {
+                remaining -= self.tree[next - 1];
+                index = next;
+            }
+            step >>= 1;
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fenwick::Fenwick;
+
+    #[test]
+    fn it_tracks_cumulative_counts() {
+        let counts = [3, 0, 2, 5, 1];
+        let mut f = Fenwick::default();
+        for c in &counts {
+            f.push(*c);
+        }
+
+        for i in 0..=counts.len() {
+            assert_eq!(f.prefix_sum(i), counts[..i].iter().sum::<usize>());
+        }
+        assert_eq!(f.tree, Fenwick::from_counts(counts.iter().copied()).tree);
+
+        f.add(1, 4);
+        f.subtract(3, 2);
+        assert_eq!(f.prefix_sum(5), 13);
+        assert_eq!(f.prefix_sum(3), 9);
+    }
+
+    #[test]
+    fn it_finds_counts_containing_positions() {
+        let f = Fenwick::from_counts(vec![3, 0, 2, 5, 1]);
+
+        let found: Vec<usize> = (0..12).map(|p| f.find(p)).collect();
+        assert_eq!(found, vec![0, 0, 0, 2, 2, 3, 3, 3, 3, 3, 4, 5]);
+        assert_eq!(Fenwick::default().find(0), 0);
+    }
+}
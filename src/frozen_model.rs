@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+/// A [Model](struct.Model.html) which has been frozen for serving, as returned
+/// by [`Model::freeze`](struct.Model.html#method.freeze).
+///
+/// Rather than a table for each context, a frozen model holds its transitions
+/// in a few contiguous arrays, indexed by the ID of each context. The symbols
+/// of each context are laid out from most to least frequent, along with their
+/// cumulative frequencies and alias tables for sampling in O(1), so that
+/// queries need not order or search them. As frozen models cannot be trained,
+/// this layout is kept for as long as they are used.
+///
+/// FrozenModels are read by Predictors and Generators through the
+/// [ReadModel](trait.ReadModel.html) trait, in the same way as Models.
+pub struct FrozenModel<TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    order: usize,
+    // Each context is stored once, and its entries are found at
+    // offsets[id]..offsets[id + 1] of the following arrays
+    contexts: crate::interner::Interner<crate::Sequence<TSymbol>, THasher>,
+    offsets: Vec<usize>,
+    symbols: Vec<Option<TSymbol>>,
+    // The frequency of each entry plus those of the entries before it in the
+    // same context
+    cumulative: Vec<usize>,
+    alias: crate::alias::AliasTable,
+    // The position of each entry, by the ID of its context and its symbol
+    entry_indices: HashMap<(u32, Option<TSymbol>), usize, THasher>,
+    normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+    // Kept only to restore the model when it is thawed
+    count_overflow: crate::CountOverflow,
+    context_filter_rate: Option<f64>,
+}
+
+impl<TSymbol, THasher> FrozenModel<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    pub(crate) fn new(model: crate::Model<TSymbol, THasher>) -> FrozenModel<TSymbol, THasher> {
+        let hasher = model.hasher().clone();
+        let mut contexts = crate::interner::Interner::with_hasher(hasher.clone());
+        let mut offsets = vec![0];
+        let mut symbols = vec![];
+        let mut cumulative = vec![];
+        let mut alias = crate::alias::AliasTable::default();
+        let mut entry_indices = HashMap::with_hasher(hasher);
+
+        for (seq, table) in model.tables() {
+            let id = contexts.intern(seq.symbols());
+
+            let mut total: usize = 0;
+            for (symbol, frequency) in table.iter() {
+                total = total.saturating_add(frequency);
+                entry_indices.insert((id, *symbol), symbols.len());
+                symbols.push(*symbol);
+                cumulative.push(total);
+            }
+            alias.push(table.iter().map(|(_, frequency)| frequency));
+            offsets.push(symbols.len());
+        }
+
+        FrozenModel {
+            order: model.order(),
+            contexts,
+            offsets,
+            symbols,
+            cumulative,
+            alias,
+            entry_indices,
+            count_overflow: model.count_overflow(),
+            context_filter_rate: model.context_filter_rate(),
+            normalizer: model.into_normalizer(),
+        }
+    }
+
+    /// Returns a model with the same transitions as this one, so that it can
+    /// be trained further.
+    ///
+    /// The symbols of each context are restored from most to least frequent,
+    /// so ties between them are afterwards broken in that order rather than
+    /// the order in which they were first seen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars()]).freeze();
+    ///
+    /// let mut model = model.thaw();
+    /// markov::Accumulator::new(&mut model).train_sequence("ac".chars());
+    /// ```
+    pub fn thaw(mut self) -> crate::Model<TSymbol, THasher> {
+        let mut model = crate::Model::with_hasher(self.order, self.contexts.hasher().clone())
+            .with_normalizer(self.normalizer.take());
+        model.set_count_overflow(self.count_overflow);
+
+        for (id, seq) in self.contexts.iter().enumerate() {
+            let range = self.offsets[id]..self.offsets[id + 1];
+            for i in range.clone() {
+                let frequency = self.frequency_at(range.start, i);
+                model.add_weighted(seq, self.symbols[i], frequency);
+            }
+        }
+
+        model.set_context_filter(self.context_filter_rate);
+        model
+    }
+
+    /// Converts this model to a succinct form which keeps exact counts in far
+    /// less memory, at the cost of decoding them for each query. This suits
+    /// models too large to otherwise hold in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+    ///
+    /// let model = model.freeze().into_succinct();
+    /// assert_eq!(model.predict(&['a']), Some(&'b'));
+    /// ```
+    pub fn into_succinct(self) -> crate::SuccinctModel<TSymbol, THasher> {
+        let FrozenModel {
+            order,
+            contexts,
+            offsets,
+            symbols,
+            cumulative,
+            normalizer,
+            ..
+        } = self;
+        crate::SuccinctModel::new(order, contexts, &offsets, &symbols, &cumulative, normalizer)
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns the ID by which the specified context is stored in this model.
+    ///
+    /// Contexts are numbered afresh when a model is frozen, so these IDs are
+    /// separate from those of [`Model::context_id`](struct.Model.html#method.context_id),
+    /// and are only valid for this FrozenModel.
+    ///
+    /// `None` is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first.
+    pub fn context_id(&self, context: &[TSymbol]) -> Option<u32> {
+        let context: Vec<TSymbol> = context
+            .iter()
+            .map(|s| crate::ReadModel::normalize(self, *s))
+            .collect();
+        self.contexts
+            .get(crate::Sequence::from_slice(&context, self.order).symbols())
+    }
+
+    /// Returns the symbols of the context with the specified ID, as returned
+    /// by [`context_id`](#method.context_id).
+    ///
+    /// `None` is returned if no context has the specified ID.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    pub fn context_symbols(&self, id: u32) -> Option<&[TSymbol]> {
+        self.contexts.resolve(id).map(|seq| seq.symbols())
+    }
+
+    /// Returns the approximate number of bytes used to store the transitions
+    /// of this model, excluding its contexts, as per
+    /// [`SuccinctModel::transition_bytes`](struct.SuccinctModel.html#method.transition_bytes).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let corpus: Vec<String> = (0..1000).map(|i| format!("{}", i * 7919)).collect();
+    /// let model = markov::Model::train_from(2, corpus.iter().map(|s| s.chars())).freeze();
+    ///
+    /// let frozen_bytes = model.transition_bytes();
+    /// assert!(model.into_succinct().transition_bytes() < frozen_bytes);
+    /// ```
+    pub fn transition_bytes(&self) -> usize {
+        // Each slot of a hash table also takes a byte of control data
+        let index_entry = std::mem::size_of::<((u32, Option<TSymbol>), usize)>() + 1;
+
+        self.offsets.len() * std::mem::size_of::<usize>()
+            + self.symbols.len() * std::mem::size_of::<Option<TSymbol>>()
+            + self.cumulative.len() * std::mem::size_of::<usize>()
+            + self.alias.size_in_bytes()
+            + self.entry_indices.capacity() * index_entry
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first.
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]).freeze();
+    ///
+    /// assert_eq!(model.probability(&['a'], Some('b')), 0.5);
+    /// ```
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        let symbol = symbol.map(|s| crate::ReadModel::normalize(self, s));
+
+        match self.context_id(context) {
+            Some(id) => {
+                crate::ReadModel::frequency_of(self, id, &symbol) as f64
+                    / crate::ReadModel::total(self, id) as f64
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Returns the probability that a sequence ends immediately after the
+    /// specified context, as per
+    /// [`Model::end_probability`](struct.Model.html#method.end_probability).
+    ///
+    /// `None` is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first.
+    pub fn end_probability(&self, context: &[TSymbol]) -> Option<f64> {
+        self.context_id(context).map(|id| {
+            crate::ReadModel::frequency_of(self, id, &None) as f64
+                / crate::ReadModel::total(self, id) as f64
+        })
+    }
+
+    // Returns the frequency of the entry at a position, given the position at
+    // which the entries of its context start
+    fn frequency_at(&self, start: usize, i: usize) -> usize {
+        if i == start {
+            self.cumulative[i]
+        } else {
+            self.cumulative[i] - self.cumulative[i - 1]
+        }
+    }
+
+    fn range(&self, id: u32) -> std::ops::Range<usize> {
+        self.offsets[id as usize]..self.offsets[id as usize + 1]
+    }
+}
+
+impl<TSymbol, THasher> crate::ReadModel<TSymbol> for FrozenModel<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    fn order(&self) -> usize {
+        self.order
+    }
+
+    fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        match &self.normalizer {
+            Some(normalizer) => normalizer(symbol),
+            None => symbol,
+        }
+    }
+
+    fn find_context(&self, context: &[TSymbol]) -> Option<u32> {
+        self.contexts.get(context)
+    }
+
+    fn contexts(&self) -> Box<dyn Iterator<Item = (u32, &[TSymbol])> + '_> {
+        Box::new(
+            self.contexts
+                .iter()
+                .enumerate()
+                .map(|(id, seq)| (id as u32, seq.symbols())),
+        )
+    }
+
+    fn symbol_count(&self, id: u32) -> usize {
+        self.range(id).len()
+    }
+
+    fn ranked_symbol(&self, id: u32, rank: usize) -> (&Option<TSymbol>, usize) {
+        let start = self.offsets[id as usize];
+        let i = start + rank;
+        (&self.symbols[i], self.frequency_at(start, i))
+    }
+
+    fn frequency_of(&self, id: u32, symbol: &Option<TSymbol>) -> usize {
+        match self.entry_indices.get(&(id, *symbol)) {
+            Some(&i) => self.frequency_at(self.offsets[id as usize], i),
+            None => 0,
+        }
+    }
+
+    fn total(&self, id: u32) -> usize {
+        self.cumulative[self.offsets[id as usize + 1] - 1]
+    }
+
+    fn sample(&self, id: u32, sample_value: f64) -> Option<&TSymbol> {
+        match self.alias.sample(self.range(id), sample_value) {
+            Some(i) => self.symbols[i].as_ref(),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::generator::Generator;
+    use crate::model::Model;
+    use crate::predictor::Predictor;
+    use crate::read_model::ReadModel;
+
+    #[test]
+    fn it_is_read_like_the_model_it_was_frozen_from() {
+        let mut m = Model::train_from(1, vec!["abc".chars(), "abd".chars(), "abd".chars()]);
+        m.remove_sequence("abc".chars()).unwrap();
+        let frozen = m.freeze();
+
+        assert_eq!(frozen.context_count(), 4);
+        assert_eq!(frozen.end_probability(&['d']), Some(1.0));
+        assert_eq!(frozen.probability(&['b'], Some('d')), 1.0);
+        assert_eq!(frozen.probability(&['b'], Some('c')), 0.0);
+        assert_eq!(frozen.probability(&['x'], None), 0.0);
+
+        let mut pre = Predictor::new(&frozen);
+        pre.given('b');
+        assert_eq!(pre.predict(), Some(&'d'));
+    }
+
+    #[test]
+    fn it_can_be_thawed_and_trained_further() {
+        let mut m = Model::train_from(1, vec!["ab".chars()]).freeze().thaw();
+        crate::Accumulator::new(&mut m).train_sequence("ac".chars());
+        crate::Accumulator::new(&mut m).train_sequence("ac".chars());
+
+        let mut pre = Predictor::new(&m);
+        pre.given('a');
+        assert_eq!(pre.predict(), Some(&'c'));
+    }
+
+    #[test]
+    fn it_lays_out_the_symbols_of_each_context_from_most_frequent() {
+        let train = || Model::train_from(1, vec!["abacacad".chars(), "ca".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        for (id, context) in m.contexts() {
+            let frozen_id = frozen.find_context(context).unwrap();
+            assert_eq!(frozen.symbol_count(frozen_id), m.symbol_count(id));
+            assert_eq!(frozen.total(frozen_id), m.total(id));
+            for rank in 0..m.symbol_count(id) {
+                let (symbol, frequency) = m.ranked_symbol(id, rank);
+                assert_eq!(frozen.ranked_symbol(frozen_id, rank), (symbol, frequency));
+                assert_eq!(frozen.frequency_of(frozen_id, symbol), frequency);
+            }
+        }
+
+        let id = frozen.find_context(&['a']).unwrap();
+        assert_eq!(frozen.ranked_symbol(id, 0), (&Some('c'), 2));
+        assert_eq!(frozen.frequency_of(id, &Some('x')), 0);
+        assert_eq!(frozen.find_context(&['x']), None);
+    }
+
+    #[test]
+    fn it_samples_in_proportion_to_frequency() {
+        let frozen = Model::train_from(
+            1,
+            vec!["ab".chars(), "ac".chars(), "ac".chars(), "a".chars()],
+        )
+        .freeze();
+        let id = frozen.find_context(&['a']).unwrap();
+
+        let mut counts = [0; 3];
+        for i in 0..400 {
+            match frozen.sample(id, i as f64 / 400.0) {
+                Some('b') => counts[0] += 1,
+                Some('c') => counts[1] += 1,
+                None => counts[2] += 1,
+                Some(_) => unreachable!(),
+            }
+        }
+        assert_eq!(counts, [100, 200, 100]);
+    }
+
+    #[test]
+    fn it_generates_like_the_model_it_was_frozen_from() {
+        let train = || Model::train_from(2, vec!["the cat sat".chars(), "the hat".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        let output: String = Generator::new(&frozen, || 0.0).collect();
+        assert_eq!(output, "the cat");
+
+        let output: String = Generator::seeded(&frozen, 7).collect();
+        let mut pre = Predictor::new(&m);
+        for symbol in output.chars() {
+            assert!(pre.probability_of(symbol) > 0.0);
+            pre.given(symbol);
+        }
+
+        let mut pre = Predictor::new(&frozen);
+        pre.given('t');
+        pre.given('h');
+        assert_eq!(pre.probability_of('e'), 1.0);
+    }
+}
@@ -1,55 +1,1198 @@
+type Constraint<'a, TSymbol> = Box<dyn Fn(&TSymbol, &[TSymbol]) -> bool + Send + 'a>;
+
+type Observer<'a, TSymbol> = Box<dyn FnMut(&[TSymbol], &TSymbol, f64) -> bool + Send + 'a>;
+
+enum ModelRef<'a, M> {
+    Borrowed(&'a M),
+    Shared(std::sync::Arc<M>),
+}
+
+impl<'a, M> std::ops::Deref for ModelRef<'a, M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        match self {
+            ModelRef::Borrowed(model) => model,
+            ModelRef::Shared(model) => model,
+        }
+    }
+}
+
 /// A Generator for generating probable outcomes using a
 /// [Model](struct.Model.html).
 ///
-/// Generators do not modify the underlying model.
-pub struct Generator<'a, TSymbol>
+/// Generators do not modify the underlying model, which may either be
+/// borrowed (see [`new`](#method.new)) or shared through an `Arc` (see
+/// [`owned`](#method.owned)).
+///
+/// A Generator is `Send` whenever its random source is, such as an unboxed
+/// closure or an [RngSource](struct.RngSource.html) over a `Send` generator,
+/// so that it can be moved to another thread. The default boxed source is not
+/// `Send`, but `Box<dyn FnMut() -> f64 + Send>` may be used in its place.
+pub struct Generator<'a, TSymbol, R = Box<dyn FnMut() -> f64 + 'a>, M = crate::Model<TSymbol>>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+{
+    model: ModelRef<'a, M>,
+    current_sequence: crate::Window<TSymbol>,
+    next_rand: R,
+    temperature: f64,
+    top_k: Option<usize>,
+    top_p: Option<f64>,
+    repetition_window: usize,
+    repetition_penalty: f64,
+    history: Vec<TSymbol>,
+    generated: usize,
+    min_len: usize,
+    max_len: Option<usize>,
+    overflow: crate::Overflow,
+    constraint: Option<Constraint<'a, TSymbol>>,
+    backoff: bool,
+    cycle_len: usize,
+    cycle_max_repeats: usize,
+    cycle: crate::Cycle,
+    mixture: Vec<(&'a M, f64)>,
+    observer: Option<Observer<'a, TSymbol>>,
+    last_probability: f64,
+    stopped: bool,
+    stop_symbols: std::collections::HashSet<TSymbol>,
+    start_contexts: Vec<(crate::Sequence<TSymbol>, usize)>,
+    offered: Vec<Option<TSymbol>>,
+    offered_context: Option<crate::Sequence<TSymbol>>,
+}
+
+impl<'a, TSymbol, R, M> Generator<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
+{
+    /// Creates a Generator which uses the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to base generated data on.
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0) range,
+    /// used to generate the output. This may be any
+    /// [RandomSource](trait.RandomSource.html), including unboxed closures.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::Rng;
+    ///
+    /// let model = markov::Model::<i32>::empty(1);
+    ///
+    /// let mut rng = rand::thread_rng();
+    /// let mut gen = markov::Generator::new(&model, Box::new(move || rng.gen::<f64>()));
+    /// ```
+    pub fn new(model: &'a M, rand_source: R) -> Generator<'a, TSymbol, R, M> {
+        Generator::from_model_ref(ModelRef::Borrowed(model), rand_source)
+    }
+
+    /// Creates a Generator which samples each symbol from a weighted mixture of
+    /// the next symbol probabilities of several models, such as to blend a
+    /// general model with a domain-specific one.
+    ///
+    /// Models may be of different orders. The first model is used to
+    /// normalize symbols and for any other features which consult a single
+    /// model, such as [`set_backoff`](#method.set_backoff).
+    ///
+    /// # Arguments
+    ///
+    /// `models` - The Markov models to base generated data on, each paired
+    /// with its mixture weight.
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0) range,
+    /// used to generate the output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no models are specified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let general = markov::Model::train_from(1, vec!["the cat sat".split(' ')]);
+    /// let domain = markov::Model::train_from(1, vec!["the rat ran".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::ensemble(&[(&general, 0.3), (&domain, 0.7)], || 0.5);
+    /// assert_eq!(gen.generate_sequence(), vec!["the", "rat", "ran"]);
+    /// ```
+    pub fn ensemble(models: &[(&'a M, f64)], rand_source: R) -> Generator<'a, TSymbol, R, M> {
+        assert!(!models.is_empty(), "at least one model must be specified");
+
+        let mut gen = Generator::new(models[0].0, rand_source);
+        gen.mixture = models.to_vec();
+        gen
+    }
+
+    /// Specifies a prior symbol upon which generated symbols will be based,
+    /// so that generation continues from a user-supplied prefix.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The most recent past symbol on which to base generation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["the quick brown fox".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.given("quick");
+    ///
+    /// assert_eq!(gen.collect::<Vec<_>>(), vec!["brown", "fox"]);
+    /// ```
+    pub fn given(&mut self, symbol: TSymbol) {
+        let symbol = self.model.normalize(symbol);
+        self.current_sequence.push(symbol);
+        self.history.push(symbol);
+        self.offered_context = None;
+    }
+
+    /// Returns the context on which the next generated symbol will be based,
+    /// consisting of the most recent symbols of the current sequence (up to
+    /// the order of the model).
+    ///
+    /// Together with [`set_context`](#method.set_context), this allows the
+    /// state of generation to be saved (such as by serializing the returned
+    /// symbols) and later resumed without regenerating the sequence so far.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(2, vec!["once upon a time".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.next();
+    /// gen.next();
+    /// let saved = gen.context().to_vec();
+    ///
+    /// let mut resumed = markov::Generator::new(&model, || 0.0);
+    /// resumed.set_context(&saved);
+    /// assert_eq!(resumed.collect::<Vec<_>>(), vec!["a", "time"]);
+    /// ```
+    pub fn context(&self) -> &[TSymbol] {
+        self.current_sequence.symbols()
+    }
+
+    /// Returns whether the model has observed the current context, so that
+    /// callers can tell whether `None` from [`next`](#method.next) marks the
+    /// natural end of a sequence or a context which the model cannot continue
+    /// from.
+    ///
+    /// For an [ensemble](#method.ensemble), the context is known if any of
+    /// the models has observed it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.given('a');
+    /// assert_eq!(gen.next(), Some('b'));
+    /// assert_eq!(gen.next(), None);
+    /// assert!(gen.knows_context());
+    ///
+    /// gen.end();
+    /// gen.given('x');
+    /// assert_eq!(gen.next(), None);
+    /// assert!(!gen.knows_context());
+    /// ```
+    pub fn knows_context(&self) -> bool {
+        if !self.mixture.is_empty() {
+            return self.mixture.iter().any(|(model, _)| {
+                let context = crate::Sequence::from_slice(&self.history, model.order())
+                    .map(|s| model.normalize(s));
+                model.find_context(context.symbols()).is_some()
+            });
+        }
+
+        if self.backoff {
+            let context =
+                crate::read_model::back_off(&*self.model, self.current_sequence.symbols());
+            return self.model.find_context(context).is_some();
+        }
+        self.model
+            .find_context(self.current_sequence.symbols())
+            .is_some()
+    }
+
+    /// Resets this Generator and restores a context previously returned by
+    /// [`context`](#method.context), as though its symbols had been specified
+    /// via [`given`](#method.given).
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols on which to base generation.
+    pub fn set_context(&mut self, context: &[TSymbol]) {
+        self.end();
+        for symbol in context {
+            self.given(*symbol);
+        }
+    }
+
+    /// Sets the temperature used to reshape the distribution of next symbols
+    /// before sampling, with each symbol's frequency raised to the power of
+    /// `1 / temperature`.
+    ///
+    /// Temperatures below 1.0 favour the most probable symbols, producing more
+    /// conservative output, while temperatures above 1.0 flatten the
+    /// distribution, producing more surprising output. A temperature of zero
+    /// always chooses the most probable symbol.
+    ///
+    /// # Arguments
+    ///
+    /// `temperature` - The sampling temperature, which defaults to 1.0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["the quick brown fox".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::with_rng(&model, rand::rngs::StdRng::seed_from_u64(1));
+    /// gen.set_temperature(0.5);
+    /// ```
+    pub fn set_temperature(&mut self, temperature: f64) {
+        self.temperature = temperature;
+    }
+
+    /// Restricts sampling to the `k` most probable next symbols (including the
+    /// end of a sequence) for each context, so that rare transitions cannot
+    /// derail generation.
+    ///
+    /// # Arguments
+    ///
+    /// `k` - The number of most probable symbols to sample from, or `None` to
+    /// sample from all symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["the quick brown fox".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::with_rng(&model, rand::rngs::StdRng::seed_from_u64(1));
+    /// gen.set_top_k(Some(3));
+    /// ```
+    pub fn set_top_k(&mut self, k: Option<usize>) {
+        self.top_k = k;
+    }
+
+    /// Restricts sampling to the smallest set of most probable next symbols
+    /// (including the end of a sequence) whose cumulative probability reaches
+    /// `p`, also known as nucleus sampling.
+    ///
+    /// Unlike [`set_top_k`](#method.set_top_k), the number of eligible
+    /// symbols adapts to how peaked each context's distribution is. When both
+    /// are set, top-k truncation is applied first.
+    ///
+    /// # Arguments
+    ///
+    /// `p` - The cumulative probability, in the (0.0, 1.0] range, of the
+    /// symbols to sample from, or `None` to sample from all symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["the quick brown fox".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::with_rng(&model, rand::rngs::StdRng::seed_from_u64(1));
+    /// gen.set_top_p(Some(0.9));
+    /// ```
+    pub fn set_top_p(&mut self, p: Option<f64>) {
+        self.top_p = p;
+    }
+
+    /// Down-weights symbols which already appear among the most recent
+    /// symbols of the current sequence, to curb repetitive loops.
+    ///
+    /// # Arguments
+    ///
+    /// `window` - The number of most recent symbols to consider, or zero to
+    /// disable the penalty.
+    ///
+    /// `penalty` - The factor by which the weights of recently seen symbols
+    /// are divided. Values greater than 1.0 discourage repetition.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["the the the end".split(' ')]);
+    ///
+    /// let mut gen = markov::Generator::with_rng(&model, rand::rngs::StdRng::seed_from_u64(1));
+    /// gen.set_repetition_penalty(3, 2.0);
+    /// ```
+    pub fn set_repetition_penalty(&mut self, window: usize, penalty: f64) {
+        self.repetition_window = window;
+        self.repetition_penalty = penalty;
+    }
+
+    /// Prevents sequences from ending until at least `min_len` symbols have
+    /// been generated, by excluding the end of a sequence from sampling and
+    /// renormalizing the probabilities of the remaining symbols.
+    ///
+    /// A sequence may still end early if the model has never observed any
+    /// symbol following the current context.
+    ///
+    /// # Arguments
+    ///
+    /// `min_len` - The minimum number of symbols to generate before the end
+    /// of a sequence, which defaults to zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "abcd".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_min_len(3);
+    /// assert_eq!(gen.collect::<String>(), "abcd");
+    /// ```
+    pub fn set_min_len(&mut self, min_len: usize) {
+        self.min_len = min_len;
+    }
+
+    /// Limits the number of symbols generated for each sequence, so that
+    /// generation is guaranteed to stop even if the model never reaches the
+    /// end of a sequence (such as when its training sequences were never
+    /// ended).
+    ///
+    /// # Arguments
+    ///
+    /// `max_len` - The maximum number of symbols to generate before the end
+    /// of a sequence, or `None` for no limit.
+    ///
+    /// `overflow` - How to stop generation once the limit is reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abcab".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_max_len(Some(3), markov::Overflow::Truncate);
+    /// assert_eq!(gen.collect::<String>(), "abc");
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_max_len(Some(3), markov::Overflow::ForceEnd);
+    /// assert_eq!(gen.collect::<String>(), "ab");
+    /// ```
+    pub fn set_max_len(&mut self, max_len: Option<usize>, overflow: crate::Overflow) {
+        self.max_len = max_len;
+        self.overflow = overflow;
+    }
+
+    /// Restricts generation to symbols accepted by a predicate, with the
+    /// probabilities of the remaining symbols renormalized before sampling.
+    /// This can be used for blocklists, deduplication, and domain-specific
+    /// constraints.
+    ///
+    /// If every possible next symbol is rejected, the sequence ends.
+    ///
+    /// # Arguments
+    ///
+    /// `constraint` - A function which is passed each candidate symbol along
+    /// with the symbols of the current sequence so far (including any given
+    /// symbols), and returns whether the candidate is allowed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["aabb".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_constraint(|symbol, output| !output.contains(symbol));
+    /// assert_eq!(gen.collect::<String>(), "ab");
+    /// ```
+    pub fn set_constraint<F>(&mut self, constraint: F)
+    where
+        F: Fn(&TSymbol, &[TSymbol]) -> bool + Send + 'a,
+    {
+        self.constraint = Some(Box::new(constraint));
+    }
+
+    /// Removes any constraint previously set with
+    /// [`set_constraint`](#method.set_constraint).
+    pub fn clear_constraint(&mut self) {
+        self.constraint = None;
+    }
+
+    /// Enables backing off to shorter contexts when the current context was
+    /// never observed during training (such as after an unfamiliar
+    /// [`given`](#method.given) prefix). The oldest symbols of the context
+    /// are dropped one at a time until a known context is found, down to the
+    /// empty context used to begin sequences.
+    ///
+    /// Since a model only records contexts shorter than its order at the
+    /// beginning of training sequences, backed-off choices favour the way
+    /// sequences begin. Without backoff, generation ends as soon as an unknown
+    /// context is reached.
+    ///
+    /// # Arguments
+    ///
+    /// `backoff` - Whether to back off to shorter contexts, which defaults to
+    /// `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(2, vec!["abc".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.given('x');
+    /// gen.given('a');
+    /// assert_eq!(gen.next(), None);
+    ///
+    /// gen.set_backoff(true);
+    /// assert_eq!(gen.collect::<String>(), "bc");
+    /// ```
+    pub fn set_backoff(&mut self, backoff: bool) {
+        self.backoff = backoff;
+    }
+
+    /// Begins each sequence from a randomly chosen context observed during
+    /// training, weighted by how often it occurred, rather than from the
+    /// beginning of a sequence. This suits models trained on what is
+    /// effectively one endless stream, whose sequence beginnings are not
+    /// meaningful.
+    ///
+    /// The symbols of the chosen context are treated as though they were
+    /// specified via [`given`](#method.given), so are not themselves
+    /// generated, but can be retrieved with [`context`](#method.context).
+    ///
+    /// # Arguments
+    ///
+    /// `random_start` - Whether to begin sequences from random contexts,
+    /// which defaults to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(2, vec!["abcdefgh".chars()]);
+    ///
+    /// let mut gen = markov::Generator::seeded(&model, 1);
+    /// gen.set_random_start(true);
+    /// gen.next();
+    /// assert_eq!(gen.context().len(), 2);
+    /// ```
+    pub fn set_random_start(&mut self, random_start: bool) {
+        self.start_contexts.clear();
+
+        if random_start {
+            let mut cumulative = 0;
+            for (context, count) in crate::read_model::full_contexts(&*self.model) {
+                cumulative += count;
+                self.start_contexts.push((context, cumulative));
+            }
+        }
+    }
+
+    /// Specifies symbols which end the current sequence when generated, in
+    /// addition to the end of a sequence as learned by the model, such as to
+    /// stop at a newline or a sentinel token. Stop symbols are not included in
+    /// the generated output.
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - The symbols at which to stop, replacing any previously
+    /// specified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["one\ntwo".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.stop_on(vec!['\n']);
+    /// assert_eq!(gen.collect::<String>(), "one");
+    /// ```
+    pub fn stop_on<I>(&mut self, symbols: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        self.stop_symbols = symbols
+            .into_iter()
+            .map(|s| self.model.normalize(s))
+            .collect();
+    }
+
+    /// Registers an observer which is invoked after each symbol is generated,
+    /// such as for logging, streaming output to a user interface, or
+    /// implementing custom stopping criteria.
+    ///
+    /// The observer returns whether generation should continue. Once it
+    /// returns `false`, the current sequence ends after the observed symbol.
+    ///
+    /// Symbols generated by
+    /// [`generate_sequence_with_backtracking`](#method.generate_sequence_with_backtracking)
+    /// are not observed, as they may later be retracted.
+    ///
+    /// # Arguments
+    ///
+    /// `observer` - The function to invoke with the context preceding each
+    /// generated symbol (as determined by the order of the model), the symbol
+    /// itself, and the probability with which it was sampled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abcd".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.on_emit(|context, symbol, p| {
+    ///     println!("{:?} -> {} ({})", context, symbol, p);
+    ///     *symbol != 'c'
+    /// });
+    ///
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn on_emit<F>(&mut self, observer: F)
+    where
+        F: FnMut(&[TSymbol], &TSymbol, f64) -> bool + Send + 'a,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Generates and returns the next symbol like
+    /// [`next`](#method.next), along with the probability with which it was
+    /// sampled (after any adjustments such as
+    /// [`set_temperature`](#method.set_temperature)).
+    ///
+    /// `None` is returned when the end of a sequence is reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// assert_eq!(gen.next_with_prob(), Some(('a', 1.0)));
+    /// assert_eq!(gen.next_with_prob(), Some(('b', 0.5)));
+    /// assert_eq!(gen.next_with_prob(), None);
+    /// ```
+    pub fn next_with_prob(&mut self) -> Option<(TSymbol, f64)> {
+        self.emit(true)
+    }
+
+    /// Samples a candidate for the next symbol without generating it, such
+    /// that repeated calls for the same context offer different candidates,
+    /// as though sampling without replacement. This allows a user interface
+    /// to offer alternative continuations without repeating itself.
+    ///
+    /// A candidate of `Some(None)` represents the end of the sequence. `None`
+    /// is returned once every candidate has been offered. A candidate can be
+    /// accepted by passing it to [`given`](#method.given), after which
+    /// candidates for the new context are offered afresh.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars(), "a".chars()]);
+    ///
+    /// let mut gen = markov::Generator::seeded(&model, 3);
+    /// gen.given('a');
+    ///
+    /// let mut options = vec![];
+    /// while let Some(option) = gen.next_alternative() {
+    ///     options.push(option);
+    /// }
+    ///
+    /// options.sort();
+    /// assert_eq!(options, vec![None, Some('b'), Some('c')]);
+    /// ```
+    pub fn next_alternative(&mut self) -> Option<Option<TSymbol>> {
+        if self.offered_context.as_ref().map(|c| c.symbols())
+            != Some(self.current_sequence.symbols())
+        {
+            self.offered.clear();
+            self.offered_context = Some(self.current_sequence.to_sequence());
+        }
+
+        let mut d = crate::read_model::distribution(&*self.model, self.current_sequence.symbols())?;
+        self.shape(&mut d);
+
+        let offered = &self.offered;
+        if !d.restrict(|s| !offered.contains(s)) {
+            return None;
+        }
+
+        let choice = d.sample(self.next_rand.next_f64())?;
+        self.offered.push(choice);
+        Some(choice)
+    }
+
+    /// Generates the remainder of the current sequence, then resets this
+    /// Generator so that the next symbol generated will be the beginning of a
+    /// new sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c']);
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate_sequence(&mut self) -> Vec<TSymbol> {
+        let sequence = self.by_ref().collect();
+        self.end();
+        sequence
+    }
+
+    /// Generates the remainder of the current sequence, stopping early if
+    /// `limit` symbols are generated first, then resets this Generator so
+    /// that the next symbol generated will be the beginning of a new
+    /// sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `limit` - The maximum number of symbols to generate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// assert_eq!(gen.generate_n(2), vec!['a', 'b']);
+    /// assert_eq!(gen.generate_n(5), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate_n(&mut self, limit: usize) -> Vec<TSymbol> {
+        let sequence = self.by_ref().take(limit).collect();
+        self.end();
+        sequence
+    }
+
+    /// Generates the remainder of the current sequence like
+    /// [`generate_sequence`](#method.generate_sequence), but backtracks to
+    /// resample earlier choices whenever generation reaches a dead end: a
+    /// context with no permissible next symbol before the minimum length set
+    /// by [`set_min_len`](#method.set_min_len) has been reached, or where the
+    /// [constraint](#method.set_constraint) rejects every candidate.
+    ///
+    /// Returns `None` if no acceptable sequence is found within the allowed
+    /// number of backtracks. Either way, this Generator is then reset so that
+    /// the next symbol generated will be the beginning of a new sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `max_backtracks` - The maximum number of earlier choices to resample
+    /// before giving up.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "cde".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_min_len(3);
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b']);
+    ///
+    /// assert_eq!(
+    ///     gen.generate_sequence_with_backtracking(10),
+    ///     Some(vec!['c', 'd', 'e'])
+    /// );
+    /// ```
+    pub fn generate_sequence_with_backtracking(
+        &mut self,
+        max_backtracks: usize,
+    ) -> Option<Vec<TSymbol>> {
+        let mut output = vec![];
+        let mut contexts = vec![];
+        let mut excluded: Vec<Vec<TSymbol>> = vec![vec![]];
+        let mut backtracks = 0;
+
+        loop {
+            match self.choose(&excluded[excluded.len() - 1], false) {
+                Some(Some(s)) => {
+                    contexts.push(self.current_sequence.clone());
+                    self.accept(s);
+                    output.push(s);
+                    excluded.push(vec![]);
+                }
+
+                Some(None) => break,
+
+                None => {
+                    if output.is_empty() || backtracks == max_backtracks {
+                        self.end();
+                        return None;
+                    }
+                    backtracks += 1;
+
+                    excluded.pop();
+                    let rejected = output.pop().unwrap();
+                    self.current_sequence = contexts.pop().unwrap();
+                    self.history.pop();
+                    self.generated -= 1;
+                    excluded.last_mut().unwrap().push(rejected);
+                }
+            }
+        }
+
+        self.end();
+        Some(output)
+    }
+
+    /// Caps the number of times the same run of `len` consecutive symbols may
+    /// appear in the current sequence, to break out of the cycles which
+    /// low-order models readily fall into.
+    ///
+    /// # Arguments
+    ///
+    /// `len` - The number of consecutive symbols in each run, or zero to
+    /// disable the cap.
+    ///
+    /// `max_repeats` - The maximum number of times each run may appear.
+    ///
+    /// `cycle` - How to avoid exceeding the cap.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abcabcabd".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.0);
+    /// gen.set_cycle_cap(3, 1, markov::Cycle::Stop);
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c', 'a', 'b']);
+    ///
+    /// gen.set_cycle_cap(3, 1, markov::Cycle::Resample);
+    /// assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c', 'a', 'b', 'd']);
+    /// ```
+    pub fn set_cycle_cap(&mut self, len: usize, max_repeats: usize, cycle: crate::Cycle) {
+        self.cycle_len = len;
+        self.cycle_max_repeats = max_repeats;
+        self.cycle = cycle;
+    }
+
+    /// Generates a sequence ending with the specified suffix, by extending it
+    /// backwards. This requires the Generator's model to have been trained on
+    /// reversed sequences, such as via
+    /// [`Model::train_reversed_from`](struct.Model.html#method.train_reversed_from).
+    ///
+    /// Any symbols previously specified via [`given`](#method.given) are
+    /// discarded. Afterwards, this Generator is reset so that the next symbol
+    /// generated will be the beginning of a new sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `suffix` - The symbols with which the generated sequence must end.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_reversed_from(1, vec!["light".chars(), "bright".chars()]);
+    ///
+    /// let mut gen = markov::Generator::new(&model, || 0.9);
+    /// let word: String = gen.generate_ending_with(&['g', 'h', 't']).into_iter().collect();
+    /// assert_eq!(word, "bright");
+    /// ```
+    pub fn generate_ending_with(&mut self, suffix: &[TSymbol]) -> Vec<TSymbol> {
+        self.end();
+        for symbol in suffix.iter().rev() {
+            self.given(*symbol);
+        }
+
+        let mut sequence = self.generate_sequence();
+        sequence.reverse();
+        sequence.extend(suffix.iter().map(|s| self.model.normalize(*s)));
+        sequence
+    }
+
+    /// Converts this Generator into an asynchronous stream of the symbols it
+    /// generates, which ends when the end of a sequence is reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::stream::StreamExt;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["abc".chars()]);
+    ///
+    /// let stream = markov::Generator::new(&model, || 0.0).into_stream();
+    /// let text: String = futures::executor::block_on(stream.collect());
+    /// assert_eq!(text, "abc");
+    /// ```
+    #[cfg(feature = "stream")]
+    pub fn into_stream(self) -> crate::GeneratorStream<'a, TSymbol, R, M> {
+        crate::GeneratorStream::new(self)
+    }
+
+    /// Resets this Generator so that the next symbol generated will be the
+    /// beginning of a sequence.
+    pub fn end(&mut self) {
+        self.current_sequence.clear();
+        self.history.clear();
+        self.generated = 0;
+        self.stopped = false;
+        self.offered_context = None;
+    }
+}
+
+impl<'a, TSymbol, R, M> Generator<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
+{
+    fn from_model_ref(model: ModelRef<'a, M>, rand_source: R) -> Generator<'a, TSymbol, R, M> {
+        let order = model.order();
+        Generator {
+            model,
+            current_sequence: crate::Window::empty(order),
+            next_rand: rand_source,
+            temperature: 1.0,
+            top_k: None,
+            top_p: None,
+            repetition_window: 0,
+            repetition_penalty: 1.0,
+            history: vec![],
+            generated: 0,
+            min_len: 0,
+            max_len: None,
+            overflow: crate::Overflow::Truncate,
+            constraint: None,
+            backoff: false,
+            cycle_len: 0,
+            cycle_max_repeats: 0,
+            cycle: crate::Cycle::Stop,
+            mixture: vec![],
+            observer: None,
+            last_probability: 0.0,
+            stopped: false,
+            stop_symbols: Default::default(),
+            start_contexts: vec![],
+            offered: vec![],
+            offered_context: None,
+        }
+    }
+
+    fn emit(&mut self, with_probability: bool) -> Option<(TSymbol, f64)> {
+        match self.choose(&[], with_probability) {
+            Some(Some(s)) => {
+                if let Some(observer) = &mut self.observer {
+                    let context = self.current_sequence.symbols();
+                    if !observer(context, &s, self.last_probability) {
+                        self.stopped = true;
+                    }
+                }
+                self.accept(s);
+                Some((s, self.last_probability))
+            }
+            _ => None,
+        }
+    }
+
+    // Chooses the next symbol, returning Some(None) if the sequence ends, or
+    // None if it reaches a dead end from which no choice is possible. The
+    // probability of the choice is only recorded if requested, as it is not
+    // available when sampling directly from the model.
+    fn choose(&mut self, excluded: &[TSymbol], with_probability: bool) -> Option<Option<TSymbol>> {
+        if self.stopped {
+            return Some(None);
+        }
+
+        if let Some(max_len) = self.max_len {
+            if self.generated >= max_len {
+                return Some(None);
+            }
+        }
+
+        match self.sample_next(excluded, with_probability) {
+            Some(Some(s)) if self.stop_symbols.contains(&s) => Some(None),
+            Some(Some(s)) if self.cycle == crate::Cycle::Stop && self.exceeds_cycle_cap(s) => {
+                Some(None)
+            }
+            choice => choice,
+        }
+    }
+
+    fn sample_next(
+        &mut self,
+        excluded: &[TSymbol],
+        with_probability: bool,
+    ) -> Option<Option<TSymbol>> {
+        let forcing_end =
+            self.overflow == crate::Overflow::ForceEnd && self.max_len == Some(self.generated + 1);
+
+        let suppressing_end = self.generated < self.min_len;
+
+        if !self.start_contexts.is_empty() && self.history.is_empty() {
+            self.start_from_random_context();
+        }
+
+        if self.backoff {
+            let len =
+                crate::read_model::back_off(&*self.model, self.current_sequence.symbols()).len();
+            self.current_sequence.keep_last(len);
+        }
+
+        let sample_value = self.next_rand.next_f64();
+
+        if self.is_unshaped()
+            && !with_probability
+            && excluded.is_empty()
+            && !forcing_end
+            && !suppressing_end
+        {
+            return Some(
+                crate::read_model::sample(
+                    &*self.model,
+                    self.current_sequence.symbols(),
+                    sample_value,
+                )
+                .copied(),
+            );
+        }
+
+        let mut d = if self.mixture.is_empty() {
+            crate::read_model::distribution(&*self.model, self.current_sequence.symbols())?
+        } else {
+            self.mixed_distribution()?
+        };
+        self.shape(&mut d);
+
+        if let Some(constraint) = &self.constraint {
+            let history = &self.history;
+            if !d.restrict(|s| s.map_or(true, |s| constraint(&s, history))) {
+                return None;
+            }
+        }
+        if !excluded.is_empty() && !d.restrict(|s| s.map_or(true, |s| !excluded.contains(&s))) {
+            return None;
+        }
+        if self.cycle == crate::Cycle::Resample
+            && self.cycle_len > 0
+            && !d.restrict(|s| s.map_or(true, |s| !self.exceeds_cycle_cap(s)))
+        {
+            return None;
+        }
+        if suppressing_end && !d.restrict(|s| s.is_some()) {
+            return None;
+        }
+        if forcing_end {
+            self.restrict_to_endings(&mut d);
+        }
+
+        let choice = d.sample(sample_value)?;
+        self.last_probability = d.probability_of(&choice);
+        Some(choice)
+    }
+
+    fn start_from_random_context(&mut self) {
+        let total = self.start_contexts[self.start_contexts.len() - 1].1;
+        let value = crate::random::scale(self.next_rand.next_f64(), total);
+
+        let index = self
+            .start_contexts
+            .partition_point(|(_, cumulative)| *cumulative <= value)
+            .min(self.start_contexts.len() - 1);
+
+        self.current_sequence
+            .set(self.start_contexts[index].0.symbols());
+        self.history
+            .extend_from_slice(self.start_contexts[index].0.symbols());
+    }
+
+    fn mixed_distribution(&self) -> Option<crate::Distribution<TSymbol>> {
+        let parts: Vec<_> = self
+            .mixture
+            .iter()
+            .filter_map(|(model, weight)| {
+                let context = crate::Sequence::from_slice(&self.history, model.order())
+                    .map(|s| model.normalize(s));
+                crate::read_model::distribution(*model, context.symbols()).map(|d| (d, *weight))
+            })
+            .collect();
+
+        if parts.is_empty() {
+            return None;
+        }
+        Some(crate::Distribution::mixture(parts))
+    }
+
+    fn exceeds_cycle_cap(&self, symbol: TSymbol) -> bool {
+        if self.cycle_len == 0 || self.history.len() + 1 < self.cycle_len {
+            return false;
+        }
+
+        let prefix = &self.history[(self.history.len() + 1 - self.cycle_len)..];
+        let repeats = self
+            .history
+            .windows(self.cycle_len)
+            .filter(|w| w[..w.len() - 1] == *prefix && w[w.len() - 1] == symbol)
+            .count();
+
+        repeats >= self.cycle_max_repeats
+    }
+
+    fn accept(&mut self, symbol: TSymbol) {
+        self.current_sequence.push(symbol);
+        self.history.push(symbol);
+        self.generated += 1;
+    }
+
+    fn is_unshaped(&self) -> bool {
+        self.temperature == 1.0
+            && self.top_k.is_none()
+            && self.top_p.is_none()
+            && (self.repetition_window == 0 || self.repetition_penalty == 1.0)
+            && self.constraint.is_none()
+            && (self.cycle_len == 0 || self.cycle == crate::Cycle::Stop)
+            && self.mixture.is_empty()
+    }
+
+    fn shape(&self, distribution: &mut crate::Distribution<TSymbol>) {
+        if self.repetition_window > 0 && self.repetition_penalty != 1.0 {
+            let start = self.history.len().saturating_sub(self.repetition_window);
+            let recent = &self.history[start..];
+            distribution.penalize(self.repetition_penalty, |s| recent.contains(s));
+        }
+        if self.temperature != 1.0 {
+            distribution.apply_temperature(self.temperature);
+        }
+        if let Some(k) = self.top_k {
+            distribution.truncate_top_k(k);
+        }
+        if let Some(p) = self.top_p {
+            distribution.truncate_top_p(p);
+        }
+    }
+
+    fn restrict_to_endings(&self, distribution: &mut crate::Distribution<TSymbol>) {
+        distribution.restrict(|s| match s {
+            Some(s) => {
+                let mut next_sequence = self.current_sequence.clone();
+                next_sequence.push(*s);
+                crate::read_model::probability(&*self.model, next_sequence.symbols(), &None) > 0.0
+            }
+            None => true,
+        });
+    }
+}
+
+impl<TSymbol, R, M> Generator<'static, TSymbol, R, M>
 where
-    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq + 'static,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
 {
-    model: &'a crate::Model<TSymbol>,
-    current_sequence: crate::Sequence<TSymbol>,
-    next_rand: Box<dyn FnMut() -> f64>,
+    /// Creates a Generator which shares ownership of the specified model, so
+    /// that it is not tied to the lifetime of a borrow. This allows the
+    /// Generator to be stored in long-lived structures or moved to other
+    /// threads.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The shared Markov model to base generated data on.
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0) range,
+    /// used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    /// use std::sync::Arc;
+    ///
+    /// let model = Arc::new(markov::Model::train_from(1, vec!["abc".chars()]));
+    ///
+    /// let rng = markov::RngSource(rand::rngs::StdRng::seed_from_u64(42));
+    /// let mut gen = markov::Generator::owned(model, rng);
+    ///
+    /// let output = std::thread::spawn(move || gen.generate_sequence())
+    ///     .join()
+    ///     .unwrap();
+    /// assert_eq!(output, vec!['a', 'b', 'c']);
+    /// ```
+    pub fn owned(model: std::sync::Arc<M>, rand_source: R) -> Generator<'static, TSymbol, R, M> {
+        Generator::from_model_ref(ModelRef::Shared(model), rand_source)
+    }
 }
 
-impl<'a, TSymbol> Generator<'a, TSymbol>
+impl<'a, TSymbol, G, M> Generator<'a, TSymbol, crate::RngSource<G>, M>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    G: rand::Rng,
+    M: crate::ReadModel<TSymbol>,
 {
-    /// Creates a Generator which uses the specified model.
+    /// Creates a Generator which uses the specified model and draws random
+    /// values from a [`rand::Rng`](https://docs.rs/rand).
     ///
     /// # Arguments
     ///
     /// `model` - The Markov model to base generated data on.
     ///
-    /// `rand_source` - A function for returning values in the [0.0, 1.0) range,
-    /// used to generate the output.
+    /// `rng` - The random number generator used to generate the output.
     ///
     /// # Example
     ///
     /// ```
-    /// use rand::Rng;
+    /// use rand::SeedableRng;
     ///
     /// let model = markov::Model::<i32>::empty(1);
     ///
-    /// let mut rng = rand::thread_rng();
-    /// let mut gen = markov::Generator::new(&model, Box::new(move || rng.gen::<f64>()));
+    /// let rng = rand::rngs::StdRng::seed_from_u64(42);
+    /// let mut gen = markov::Generator::with_rng(&model, rng);
     /// ```
-    pub fn new(
-        model: &'a crate::Model<TSymbol>,
-        rand_source: Box<dyn FnMut() -> f64>,
-    ) -> Generator<'a, TSymbol> {
-        Generator {
-            model,
-            current_sequence: crate::Sequence::empty(),
-            next_rand: rand_source,
-        }
+    pub fn with_rng(model: &'a M, rng: G) -> Generator<'a, TSymbol, crate::RngSource<G>, M> {
+        Generator::new(model, crate::RngSource(rng))
     }
+}
 
-    /// Resets this Generator so that the next symbol generated will be the
-    /// beginning of a sequence.
-    pub fn end(&mut self) {
-        self.current_sequence = crate::Sequence::empty();
+impl<'a, TSymbol, M> Generator<'a, TSymbol, crate::SeededSource, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: crate::ReadModel<TSymbol>,
+{
+    /// Creates a Generator which uses the specified model and a pseudorandom
+    /// number generator initialised from a seed, so that the same seed always
+    /// produces the same output. This is useful for tests and replays.
+    ///
+    /// Values are drawn from a [SeededSource](struct.SeededSource.html), whose
+    /// algorithm is fixed, so output for a seed does not change between
+    /// versions of this crate or of `rand`.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to base generated data on.
+    ///
+    /// `seed` - The seed for the random number generator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let first: String = markov::Generator::seeded(&model, 7).collect();
+    /// let second: String = markov::Generator::seeded(&model, 7).collect();
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn seeded(model: &'a M, seed: u64) -> Generator<'a, TSymbol, crate::SeededSource, M> {
+        Generator::new(model, crate::SeededSource::new(seed))
     }
+}
+
+impl<'a, TSymbol, R, M> Iterator for Generator<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
+{
+    type Item = TSymbol;
 
     /// Generates and returns the next symbol based on the previously generated
     /// symbols.
@@ -70,26 +1213,25 @@ where
     ///      print!(" {}", symbol);
     /// }
     /// println!();
+    ///
+    /// gen.end();
+    /// let sequence: Vec<i32> = gen.by_ref().take(10).collect();
     /// ```
-    pub fn next(&mut self) -> Option<&TSymbol> {
-        match self
-            .model
-            .sample(&self.current_sequence, (self.next_rand)())
-        {
-            Some(s) => {
-                self.current_sequence = self.model.advance_sequence(&self.current_sequence, *s);
-                Some(s)
-            }
-            None => None,
-        }
+    fn next(&mut self) -> Option<TSymbol> {
+        self.emit(self.observer.is_some()).map(|(s, _)| s)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::cycle::Cycle;
     use crate::generator::Generator;
     use crate::model::Model;
+    use crate::overflow::Overflow;
+    use crate::random::RngSource;
     use crate::sequence::Sequence;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn it_generates_sequences() {
@@ -111,20 +1253,455 @@ mod test {
         let seq = model.advance_sequence(&seq, "lazy");
         model.add(&seq, Some("dog"));
 
-        let mut gen = Generator::new(&model, Box::new(|| 0.0));
+        let mut gen: Generator<_> = Generator::new(&model, Box::new(|| 0.0));
 
-        assert_eq!(gen.next(), Some(&"the"));
-        assert_eq!(gen.next(), Some(&"quick"));
-        assert_eq!(gen.next(), Some(&"brown"));
-        assert_eq!(gen.next(), Some(&"fox"));
+        assert_eq!(gen.next(), Some("the"));
+        assert_eq!(gen.next(), Some("quick"));
+        assert_eq!(gen.next(), Some("brown"));
+        assert_eq!(gen.next(), Some("fox"));
         assert_eq!(gen.next(), None);
 
         gen.end();
         gen.next_rand = Box::new(|| 0.5);
 
-        assert_eq!(gen.next(), Some(&"the"));
-        assert_eq!(gen.next(), Some(&"lazy"));
-        assert_eq!(gen.next(), Some(&"dog"));
+        assert_eq!(gen.next(), Some("the"));
+        assert_eq!(gen.next(), Some("lazy"));
+        assert_eq!(gen.next(), Some("dog"));
+        assert_eq!(gen.next(), None);
+    }
+
+    #[test]
+    fn it_works_with_iterator_adapters() {
+        let model = Model::train_from(1, vec!["abc".chars()]);
+
+        let gen = Generator::new(&model, Box::new(|| 0.0));
+        assert_eq!(gen.collect::<String>(), "abc");
+
+        let gen = Generator::new(&model, Box::new(|| 0.0));
+        assert_eq!(gen.take(2).collect::<Vec<_>>(), vec!['a', 'b']);
+    }
+
+    #[test]
+    fn it_generates_reproducibly_from_seeded_rngs() {
+        let model = Model::train_from(1, vec!["ab".chars(), "ac".chars(), "ad".chars()]);
+
+        let a: Vec<String> = (0..10)
+            .map(|i| Generator::with_rng(&model, StdRng::seed_from_u64(i)).collect())
+            .collect();
+        let b: Vec<String> = (0..10)
+            .map(|i| Generator::new(&model, RngSource(StdRng::seed_from_u64(i))).collect())
+            .collect();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn it_generates_fixed_output_for_a_seed() {
+        let model = Model::train_from(1, vec!["abcab".chars(), "bcaac".chars(), "cbba".chars()]);
+
+        let output: Vec<String> = (0..4)
+            .map(|i| Generator::seeded(&model, i).collect())
+            .collect();
+
+        assert_eq!(output, vec!["bbcacb", "c", "acac", "c"]);
+    }
+
+    #[test]
+    fn it_continues_from_given_symbols() {
+        let model = Model::train_from(2, vec!["abcd".chars(), "xbce".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.5);
+        gen.given('x');
+        gen.given('b');
+        assert_eq!(gen.collect::<String>(), "ce");
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.given('z');
+        assert_eq!(gen.next(), None);
+    }
+
+    #[test]
+    fn it_reshapes_sampling_by_temperature() {
+        let model = Model::train_from(0, vec!["aaab".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.55);
+        assert_eq!(gen.next(), Some('a'));
+
+        gen.set_temperature(100.0);
+        assert_eq!(gen.next(), Some('b'));
+
+        gen.set_temperature(0.0);
+        assert_eq!(gen.next(), Some('a'));
+    }
+
+    #[test]
+    fn it_restricts_sampling_to_top_k_symbols() {
+        let model = Model::train_from(0, vec!["aaabbc".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.99);
+        assert_eq!(gen.next(), None);
+
+        gen.set_top_k(Some(2));
+        assert_eq!(gen.next(), Some('b'));
+
+        gen.set_top_k(Some(1));
+        assert_eq!(gen.next(), Some('a'));
+    }
+
+    #[test]
+    fn it_restricts_sampling_to_top_p_symbols() {
+        let model = Model::train_from(0, vec!["aaabbc".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.99);
+        gen.set_top_p(Some(0.7));
+        assert_eq!(gen.next(), Some('b'));
+
+        gen.set_top_p(Some(0.4));
+        assert_eq!(gen.next(), Some('a'));
+    }
+
+    #[test]
+    fn it_penalizes_recently_repeated_symbols() {
+        let model = Model::train_from(1, vec!["aaaaab".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.5);
+        assert_eq!(gen.by_ref().take(3).collect::<String>(), "aaa");
+
+        gen.end();
+        gen.set_repetition_penalty(2, 10.0);
+        assert_eq!(gen.by_ref().take(3).collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn it_stops_at_the_maximum_length() {
+        let model = Model::train_from(1, vec!["abcab".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.by_ref().take(7).collect::<String>(), "abcabca");
+
+        gen.end();
+        gen.set_max_len(Some(5), Overflow::Truncate);
+        assert_eq!(gen.by_ref().collect::<String>(), "abcab");
+        assert_eq!(gen.next(), None);
+
+        gen.end();
+        gen.set_max_len(Some(4), Overflow::ForceEnd);
+        assert_eq!(gen.by_ref().collect::<String>(), "abca");
+
+        gen.end();
+        gen.set_max_len(Some(3), Overflow::ForceEnd);
+        assert_eq!(gen.by_ref().collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn it_suppresses_endings_before_the_minimum_length() {
+        let model = Model::train_from(1, vec!["ab".chars(), "ab".chars(), "abc".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.by_ref().collect::<String>(), "ab");
+
+        gen.end();
+        gen.set_min_len(3);
+        assert_eq!(gen.by_ref().collect::<String>(), "abc");
+
+        gen.end();
+        gen.set_min_len(5);
+        assert_eq!(gen.by_ref().collect::<String>(), "abc");
+    }
+
+    #[test]
+    fn it_resamples_among_symbols_allowed_by_a_constraint() {
+        let model = Model::train_from(1, vec!["abc".chars(), "abd".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.set_constraint(|s, _| *s != 'c');
+        assert_eq!(gen.by_ref().collect::<String>(), "abd");
+
+        gen.end();
+        gen.set_constraint(|s, _| *s != 'b');
+        assert_eq!(gen.by_ref().collect::<String>(), "a");
+
+        gen.end();
+        gen.clear_constraint();
+        assert_eq!(gen.by_ref().collect::<String>(), "abc");
+    }
+
+    #[test]
+    fn it_generates_whole_sequences_and_resets() {
+        let model = Model::train_from(2, vec!["abcd".chars(), "xbce".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.5);
+        gen.given('x');
+        gen.given('b');
+        assert_eq!(gen.generate_sequence(), vec!['c', 'e']);
+        assert_eq!(gen.generate_sequence(), vec!['x', 'b', 'c', 'e']);
+        assert_eq!(gen.generate_n(2), vec!['x', 'b']);
+        assert_eq!(gen.generate_n(0), vec![]);
+        assert_eq!(gen.generate_n(1), vec!['x']);
+    }
+
+    #[test]
+    fn it_generates_from_shared_models() {
+        let model = std::sync::Arc::new(Model::train_from(1, vec!["abc".chars()]));
+
+        let mut gen = Generator::owned(model.clone(), || 0.0);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c']);
+
+        drop(gen);
+        assert_eq!(std::sync::Arc::strong_count(&model), 1);
+    }
+
+    #[test]
+    fn it_can_be_moved_between_threads() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        let model = Model::train_from(1, vec!["abc".chars()]);
+
+        assert_send(&Generator::seeded(&model, 42));
+        assert_send(&Generator::new(&model, || 0.0));
+
+        let source: Box<dyn FnMut() -> f64 + Send> = Box::new(|| 0.0);
+        let mut gen = Generator::new(&model, source);
+        let output = std::thread::scope(|s| s.spawn(move || gen.generate_sequence()).join());
+        assert_eq!(output.unwrap(), vec!['a', 'b', 'c']);
+    }
+
+    #[test]
+    fn it_backs_off_from_unknown_contexts() {
+        let model = Model::train_from(2, vec!["abc".chars(), "bd".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.set_backoff(true);
+        gen.given('x');
+        gen.given('y');
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c']);
+
+        gen.given('x');
+        gen.given('b');
+        assert_eq!(gen.generate_sequence(), vec!['d']);
+    }
+
+    #[test]
+    fn it_backtracks_from_dead_ends() {
+        let model = Model::train_from(1, vec!["abx".chars(), "ab".chars(), "acde".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.set_min_len(4);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'x']);
+
+        assert_eq!(
+            gen.generate_sequence_with_backtracking(2),
+            Some(vec!['a', 'c', 'd', 'e'])
+        );
+        assert_eq!(gen.generate_sequence_with_backtracking(1), None);
+
+        gen.set_min_len(5);
+        assert_eq!(gen.generate_sequence_with_backtracking(10), None);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'x']);
+    }
+
+    #[test]
+    fn it_caps_repeated_runs_of_symbols() {
+        let model = Model::train_from(1, vec!["abcabcabd".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.generate_n(10), "abcabcabca".chars().collect::<Vec<_>>());
+
+        gen.set_cycle_cap(3, 1, Cycle::Stop);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c', 'a', 'b']);
+
+        gen.set_cycle_cap(3, 1, Cycle::Resample);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b', 'c', 'a', 'b', 'd']);
+
+        gen.set_cycle_cap(0, 1, Cycle::Stop);
+        assert_eq!(gen.generate_n(10).len(), 10);
+    }
+
+    #[test]
+    fn it_samples_from_a_mixture_of_models() {
+        let first = Model::train_from(1, vec!["ab".chars()]);
+        let second = Model::train_from(2, vec!["ac".chars(), "xyz".chars()]);
+
+        let mut gen = Generator::ensemble(&[(&first, 0.5), (&second, 0.5)], || 0.0);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b']);
+
+        let mut gen = Generator::ensemble(&[(&first, 0.5), (&second, 0.5)], || 0.99);
+        assert_eq!(gen.generate_sequence(), vec!['x', 'y', 'z']);
+
+        let mut gen = Generator::ensemble(&[(&first, 0.5), (&second, 0.5)], || 0.6);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'c']);
+    }
+
+    #[test]
+    fn it_notifies_observers_of_generated_symbols() {
+        let model = Model::train_from(1, vec!["ab".chars(), "ac".chars(), "ac".chars()]);
+        let mut observed = vec![];
+
+        {
+            let mut gen = Generator::new(&model, || 0.5);
+            gen.on_emit(|context, symbol, p| {
+                observed.push((context.to_vec(), *symbol, p));
+                true
+            });
+            assert_eq!(gen.generate_sequence(), vec!['a', 'c']);
+        }
+
+        assert_eq!(observed.len(), 2);
+        assert_eq!(observed[0], (vec![], 'a', 1.0));
+        assert_eq!(observed[1].0, vec!['a']);
+        assert_eq!(observed[1].1, 'c');
+        assert!((observed[1].2 - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_stops_when_an_observer_declines_to_continue() {
+        let model = Model::train_from(1, vec!["abcd".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.on_emit(|_, symbol, _| *symbol != 'b');
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b']);
+        assert_eq!(gen.generate_sequence(), vec!['a', 'b']);
+    }
+
+    #[test]
+    fn it_stops_on_specified_symbols() {
+        let model = Model::train_from(1, vec!["a.b;c".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.stop_on(vec![';', '.']);
+        assert_eq!(gen.generate_sequence(), vec!['a']);
+
+        gen.stop_on(vec![';']);
+        assert_eq!(gen.generate_sequence(), vec!['a', '.', 'b']);
+
+        gen.stop_on(vec![]);
+        assert_eq!(gen.generate_sequence().len(), 5);
+    }
+
+    #[test]
+    fn it_saves_and_restores_context() {
+        let model = Model::train_from(2, vec!["abcde".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.context(), &[] as &[char]);
+        assert_eq!(gen.generate_n(1), vec!['a']);
+
+        assert_eq!(gen.by_ref().take(3).collect::<String>(), "abc");
+        assert_eq!(gen.context(), &['b', 'c']);
+
+        let saved = gen.context().to_vec();
+        gen.set_context(&['x']);
+        assert_eq!(gen.context(), &['x']);
+
+        gen.set_context(&saved);
+        assert_eq!(gen.collect::<String>(), "de");
+    }
+
+    #[test]
+    fn it_accepts_random_sources_borrowing_local_state() {
+        let model = Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+        let values = [0.0, 0.9];
+        let mut draws = 0;
+
+        {
+            let next_value = || {
+                draws += 1;
+                values[draws % 2]
+            };
+            let mut gen: Generator<_> = Generator::new(&model, Box::new(next_value));
+            assert_eq!(gen.generate_sequence(), vec!['a', 'b']);
+        }
+
+        {
+            let mut gen = Generator::new(&model, || {
+                draws += 1;
+                values[draws % 2]
+            });
+            assert_eq!(gen.generate_sequence(), vec!['a', 'c']);
+        }
+
+        assert_eq!(draws, 6);
+    }
+
+    #[test]
+    fn it_returns_probabilities_of_generated_symbols() {
+        let model = Model::train_from(0, vec!["aaab".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.next_with_prob(), Some(('a', 0.6)));
+
+        gen.set_top_k(Some(1));
+        assert_eq!(gen.next_with_prob(), Some(('a', 1.0)));
+    }
+
+    #[test]
+    fn it_generates_backwards_from_a_suffix() {
+        let model =
+            Model::train_reversed_from(2, vec!["cat".chars(), "hat".chars(), "dog".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.generate_ending_with(&['a', 't']), vec!['c', 'a', 't']);
+        assert_eq!(gen.generate_ending_with(&['g']), vec!['d', 'o', 'g']);
+        assert_eq!(gen.generate_sequence(), vec!['t', 'a', 'c']);
+    }
+
+    #[test]
+    fn it_starts_from_random_contexts_weighted_by_frequency() {
+        let model = Model::train_from(1, vec!["aababc".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        gen.set_random_start(true);
+        assert_eq!(gen.generate_n(2), vec!['a', 'a']);
+
+        let mut gen = Generator::new(&model, || 0.99);
+        gen.set_random_start(true);
         assert_eq!(gen.next(), None);
+        assert_eq!(gen.context(), &['c']);
+
+        gen.end();
+        gen.set_random_start(false);
+        assert_eq!(gen.context(), &[] as &[char]);
+        assert_eq!(gen.next(), Some('a'));
+    }
+
+    #[test]
+    fn it_offers_alternatives_without_replacement() {
+        let model = Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert_eq!(gen.next_alternative(), Some(Some('a')));
+        assert_eq!(gen.next_alternative(), None);
+
+        gen.given('a');
+        assert_eq!(gen.next_alternative(), Some(Some('b')));
+        assert_eq!(gen.next_alternative(), Some(Some('c')));
+        assert_eq!(gen.next_alternative(), None);
+
+        gen.given('b');
+        assert_eq!(gen.next_alternative(), Some(None));
+        assert_eq!(gen.next_alternative(), None);
+
+        gen.end();
+        assert_eq!(gen.next_alternative(), Some(Some('a')));
+    }
+
+    #[test]
+    fn it_distinguishes_sequence_ends_from_unknown_contexts() {
+        let model = Model::train_from(2, vec!["abc".chars()]);
+        let other = Model::train_from(1, vec!["xy".chars()]);
+
+        let mut gen = Generator::new(&model, || 0.0);
+        assert!(gen.knows_context());
+        gen.given('x');
+        assert!(!gen.knows_context());
+
+        gen.set_backoff(true);
+        assert!(gen.knows_context());
+
+        let mut gen = Generator::ensemble(&[(&model, 0.5), (&other, 0.5)], || 0.0);
+        gen.given('x');
+        assert!(gen.knows_context());
+        gen.given('z');
+        assert!(!gen.knows_context());
     }
 }
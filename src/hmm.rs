@@ -0,0 +1,6 @@
+//! Hidden Markov models, whose states are not observed directly but through
+//! the symbols they emit, for tasks such as tagging and segmentation.
+
+mod hidden_markov_model;
+
+pub use self::hidden_markov_model::HiddenMarkovModel;
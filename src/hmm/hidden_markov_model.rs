@@ -0,0 +1,323 @@
+/// A first order hidden Markov model, in which each hidden state is followed
+/// by another with some probability, and emits an observed symbol with some
+/// probability.
+///
+/// Models are trained on sequences whose states are known, such as tagged
+/// text, and can then [decode](#method.decode) the most likely states behind
+/// a sequence of observations alone.
+pub struct HiddenMarkovModel<TState, TSymbol>
+where
+    TState: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    states: crate::interner::Interner<TState>,
+    // The IDs of the states which begin each sequence, where `None` refers to
+    // an empty sequence
+    initial: crate::Table<u32>,
+    // The IDs of the states following each state, indexed by state ID, where
+    // `None` refers to the end of a sequence
+    transitions: Vec<crate::Table<u32>>,
+    // The symbols emitted by each state, indexed by state ID
+    emissions: Vec<crate::Table<TSymbol>>,
+}
+
+impl<TState, TSymbol> HiddenMarkovModel<TState, TSymbol>
+where
+    TState: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Creates an empty HiddenMarkovModel.
+    pub fn empty() -> HiddenMarkovModel<TState, TSymbol> {
+        HiddenMarkovModel {
+            states: crate::interner::Interner::empty(),
+            initial: crate::Table::with_hasher(Default::default()),
+            transitions: vec![],
+            emissions: vec![],
+        }
+    }
+
+    /// Creates a HiddenMarkovModel trained on the specified sequences.
+    ///
+    /// # Arguments
+    ///
+    /// `sequences` - The training sequences, as per
+    /// [`train_sequence`](#method.train_sequence).
+    pub fn train_from<I, S>(sequences: I) -> HiddenMarkovModel<TState, TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = (TState, TSymbol)>,
+    {
+        let mut model = HiddenMarkovModel::empty();
+        for sequence in sequences {
+            model.train_sequence(sequence);
+        }
+        model
+    }
+
+    /// Trains the model on a complete sequence, including its end.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - Each state of the sequence, along with the symbol it
+    /// emitted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::hmm::HiddenMarkovModel::empty();
+    /// model.train_sequence(vec![("DET", "the"), ("NOUN", "dog")]);
+    ///
+    /// assert_eq!(model.transition_probability(Some("DET"), Some("NOUN")), 1.0);
+    /// assert_eq!(model.emission_probability("NOUN", "dog"), 1.0);
+    /// ```
+    pub fn train_sequence<I>(&mut self, sequence: I)
+    where
+        I: IntoIterator<Item = (TState, TSymbol)>,
+    {
+        let mut previous: Option<u32> = None;
+
+        for (state, symbol) in sequence {
+            let id = self.states.intern_value(state);
+            if id as usize == self.transitions.len() {
+                self.transitions
+                    .push(crate::Table::with_hasher(Default::default()));
+                self.emissions
+                    .push(crate::Table::with_hasher(Default::default()));
+            }
+
+            self.table_mut(previous)
+                .add_weighted_with(Some(id), 1, crate::CountOverflow::Saturate);
+            self.emissions[id as usize].add_weighted_with(
+                Some(symbol),
+                1,
+                crate::CountOverflow::Saturate,
+            );
+            previous = Some(id);
+        }
+
+        self.table_mut(previous)
+            .add_weighted_with(None, 1, crate::CountOverflow::Saturate);
+    }
+
+    /// Returns the number of distinct hidden states in this model.
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the probability of one state being followed by another.
+    ///
+    /// # Arguments
+    ///
+    /// `from` - The earlier state, where `None` refers to the beginning of a
+    /// sequence.
+    ///
+    /// `to` - The later state, where `None` refers to the end of a sequence.
+    pub fn transition_probability(&self, from: Option<TState>, to: Option<TState>) -> f64 {
+        let table = match from {
+            Some(state) => match self.states.get(&state) {
+                Some(id) => &self.transitions[id as usize],
+                None => return 0.0,
+            },
+            None => &self.initial,
+        };
+
+        match to {
+            Some(state) => match self.states.get(&state) {
+                Some(id) => table.probability_of(&Some(id)),
+                None => 0.0,
+            },
+            None => table.probability_of(&None),
+        }
+    }
+
+    /// Returns the probability of a state emitting a symbol.
+    ///
+    /// # Arguments
+    ///
+    /// `state` - The state emitting the symbol.
+    ///
+    /// `symbol` - The symbol emitted.
+    pub fn emission_probability(&self, state: TState, symbol: TSymbol) -> f64 {
+        match self.states.get(&state) {
+            Some(id) => self.emissions[id as usize].probability_of(&Some(symbol)),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most likely sequence of hidden states to have emitted a
+    /// complete sequence of observed symbols, using the Viterbi algorithm.
+    ///
+    /// The sequence's end is taken into account, so only states which have
+    /// ended a training sequence can end the decoded one. Ties between equally
+    /// likely paths are broken in favour of states first seen in training.
+    /// `None` is returned if no sequence of states could have emitted the
+    /// symbols.
+    ///
+    /// # Arguments
+    ///
+    /// `observations` - The observed symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::hmm::HiddenMarkovModel::train_from(vec![
+    ///     vec![("DET", "the"), ("NOUN", "dog"), ("VERB", "barks")],
+    ///     vec![("DET", "the"), ("NOUN", "cat"), ("VERB", "sleeps")],
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     model.decode(&["the", "cat", "barks"]),
+    ///     Some(vec!["DET", "NOUN", "VERB"])
+    /// );
+    /// assert_eq!(model.decode(&["the", "fish"]), None);
+    /// ```
+    pub fn decode(&self, observations: &[TSymbol]) -> Option<Vec<TState>> {
+        let (first, rest) = match observations.split_first() {
+            Some(split) => split,
+            None if self.initial.probability_of(&None) > 0.0 => return Some(vec![]),
+            None => return None,
+        };
+
+        // The log probability of the likeliest path ending in each state, and
+        // for each observation after the first, the state preceding each state
+        // on its likeliest path
+        let mut scores = vec![f64::NEG_INFINITY; self.state_count()];
+        let mut predecessors: Vec<Vec<u32>> = Vec::with_capacity(rest.len());
+
+        for (id, score) in Self::log_transitions(&self.initial) {
+            scores[id as usize] = score + self.log_emission(id, first);
+        }
+
+        for symbol in rest {
+            let mut next = vec![f64::NEG_INFINITY; self.state_count()];
+            let mut from = vec![0; self.state_count()];
+
+            for (i, score) in scores.iter().enumerate() {
+                if *score == f64::NEG_INFINITY {
+                    continue;
+                }
+
+                for (id, transition) in Self::log_transitions(&self.transitions[i]) {
+                    let candidate = score + transition;
+                    if candidate > next[id as usize] {
+                        next[id as usize] = candidate;
+                        from[id as usize] = i as u32;
+                    }
+                }
+            }
+
+            for (id, score) in next.iter_mut().enumerate() {
+                *score += self.log_emission(id as u32, symbol);
+            }
+
+            scores = next;
+            predecessors.push(from);
+        }
+
+        let mut best: Option<(u32, f64)> = None;
+        for (i, score) in scores.iter().enumerate() {
+            let score = score + self.transitions[i].probability_of(&None).log2();
+            if score > f64::NEG_INFINITY && best.map_or(true, |(_, s)| score > s) {
+                best = Some((i as u32, score));
+            }
+        }
+
+        let (mut id, _) = best?;
+        let mut path = vec![id];
+        for from in predecessors.iter().rev() {
+            id = from[id as usize];
+            path.push(id);
+        }
+
+        Some(
+            path.into_iter()
+                .rev()
+                .map(|id| *self.states.resolve(id).unwrap())
+                .collect(),
+        )
+    }
+
+    // Returns the log probability of each state following in a table
+    fn log_transitions(table: &crate::Table<u32>) -> impl Iterator<Item = (u32, f64)> + '_ {
+        let total = table.total() as f64;
+        table
+            .iter()
+            .filter_map(move |(id, frequency)| id.map(|id| (id, (frequency as f64 / total).log2())))
+    }
+
+    fn log_emission(&self, id: u32, symbol: &TSymbol) -> f64 {
+        self.emissions[id as usize]
+            .probability_of(&Some(*symbol))
+            .log2()
+    }
+
+    fn table_mut(&mut self, state: Option<u32>) -> &mut crate::Table<u32> {
+        match state {
+            Some(id) => &mut self.transitions[id as usize],
+            None => &mut self.initial,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hmm::HiddenMarkovModel;
+
+    // Returns the likeliest state path by trying every one of them
+    fn brute_force(model: &HiddenMarkovModel<char, u8>, observations: &[u8]) -> Option<Vec<char>> {
+        let states: Vec<char> = model.states.iter().copied().collect();
+        let mut best: Option<(Vec<char>, f64)> = None;
+
+        for mut n in 0..states.len().pow(observations.len() as u32) {
+            let mut path = vec![];
+            for _ in observations {
+                path.push(states[n % states.len()]);
+                n /= states.len();
+            }
+
+            let mut p = model.transition_probability(path.last().copied(), None);
+            let mut previous = None;
+            for (state, symbol) in path.iter().zip(observations) {
+                p *= model.transition_probability(previous, Some(*state));
+                p *= model.emission_probability(*state, *symbol);
+                previous = Some(*state);
+            }
+
+            if p > 0.0 && best.as_ref().map_or(true, |(_, b)| p > *b) {
+                best = Some((path, p));
+            }
+        }
+
+        best.map(|(path, _)| path)
+    }
+
+    #[test]
+    fn it_decodes_the_likeliest_state_path() {
+        let model = HiddenMarkovModel::train_from(vec![
+            vec![('h', 1), ('h', 2), ('c', 3), ('c', 3)],
+            vec![('c', 1), ('c', 1), ('h', 3), ('h', 2)],
+            vec![('h', 3), ('c', 1), ('c', 2)],
+            vec![('c', 2), ('h', 3), ('h', 3), ('c', 1)],
+        ]);
+        assert_eq!(model.state_count(), 2);
+
+        for observations in [&[1, 2, 3][..], &[3, 3, 1, 1], &[2, 2, 2, 2, 3], &[3]] {
+            let decoded = model.decode(observations);
+            assert!(decoded.is_some());
+            assert_eq!(decoded, brute_force(&model, observations));
+        }
+        assert_eq!(model.decode(&[4]), None);
+    }
+
+    #[test]
+    fn it_only_decodes_paths_which_can_end() {
+        let model = HiddenMarkovModel::train_from(vec![vec![('a', 1), ('b', 1)]]);
+
+        assert_eq!(model.decode(&[1, 1]), Some(vec!['a', 'b']));
+        assert_eq!(model.decode(&[1]), None);
+        assert_eq!(model.decode(&[]), None);
+        assert_eq!(model.transition_probability(None, Some('a')), 1.0);
+        assert_eq!(model.transition_probability(Some('b'), None), 1.0);
+        assert_eq!(model.transition_probability(Some('x'), None), 0.0);
+    }
+}
@@ -0,0 +1,227 @@
+/// A Markov model over symbols which are interned to dense IDs.
+///
+/// Each distinct symbol is stored only once, and the underlying
+/// [Model](struct.Model.html) tracks compact `u32` IDs in place of the symbols
+/// themselves. This saves memory for models over wide symbol types, and allows
+/// symbols which are not `Copy`, such as `String`s, to be modelled.
+pub struct InternedModel<TSymbol>
+where
+    TSymbol: std::hash::Hash + std::cmp::Eq + std::clone::Clone,
+{
+    model: crate::Model<u32>,
+    symbols: crate::interner::Interner<TSymbol>,
+}
+
+impl<TSymbol> InternedModel<TSymbol>
+where
+    TSymbol: std::hash::Hash + std::cmp::Eq + std::clone::Clone,
+{
+    /// Creates an empty InternedModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    pub fn new(order: usize) -> InternedModel<TSymbol> {
+        InternedModel {
+            model: crate::Model::empty(order),
+            symbols: crate::interner::Interner::empty(),
+        }
+    }
+
+    /// Trains the model on a complete sequence, as per
+    /// [`Accumulator::train_sequence`](struct.Accumulator.html#method.train_sequence).
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::InternedModel::new(1);
+    /// model.train_sequence(vec![String::from("the"), String::from("fox")]);
+    ///
+    /// assert_eq!(model.id("fox"), Some(1));
+    /// ```
+    pub fn train_sequence<I>(&mut self, sequence: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let symbols = &mut self.symbols;
+        let ids: Vec<u32> = sequence
+            .into_iter()
+            .map(|s| symbols.intern_value(s))
+            .collect();
+
+        crate::Accumulator::new(&mut self.model).train_sequence(ids);
+    }
+
+    /// Generates a complete sequence from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::InternedModel::new(1);
+    /// model.train_sequence(vec![String::from("the"), String::from("fox")]);
+    ///
+    /// assert_eq!(model.generate(Box::new(|| 0.0)), vec!["the", "fox"]);
+    /// ```
+    pub fn generate(&self, rand_source: Box<dyn FnMut() -> f64 + '_>) -> Vec<TSymbol> {
+        crate::Generator::new(&self.model, rand_source)
+            .filter_map(|id| self.symbol(id).cloned())
+            .collect()
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Symbols are given in a borrowed form, such as `&str` for a model of
+    /// `String`s, so that querying the model need not allocate owned symbols.
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first, as per
+    /// [`Model::end_probability`](struct.Model.html#method.end_probability).
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::InternedModel::new(1);
+    /// model.train_sequence(vec![String::from("the"), String::from("fox")]);
+    /// model.train_sequence(vec![String::from("the"), String::from("dog")]);
+    ///
+    /// assert_eq!(model.probability(&["the"], Some("fox")), 0.5);
+    /// assert_eq!(model.probability(&["fox"], None), 1.0);
+    /// ```
+    pub fn probability<Q>(&self, context: &[&Q], symbol: Option<&Q>) -> f64
+    where
+        TSymbol: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        let next_symbol = match symbol {
+            Some(s) => match self.id(s) {
+                Some(id) => Some(id),
+                None => return 0.0,
+            },
+            None => None,
+        };
+
+        match self.context_ids(context) {
+            Some(ids) => self.model.probability(&ids[..], &next_symbol),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// `None` is returned if the context has never been observed, or if it is
+    /// most likely to be followed by the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, in a borrowed form,
+    /// as per [`probability`](#method.probability).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::InternedModel::new(1);
+    /// model.train_sequence(vec![String::from("the"), String::from("fox")]);
+    ///
+    /// assert_eq!(model.predict(&["the"]).map(|s| s.as_str()), Some("fox"));
+    /// ```
+    pub fn predict<Q>(&self, context: &[&Q]) -> Option<&TSymbol>
+    where
+        TSymbol: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        let ids = self.context_ids(context)?;
+        self.model.predict(&ids[..]).and_then(|id| self.symbol(*id))
+    }
+
+    /// Returns the underlying model, whose symbols are symbol IDs.
+    pub fn model(&self) -> &crate::Model<u32> {
+        &self.model
+    }
+
+    /// Returns the ID of a symbol, if it has been seen during training.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The symbol, or a borrowed form of it.
+    pub fn id<Q>(&self, symbol: &Q) -> Option<u32>
+    where
+        TSymbol: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        self.symbols.get(symbol)
+    }
+
+    /// Returns the symbol with the specified ID.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the symbol.
+    pub fn symbol(&self, id: u32) -> Option<&TSymbol> {
+        self.symbols.resolve(id)
+    }
+
+    // Returns the IDs of the last `order` symbols of a context, or `None` if
+    // any of them has never been seen, in which case neither has the context
+    fn context_ids<Q>(&self, context: &[&Q]) -> Option<Vec<u32>>
+    where
+        TSymbol: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        let context = &context[context.len().saturating_sub(self.model.order())..];
+        context.iter().map(|s| self.id(*s)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::interned_model::InternedModel;
+
+    #[test]
+    fn it_models_interned_symbols() {
+        let mut model = InternedModel::new(1);
+        model.train_sequence(vec!["a".to_string(), "b".to_string(), "a".to_string()]);
+
+        assert_eq!(model.id("a"), Some(0));
+        assert_eq!(model.id("b"), Some(1));
+        assert_eq!(model.id("c"), None);
+        assert_eq!(model.symbol(1).map(|s| s.as_str()), Some("b"));
+        assert_eq!(model.symbol(2), None);
+
+        assert_eq!(model.probability(&["a"], Some("b")), 0.5);
+        assert_eq!(model.probability(&["x", "b"], Some("a")), 1.0);
+        assert_eq!(model.probability(&["c"], None), 0.0);
+        assert_eq!(model.probability(&["a"], Some("c")), 0.0);
+        assert_eq!(model.predict(&["b"]).map(|s| s.as_str()), Some("a"));
+        assert_eq!(model.predict(&["c"]), None);
+
+        let mut pre = crate::Predictor::new(model.model());
+        pre.given(0);
+        assert_eq!(pre.predict(), Some(&1));
+    }
+
+    #[test]
+    fn it_generates_the_original_symbols() {
+        let mut model = InternedModel::new(2);
+        model.train_sequence(vec![vec![1, 2], vec![3], vec![1, 2]]);
+
+        assert_eq!(
+            model.generate(Box::new(|| 0.0)),
+            vec![vec![1, 2], vec![3], vec![1, 2]]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Values are stored once, in the order in which they were first interned, and
+// the lookup table holds only their IDs, hashing the values they refer to
+pub(crate) struct Interner<T, THasher = std::collections::hash_map::RandomState>
+where
+    T: std::hash::Hash + std::cmp::Eq + std::clone::Clone,
+{
+    values: Vec<T>,
+    ids: hashbrown::HashTable<u32>,
+    hasher: THasher,
+}
+
+impl<T> Interner<T>
+where
+    T: std::hash::Hash + std::cmp::Eq + std::clone::Clone,
+{
+    pub(crate) fn empty() -> Interner<T> {
+        Interner::with_hasher(Default::default())
+    }
+}
+
+impl<T, THasher> Interner<T, THasher>
+where
+    T: std::hash::Hash + std::cmp::Eq + std::clone::Clone,
+    THasher: std::hash::BuildHasher,
+{
+    pub(crate) fn with_hasher(hasher: THasher) -> Interner<T, THasher> {
+        Interner {
+            values: vec![],
+            ids: hashbrown::HashTable::new(),
+            hasher,
+        }
+    }
+
+    pub(crate) fn intern<'q, Q>(&mut self, value: &'q Q) -> u32
+    where
+        T: std::borrow::Borrow<Q> + From<&'q Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        let values = &self.values;
+        let hasher = &self.hasher;
+        let hash = hasher.hash_one(value);
+
+        match self.ids.entry(
+            hash,
+            |id| values[*id as usize].borrow() == value,
+            |id| hasher.hash_one(&values[*id as usize]),
+        ) {
+            hashbrown::hash_table::Entry::Occupied(entry) => *entry.get(),
+            hashbrown::hash_table::Entry::Vacant(entry) => {
+                let id = values.len() as u32;
+                entry.insert(id);
+                self.values.push(T::from(value));
+                id
+            }
+        }
+    }
+
+    pub(crate) fn intern_value(&mut self, value: T) -> u32 {
+        let values = &self.values;
+        let hasher = &self.hasher;
+        let hash = hasher.hash_one(&value);
+
+        match self.ids.entry(
+            hash,
+            |id| values[*id as usize] == value,
+            |id| hasher.hash_one(&values[*id as usize]),
+        ) {
+            hashbrown::hash_table::Entry::Occupied(entry) => *entry.get(),
+            hashbrown::hash_table::Entry::Vacant(entry) => {
+                let id = values.len() as u32;
+                entry.insert(id);
+                self.values.push(value);
+                id
+            }
+        }
+    }
+
+    pub(crate) fn get<Q>(&self, value: &Q) -> Option<u32>
+    where
+        T: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + std::cmp::Eq + ?Sized,
+    {
+        let hash = self.hasher.hash_one(value);
+        self.ids
+            .find(hash, |id| self.values[*id as usize].borrow() == value)
+            .copied()
+    }
+
+    pub(crate) fn resolve(&self, id: u32) -> Option<&T> {
+        self.values.get(id as usize)
+    }
+
+    pub(crate) fn hasher(&self) -> &THasher {
+        &self.hasher
+    }
+
+    // Iterates over the interned values in order of their IDs
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    pub(crate) fn into_values(self) -> Vec<T> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::interner::Interner;
+
+    #[test]
+    fn it_assigns_dense_ids_to_distinct_values() {
+        let mut i = Interner::<String>::empty();
+
+        assert_eq!(i.intern("the"), 0);
+        assert_eq!(i.intern("fox"), 1);
+        assert_eq!(i.intern("the"), 0);
+
+        assert_eq!(i.get("fox"), Some(1));
+        assert_eq!(i.get("dog"), None);
+        assert_eq!(i.resolve(0).map(|s| s.as_str()), Some("the"));
+        assert_eq!(i.resolve(2), None);
+    }
+
+    #[test]
+    fn it_iterates_over_values_in_order_of_their_ids() {
+        let mut i = Interner::<String>::empty();
+
+        for word in &["the", "quick", "the", "fox"] {
+            i.intern(*word);
+        }
+
+        let values: Vec<&str> = i.iter().map(|s| s.as_str()).collect();
+        assert_eq!(values, vec!["the", "quick", "fox"]);
+    }
+}
@@ -4,16 +4,87 @@
 //! prediction and generation.
 
 mod accumulator;
+mod alias;
+mod anomaly_detector;
+mod batch_scorer;
+mod beam_search;
+mod bloom_filter;
+mod classifier;
+mod concurrent_accumulator;
+mod context_index;
+mod count_overflow;
+mod cycle;
+mod distribution;
+mod ensemble_predictor;
+mod error;
+mod evaluation;
+mod fenwick;
+mod frozen_model;
 mod generator;
+pub mod hmm;
+mod interned_model;
+mod interner;
 mod model;
+mod ord_model;
+mod overflow;
 mod predictor;
+mod progress;
+mod quantized_model;
+mod random;
+mod read_model;
+mod redistribution;
+mod scorer;
 mod sequence;
+#[cfg(feature = "stream")]
+mod stream;
+mod succinct;
+mod succinct_model;
+mod suffix_model;
 mod table;
+pub mod text;
+mod tie_break;
+mod trie_model;
+mod unknown;
+mod weight;
+mod weighted_model;
+mod window;
 
 pub use self::accumulator::Accumulator;
+pub use self::anomaly_detector::{AnomalyDetector, Observation};
+pub use self::batch_scorer::BatchScorer;
+pub use self::beam_search::BeamSearch;
+pub use self::classifier::Classifier;
+pub use self::concurrent_accumulator::ConcurrentAccumulator;
+pub use self::context_index::ContextIndex;
+pub use self::count_overflow::CountOverflow;
+pub use self::cycle::Cycle;
+pub use self::ensemble_predictor::EnsemblePredictor;
+pub use self::error::Error;
+pub use self::evaluation::{Evaluation, Unseen};
+pub use self::frozen_model::FrozenModel;
 pub use self::generator::Generator;
+pub use self::interned_model::InternedModel;
 pub use self::model::Model;
-pub use self::predictor::Predictor;
+pub use self::ord_model::OrdModel;
+pub use self::overflow::Overflow;
+pub use self::predictor::{Predictor, PredictorState};
+pub use self::progress::Progress;
+pub use self::quantized_model::QuantizedModel;
+pub use self::random::{RandomSource, RngSource, SeededSource};
+pub use self::read_model::ReadModel;
+pub use self::redistribution::Redistribution;
+pub use self::scorer::Scorer;
+#[cfg(feature = "stream")]
+pub use self::stream::GeneratorStream;
+pub use self::succinct_model::SuccinctModel;
+pub use self::suffix_model::SuffixModel;
+pub use self::tie_break::TieBreak;
+pub use self::trie_model::TrieModel;
+pub use self::unknown::Unknown;
+pub use self::weight::Weight;
+pub use self::weighted_model::WeightedModel;
 
+use self::distribution::Distribution;
 use self::sequence::Sequence;
 use self::table::Table;
+use self::window::Window;
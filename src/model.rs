@@ -1,12 +1,29 @@
 use std::collections::HashMap;
 
 /// A model based on Markov chains.
-pub struct Model<TSymbol>
+///
+/// Models are `Send` and `Sync` whenever their symbols and hasher are, so a
+/// trained model can be read from many threads at once, such as by sharing
+/// it through an `Arc`. Each thread uses its own
+/// [Predictors](struct.Predictor.html) and [Generators](struct.Generator.html),
+/// which hold the state of a sequence.
+///
+/// Contexts and symbols are looked up using the standard library's hasher by
+/// default. A faster hasher can be used instead by creating the model with
+/// [`with_hasher`](#method.with_hasher).
+pub struct Model<TSymbol, THasher = std::collections::hash_map::RandomState>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
 {
     order: usize,
-    tables_by_seq: HashMap<crate::Sequence<TSymbol>, crate::Table<TSymbol>>,
+    // Each context is stored once, and its table is found at the index given
+    // by its ID. Contexts are only discarded when the model is rebuilt, so the
+    // table of a context may be left empty when transitions are removed.
+    contexts: crate::interner::Interner<crate::Sequence<TSymbol>, THasher>,
+    tables: Vec<crate::Table<TSymbol, THasher>>,
+    normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+    count_overflow: crate::CountOverflow,
+    context_filter: Option<crate::bloom_filter::BloomFilter>,
 }
 
 impl<TSymbol> Model<TSymbol>
@@ -20,28 +37,1090 @@ where
     ///
     /// # Arguments
     ///
-    /// * `order` - The order of the model. A first order Markov model
+    /// `order` - The order of the model. A first order Markov model
     /// (order: 1) tracks probabilities of future symbols based on one prior
     /// symbol). A second order model (order: 2) tracks probabilities of
     /// future symbols based on two prior symbols, and so on.
     pub fn empty(order: usize) -> Model<TSymbol> {
+        Model::with_hasher(order, Default::default())
+    }
+
+    /// Creates a Markov model trained on the specified sequences.
+    ///
+    /// Each sequence is added in full and then ended, as per
+    /// [`Accumulator::train_sequence`](struct.Accumulator.html#method.train_sequence).
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per [`empty`](#method.empty).
+    ///
+    /// `sequences` - The training sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let corpus = "the quick brown fox\nthe lazy dog";
+    /// let model = markov::Model::train_from(1, corpus.lines().map(|l| l.split(' ')));
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given("lazy");
+    /// assert_eq!(pre.predict(), Some(&"dog"));
+    /// ```
+    pub fn train_from<I, S>(order: usize, sequences: I) -> Model<TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        let mut model = Model::empty(order);
+
+        let mut acc = crate::Accumulator::new(&mut model);
+        for sequence in sequences {
+            acc.train_sequence(sequence);
+        }
+
+        model
+    }
+
+    /// Creates a Markov model trained on the specified sequences in reverse,
+    /// so that it predicts the symbols which precede a context rather than
+    /// those which follow it.
+    ///
+    /// Reversed models can be used to extend sequences backwards from a
+    /// required suffix with
+    /// [`Generator::generate_ending_with`](struct.Generator.html#method.generate_ending_with).
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per [`empty`](#method.empty).
+    ///
+    /// `sequences` - The training sequences, in their natural order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_reversed_from(1, vec!["abc".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('b');
+    /// assert_eq!(pre.predict(), Some(&'a'));
+    /// ```
+    pub fn train_reversed_from<I, S>(order: usize, sequences: I) -> Model<TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        Model::train_from(
+            order,
+            sequences.into_iter().map(|s| {
+                let mut symbols: Vec<TSymbol> = s.into_iter().collect();
+                symbols.reverse();
+                symbols
+            }),
+        )
+    }
+
+    /// Creates a Markov model trained on the specified sequences, using
+    /// multiple threads.
+    ///
+    /// The sequences are divided amongst threads which each train a separate
+    /// model, and the results are then merged. The resulting model is the same
+    /// as one produced by [`train_from`](#method.train_from).
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per [`empty`](#method.empty).
+    ///
+    /// `sequences` - The training sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let corpus = vec![vec!["the", "quick", "brown", "fox"], vec!["the", "lazy", "dog"]];
+    /// let model = markov::Model::train_from_par(1, corpus);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn train_from_par<I, S>(order: usize, sequences: I) -> Model<TSymbol>
+    where
+        TSymbol: Send,
+        I: rayon::iter::IntoParallelIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        use rayon::iter::ParallelIterator;
+
+        sequences
+            .into_par_iter()
+            .fold(
+                || Model::empty(order),
+                |mut model, sequence| {
+                    crate::Accumulator::new(&mut model).train_sequence(sequence);
+                    model
+                },
+            )
+            .reduce(
+                || Model::empty(order),
+                |mut model, other| {
+                    model.merge(&other);
+                    model
+                },
+            )
+    }
+}
+
+impl<TSymbol, THasher> Model<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates an empty Markov model which uses the specified hasher to look
+    /// up contexts and symbols.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per [`empty`](#method.empty).
+    ///
+    /// `hasher` - The hasher with which contexts and symbols are hashed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut model = markov::Model::with_hasher(1, BuildHasherDefault::<DefaultHasher>::default());
+    /// markov::Accumulator::new(&mut model).train_sequence("abc".chars());
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('b');
+    /// assert_eq!(pre.predict(), Some(&'c'));
+    /// ```
+    pub fn with_hasher(order: usize, hasher: THasher) -> Model<TSymbol, THasher> {
         Model {
             order,
-            tables_by_seq: Default::default(),
+            contexts: crate::interner::Interner::with_hasher(hasher),
+            tables: vec![],
+            normalizer: None,
+            count_overflow: crate::CountOverflow::Saturate,
+            context_filter: None,
+        }
+    }
+
+    /// Adds the training data of another model to this one, as though the
+    /// sequences used to train it had also been used to train this model.
+    ///
+    /// # Arguments
+    ///
+    /// `other` - The model to merge into this one. Its order must be the same
+    /// as this model's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the models are of different orders.
+    pub fn merge(&mut self, other: &Model<TSymbol, THasher>) {
+        assert_eq!(self.order, other.order, "models must be of the same order");
+
+        let overflow = self.count_overflow;
+        for (seq, t) in other.tables() {
+            self.table_or_insert(seq.symbols()).merge(t, overflow);
+        }
+    }
+
+    /// Creates a model from the training data of several others, using
+    /// multiple threads, such as to combine shard models trained separately.
+    ///
+    /// Contexts are divided into disjoint ranges by their hash, and each
+    /// range is merged from every model in parallel. The result is the same
+    /// as [merging](#method.merge) each model into an empty one in turn. It
+    /// takes the hasher and count overflow strategy of the first model, but
+    /// not its normalizer.
+    ///
+    /// # Arguments
+    ///
+    /// `models` - The models to merge, which must all be of the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no models are specified, or if they are of different orders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let shards = vec![
+    ///     markov::Model::train_from(1, vec!["ab".chars()]),
+    ///     markov::Model::train_from(1, vec!["ac".chars()]),
+    /// ];
+    ///
+    /// let model = markov::Model::merge_all(&shards);
+    /// assert_eq!(model.end_probability(&['a']), Some(0.0));
+    /// assert_eq!(model.end_probability(&['c']), Some(1.0));
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn merge_all(models: &[Model<TSymbol, THasher>]) -> Model<TSymbol, THasher>
+    where
+        TSymbol: Send + Sync,
+        THasher: Send + Sync,
+    {
+        use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+        assert!(!models.is_empty(), "at least one model must be specified");
+        let first = &models[0];
+        assert!(
+            models.iter().all(|m| m.order == first.order),
+            "models must be of the same order"
+        );
+
+        let hasher = first.hasher().clone();
+        let overflow = first.count_overflow;
+        let range_count = rayon::current_num_threads() * 4;
+
+        // The IDs of each model's contexts in each range
+        let ranges: Vec<Vec<Vec<u32>>> = models
+            .par_iter()
+            .map(|m| {
+                let mut ranges = vec![vec![]; range_count];
+                for (id, seq) in m.contexts.iter().enumerate() {
+                    let range = hasher.hash_one(seq.symbols()) % range_count as u64;
+                    ranges[range as usize].push(id as u32);
+                }
+                ranges
+            })
+            .collect();
+
+        let merged: Vec<Model<TSymbol, THasher>> = (0..range_count)
+            .into_par_iter()
+            .map(|range| {
+                let mut merged = Model::with_hasher(first.order, hasher.clone());
+                merged.count_overflow = overflow;
+
+                for (m, ids) in models.iter().zip(&ranges) {
+                    for id in &ids[range] {
+                        let t = &m.tables[*id as usize];
+                        if !t.is_empty() {
+                            let seq = m.contexts.resolve(*id).unwrap();
+                            merged.table_or_insert(seq.symbols()).merge(t, overflow);
+                        }
+                    }
+                }
+                merged
+            })
+            .collect();
+
+        let mut model = Model::with_hasher(first.order, hasher);
+        model.count_overflow = overflow;
+        for m in merged {
+            model.absorb(m);
+        }
+        model
+    }
+
+    /// Removes a previously trained sequence from this model, as though it
+    /// had never been used for training.
+    ///
+    /// The sequence is walked in the same way that
+    /// [`Accumulator::train_sequence`](struct.Accumulator.html#method.train_sequence)
+    /// would have trained it, including its end. If the model's counts do not
+    /// account for the entire sequence, an error is returned and the model is
+    /// left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence to remove.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// assert_eq!(model.remove_sequence("ab".chars()), Ok(()));
+    /// assert_eq!(model.remove_sequence("ab".chars()), Err(markov::Error::NotTrained));
+    /// ```
+    pub fn remove_sequence<I>(&mut self, sequence: I) -> Result<(), crate::Error>
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut removals: HashMap<(crate::Sequence<TSymbol>, Option<TSymbol>), usize> =
+            HashMap::new();
+
+        let mut seq = crate::Sequence::empty();
+        for symbol in sequence {
+            let symbol = self.normalize(symbol);
+            let next_seq = self.advance_sequence(&seq, symbol);
+            *removals.entry((seq, Some(symbol))).or_insert(0) += 1;
+            seq = next_seq;
+        }
+        *removals.entry((seq, None)).or_insert(0) += 1;
+
+        for ((seq, next_symbol), count) in &removals {
+            let frequency = match self.table(seq) {
+                Some(t) => t.frequency_of(next_symbol),
+                None => 0,
+            };
+            if frequency < *count {
+                return Err(crate::Error::NotTrained);
+            }
+        }
+
+        for ((seq, next_symbol), count) in &removals {
+            if let Some(t) = self.table_mut(seq) {
+                t.remove_weighted(next_symbol, *count);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reduces the vocabulary of this model to its most frequent symbols,
+    /// replacing all other symbols with an "unknown" symbol.
+    ///
+    /// Symbols are ranked by how often they were observed during training.
+    /// Ties are broken in the order in which symbols are first met when
+    /// visiting contexts in the order they were first observed, so the same
+    /// symbols are kept on every run. Contexts and transitions which become
+    /// identical after replacement are combined.
+    ///
+    /// To cap the vocabulary during training instead, use
+    /// [`set_normalizer`](#method.set_normalizer) to replace symbols outside a
+    /// known vocabulary.
+    ///
+    /// # Arguments
+    ///
+    /// `max_symbols` - The number of symbols, not including `unk`, to keep.
+    ///
+    /// `unk` - The symbol which replaces all discarded symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::train_from(1, vec!["aab".chars(), "ac".chars()]);
+    /// model.cap_vocabulary(1, '?');
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), Some(&'?'));
+    /// ```
+    pub fn cap_vocabulary(&mut self, max_symbols: usize, unk: TSymbol) {
+        // Symbols are ranked in the order in which they were first seen, so
+        // that ties are broken the same way on every run
+        let mut indices: HashMap<TSymbol, usize> = HashMap::new();
+        let mut ranked: Vec<(TSymbol, usize)> = vec![];
+        for (_, t) in self.tables() {
+            for (symbol, frequency) in t.iter() {
+                if let Some(s) = symbol {
+                    let i = *indices.entry(*s).or_insert_with(|| {
+                        ranked.push((*s, 0));
+                        ranked.len() - 1
+                    });
+                    ranked[i].1 += frequency;
+                }
+            }
+        }
+
+        if ranked.len() <= max_symbols {
+            return;
+        }
+
+        ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let kept: std::collections::HashSet<TSymbol> = ranked
+            .into_iter()
+            .take(max_symbols)
+            .map(|(s, _)| s)
+            .collect();
+
+        let replace = |s: TSymbol| if kept.contains(&s) { s } else { unk };
+
+        for (seq, t) in self.take_tables() {
+            let seq = seq.map(replace);
+            for (symbol, frequency) in t.iter() {
+                self.add_weighted(&seq, symbol.map(replace), frequency);
+            }
+        }
+    }
+
+    /// Discards transitions observed fewer than the specified number of times,
+    /// so that rare occurrences such as typos do not pollute generation.
+    ///
+    /// This is intended as a final step after training. End-of-sequence
+    /// counts are never discarded, so that sequence termination is preserved,
+    /// and contexts left with no transitions are removed entirely.
+    ///
+    /// # Arguments
+    ///
+    /// `min_count` - The minimum number of observations of a transition for it
+    /// to be kept.
+    ///
+    /// `redistribution` - What happens to the counts of discarded
+    /// transitions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+    /// model.prune(2, markov::Redistribution::Discard);
+    ///
+    /// let mut scorer = markov::Scorer::new(&model);
+    /// scorer.score('a');
+    /// assert_eq!(scorer.score('c'), f64::INFINITY);
+    /// ```
+    pub fn prune(&mut self, min_count: usize, redistribution: crate::Redistribution<TSymbol>) {
+        let replacement = match redistribution {
+            crate::Redistribution::Discard => None,
+            crate::Redistribution::Replace(replacement) => Some(replacement),
+        };
+
+        // Symbols whose every transition is pruned can no longer be reached,
+        // so the contexts following them are moved to the replacement, as
+        // when capping the vocabulary, so that a sequence reaching the
+        // replacement can continue past it
+        let mut kept = std::collections::HashSet::new();
+        let mut pruned = std::collections::HashSet::new();
+        if replacement.is_some() {
+            for (_, t) in self.tables() {
+                for (symbol, frequency) in t.iter() {
+                    if let Some(s) = symbol {
+                        if frequency >= min_count {
+                            kept.insert(*s);
+                        } else {
+                            pruned.insert(*s);
+                        }
+                    }
+                }
+            }
+        }
+
+        let replace = |s: TSymbol| match replacement {
+            Some(r) if pruned.contains(&s) && !kept.contains(&s) => r,
+            _ => s,
+        };
+
+        for (seq, t) in self.take_tables() {
+            let seq = seq.map(replace);
+            for (symbol, frequency) in t.iter() {
+                if symbol.is_none() || frequency >= min_count {
+                    self.add_weighted(&seq, *symbol, frequency);
+                } else if let Some(r) = replacement {
+                    self.add_weighted(&seq, Some(r), frequency);
+                }
+            }
+        }
+    }
+
+    /// Reorders the contexts of this model by their symbols, and breaks ties
+    /// between equally frequent symbols by their order, so that the model
+    /// no longer depends on the order in which it was trained.
+    ///
+    /// Contexts are otherwise kept in the order in which they were first
+    /// observed, which for models [merged](#method.merge_all) or
+    /// [trained](#method.train_from_par) in parallel can vary between runs.
+    /// Once sorted, [transitions](#method.transitions), context IDs and
+    /// generated sequences are the same for the same training data on every
+    /// run and platform, such as for reproducible builds or golden-file tests.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut a = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    /// let mut b = markov::Model::train_from(1, vec!["ac".chars(), "ab".chars()]);
+    /// a.sort();
+    /// b.sort();
+    ///
+    /// assert!(a.transitions().eq(b.transitions()));
+    /// ```
+    pub fn sort(&mut self)
+    where
+        TSymbol: std::cmp::Ord,
+    {
+        let mut tables: Vec<_> = self.take_tables().filter(|(_, t)| !t.is_empty()).collect();
+        tables.sort_by(|(a, _), (b, _)| a.symbols().cmp(b.symbols()));
+
+        for (seq, t) in tables {
+            let mut entries: Vec<_> = t.iter().collect();
+            entries.sort_by_key(|(symbol, _)| *symbol);
+
+            for (symbol, frequency) in entries {
+                self.add_weighted(&seq, *symbol, frequency);
+            }
+        }
+    }
+
+    /// Sets a function which normalizes symbols before they are used to train
+    /// or query this model, such as by case folding or bucketing numbers.
+    ///
+    /// The normalizer is applied to symbols added via an
+    /// [Accumulator](struct.Accumulator.html) as well as symbols given to
+    /// predictors and scorers, guaranteeing that the same normalization is
+    /// used for both training and prediction. It should be set before any
+    /// training takes place.
+    ///
+    /// # Arguments
+    ///
+    /// `normalizer` - A function returning the normalized form of a symbol.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    /// model.set_normalizer(|c: char| c.to_ascii_lowercase());
+    ///
+    /// markov::Accumulator::new(&mut model).train_sequence("AB".chars());
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('A');
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn set_normalizer<F>(&mut self, normalizer: F)
+    where
+        F: Fn(TSymbol) -> TSymbol + Send + Sync + 'static,
+    {
+        self.normalizer = Some(Box::new(normalizer));
+    }
+
+    /// Sets how counts which would exceed the largest count this model can
+    /// hold are handled during training.
+    ///
+    /// Counts are held as 32-bit integers to save memory, unless the
+    /// `wide-counts` feature is enabled to hold them as 64-bit integers.
+    ///
+    /// # Arguments
+    ///
+    /// `overflow` - The strategy for handling overflowing counts, which
+    /// defaults to [`CountOverflow::Saturate`](enum.CountOverflow.html#variant.Saturate).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    /// model.set_count_overflow(markov::CountOverflow::Halve);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add_weighted("a", usize::MAX);
+    /// acc.add_weighted("b", 2);
+    /// acc.end();
+    ///
+    /// assert_eq!(model.end_probability(&["b"]), Some(1.0));
+    /// ```
+    pub fn set_count_overflow(&mut self, overflow: crate::CountOverflow) {
+        self.count_overflow = overflow;
+    }
+
+    /// Sets whether contexts are checked against a Bloom filter before being
+    /// looked up.
+    ///
+    /// The filter rules out most contexts which have never been observed
+    /// without searching the model for them, so that predicting or sampling
+    /// after an unseen context (as is common when conditioning on user input)
+    /// returns sooner. It costs around 10 bits per context for a 1% false
+    /// positive rate, and is kept up to date as the model is trained.
+    ///
+    /// # Arguments
+    ///
+    /// `false_positive_rate` - The proportion of unseen contexts which may
+    /// still be searched for, or `None` to remove the filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the false positive rate is not between 0.0 and 1.0
+    /// (exclusive).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::train_from(1, vec!["abc".chars()]);
+    /// model.set_context_filter(Some(0.01));
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('b');
+    /// assert_eq!(pre.predict(), Some(&'c'));
+    /// ```
+    pub fn set_context_filter(&mut self, false_positive_rate: Option<f64>) {
+        self.context_filter = None;
+
+        if let Some(rate) = false_positive_rate {
+            assert!(
+                rate > 0.0 && rate < 1.0,
+                "false positive rate must be between 0.0 and 1.0"
+            );
+            self.rebuild_context_filter(self.tables.len() * 2, rate);
         }
     }
 
+    /// Freezes this model for serving, once it has been fully trained.
+    ///
+    /// The transitions of the model are laid out in contiguous arrays, with the
+    /// symbols of each context from most to least frequent, and alias tables
+    /// are precomputed so that sampling the next symbol takes constant time
+    /// regardless of how many symbols have followed a context. Frozen models
+    /// can be read by Predictors and Generators, but must be
+    /// [thawed](struct.FrozenModel.html#method.thaw) to be trained further.
+    ///
+    /// Generated sequences follow the same probabilities as before freezing,
+    /// but draw differently from the values of a random source, so the same
+    /// source produces different output once frozen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let model = markov::Model::train_from(1, vec!["the quick brown fox".split(' ')]).freeze();
+    ///
+    /// let mut gen = markov::Generator::with_rng(&model, rand::rngs::StdRng::seed_from_u64(42));
+    /// assert_eq!(gen.generate_sequence(), vec!["the", "quick", "brown", "fox"]);
+    /// ```
+    pub fn freeze(self) -> crate::FrozenModel<TSymbol, THasher> {
+        crate::FrozenModel::new(self)
+    }
+
+    /// Compacts this model for memory-constrained deployment by quantizing
+    /// the distribution of each context to 16-bit fixed-point probabilities,
+    /// and discarding its raw counts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+    /// let quantized = model.quantize();
+    ///
+    /// assert!((quantized.probability(&['a'], Some('b')) - 2.0 / 3.0).abs() < 1e-4);
+    /// ```
+    pub fn quantize(self) -> crate::QuantizedModel<TSymbol, THasher> {
+        crate::QuantizedModel::new(self)
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.tables().count()
+    }
+
+    /// Returns the ID by which the specified context is stored in this model.
+    ///
+    /// Each context is stored only once, and IDs are assigned densely in the
+    /// order in which contexts were first observed. IDs remain stable while
+    /// the model is trained, but may change when it is
+    /// [pruned](#method.prune) or its vocabulary is
+    /// [capped](#method.cap_vocabulary).
+    ///
+    /// `None` is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first, as per
+    /// [`end_probability`](#method.end_probability).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars()]);
+    ///
+    /// let id = model.context_id(&['a']).unwrap();
+    /// assert_eq!(model.context_symbols(id), Some(&['a'][..]));
+    /// assert_eq!(model.context_id(&['c']), None);
+    /// ```
+    pub fn context_id(&self, context: &[TSymbol]) -> Option<u32> {
+        let context: Vec<TSymbol> = context.iter().map(|s| self.normalize(*s)).collect();
+        let seq = crate::Sequence::from_slice(&context, self.order);
+
+        self.contexts
+            .get(seq.symbols())
+            .filter(|id| !self.tables[*id as usize].is_empty())
+    }
+
+    /// Returns the symbols of the context with the specified ID, as returned
+    /// by [`context_id`](#method.context_id).
+    ///
+    /// `None` is returned if no context has the specified ID.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    pub fn context_symbols(&self, id: u32) -> Option<&[TSymbol]> {
+        self.contexts.resolve(id).map(|seq| seq.symbols())
+    }
+
+    /// Returns every transition of this model, as the symbols of a context,
+    /// the symbol which followed it, and the number of times it did so, where
+    /// `None` refers to the end of a sequence.
+    ///
+    /// Contexts are returned in the order of their IDs, and the symbols of each
+    /// from most to least frequent, with ties in the order in which they were
+    /// first seen. The order depends only on how the model was trained, not
+    /// on its hasher, and can be made independent of training order too by
+    /// [sorting](#method.sort) the model.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+    /// let transitions: Vec<_> = model.transitions().collect();
+    ///
+    /// assert_eq!(transitions[0], (&[][..], Some(&'a'), 3));
+    /// assert_eq!(transitions[1], (&['a'][..], Some(&'b'), 2));
+    /// assert_eq!(transitions[2], (&['a'][..], Some(&'c'), 1));
+    /// ```
+    pub fn transitions(&self) -> impl Iterator<Item = (&[TSymbol], Option<&TSymbol>, usize)> {
+        self.tables().flat_map(|(seq, t)| {
+            t.iter()
+                .map(move |(symbol, frequency)| (seq.symbols(), symbol.as_ref(), frequency))
+        })
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the probability that a sequence ends immediately after the
+    /// specified context.
+    ///
+    /// `None` is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first. Only
+    /// the last `order` symbols are considered, and an empty context refers to
+    /// the beginning of a sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add("the");
+    /// acc.add("end");
+    /// acc.end();
+    ///
+    /// assert_eq!(model.end_probability(&["end"]), Some(1.0));
+    /// assert_eq!(model.end_probability(&["the"]), Some(0.0));
+    /// assert_eq!(model.end_probability(&["fox"]), None);
+    /// ```
+    pub fn end_probability(&self, context: &[TSymbol]) -> Option<f64> {
+        let context: Vec<TSymbol> = context.iter().map(|s| self.normalize(*s)).collect();
+
+        self.table(&crate::Sequence::from_slice(&context, self.order))
+            .map(|t| t.probability_of(&None))
+    }
+
+    /// Evaluates how well this model predicts a corpus of test sequences,
+    /// such as held-out data not used for training.
+    ///
+    /// Each sequence is scored symbol by symbol, including its end, in the
+    /// same way that an [Accumulator](struct.Accumulator.html) would have
+    /// trained it.
+    ///
+    /// # Arguments
+    ///
+    /// `sequences` - The test sequences to evaluate.
+    ///
+    /// `unseen` - How to treat symbols to which this model assigns no
+    /// probability.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add("the");
+    /// acc.add("quick");
+    /// acc.end();
+    /// acc.add("the");
+    /// acc.add("lazy");
+    /// acc.end();
+    ///
+    /// let eval = model.evaluate(vec![vec!["the", "quick"]], markov::Unseen::Skip);
+    /// assert_eq!(eval.perplexity(), 2f64.powf(1.0 / 3.0));
+    /// ```
+    pub fn evaluate<I, S>(&self, sequences: I, unseen: crate::Unseen) -> crate::Evaluation
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        let mut evaluation = crate::Evaluation::empty();
+
+        for sequence in sequences {
+            let mut seq = crate::Sequence::empty();
+
+            for symbol in sequence {
+                let symbol = self.normalize(symbol);
+                evaluation.add(self.probability(&seq, &Some(symbol)), unseen);
+                seq = self.advance_sequence(&seq, symbol);
+            }
+
+            evaluation.add(self.probability(&seq, &None), unseen);
+        }
+
+        evaluation
+    }
+
+    /// Returns the Kullback-Leibler divergence, in bits, of another model from
+    /// this one.
+    ///
+    /// The divergence is measured over the contexts observed by both models,
+    /// with each context weighted by how often it was observed by this model.
+    /// Zero is returned if the models share no contexts.
+    ///
+    /// # Arguments
+    ///
+    /// `other` - The model to measure the divergence of.
+    ///
+    /// `unseen` - How to treat symbols observed by this model but to which the
+    /// other model assigns no probability. Skipping them discards the missing
+    /// probability mass rather than producing an infinite divergence.
+    pub fn kl_divergence(&self, other: &Model<TSymbol, THasher>, unseen: crate::Unseen) -> f64 {
+        let mut total_weight = 0;
+        let mut divergence = 0.0;
+
+        for (seq, p) in self.tables() {
+            let q = match other.table(seq) {
+                Some(q) => q,
+                None => continue,
+            };
+
+            let mut d = 0.0;
+
+            for (symbol, frequency) in p.iter() {
+                let p_prob = frequency as f64 / p.total() as f64;
+                let q_prob = match q.probability_of(symbol) {
+                    q_prob if q_prob > 0.0 => q_prob,
+                    _ => match unseen {
+                        crate::Unseen::Skip => continue,
+                        crate::Unseen::Floor(f) => f,
+                    },
+                };
+                d += p_prob * (p_prob / q_prob).log2();
+            }
+
+            divergence += d * p.total() as f64;
+            total_weight += p.total();
+        }
+
+        if total_weight == 0 {
+            return 0.0;
+        }
+        divergence / total_weight as f64
+    }
+
+    /// Returns the Jensen-Shannon divergence, in bits, between this model and
+    /// another.
+    ///
+    /// The divergence is measured over the contexts observed by both models,
+    /// with each context weighted by how often it was observed by either
+    /// model. Unlike [`kl_divergence`](#method.kl_divergence), the result is
+    /// symmetric and always in the [0.0, 1.0] range.
+    ///
+    /// # Arguments
+    ///
+    /// `other` - The model to compare with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut last_month = markov::Model::empty(1);
+    /// markov::Accumulator::new(&mut last_month).add("a");
+    ///
+    /// let mut today = markov::Model::empty(1);
+    /// markov::Accumulator::new(&mut today).add("b");
+    ///
+    /// assert_eq!(last_month.js_divergence(&last_month), 0.0);
+    /// assert_eq!(last_month.js_divergence(&today), 1.0);
+    /// ```
+    pub fn js_divergence(&self, other: &Model<TSymbol, THasher>) -> f64 {
+        let shared: Vec<_> = self
+            .tables()
+            .filter_map(|(seq, p)| other.table(seq).map(|q| (p, q)))
+            .collect();
+
+        let p_total: usize = shared.iter().map(|(p, _)| p.total()).sum();
+        let q_total: usize = shared.iter().map(|(_, q)| q.total()).sum();
+
+        if p_total == 0 || q_total == 0 {
+            return 0.0;
+        }
+
+        let mut divergence = 0.0;
+
+        for (p, q) in shared {
+            let mut d = 0.0;
+
+            for (symbol, frequency) in p.iter() {
+                let p_prob = frequency as f64 / p.total() as f64;
+                let m_prob = (p_prob + q.probability_of(symbol)) / 2.0;
+                d += p_prob * (p_prob / m_prob).log2();
+            }
+            for (symbol, frequency) in q.iter() {
+                let q_prob = frequency as f64 / q.total() as f64;
+                let m_prob = (q_prob + p.probability_of(symbol)) / 2.0;
+                d += q_prob * (q_prob / m_prob).log2();
+            }
+
+            let weight =
+                (p.total() as f64 / p_total as f64 + q.total() as f64 / q_total as f64) / 2.0;
+            divergence += weight * d / 2.0;
+        }
+
+        divergence
+    }
+
+    /// Generates a batch of independent sequences from this model.
+    ///
+    /// Each sequence is generated using its own random number generator,
+    /// seeded from `rng_seed` and the sequence's position in the batch, so
+    /// that the output is reproducible for a given seed. The two are mixed
+    /// together, so batches for nearby seeds are unrelated to each other.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The number of sequences to generate.
+    ///
+    /// `rng_seed` - The seed from which the random number generators are
+    /// derived.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let batch = model.generate_batch(10, 42);
+    /// assert_eq!(batch.len(), 10);
+    /// assert_eq!(batch, model.generate_batch(10, 42));
+    /// ```
+    pub fn generate_batch(&self, n: usize, rng_seed: u64) -> Vec<Vec<TSymbol>> {
+        (0..n).map(|i| self.generate_seeded(rng_seed, i)).collect()
+    }
+
+    /// Generates a batch of independent sequences from this model, using
+    /// multiple threads.
+    ///
+    /// The resulting sequences are the same as those produced by
+    /// [`generate_batch`](#method.generate_batch) for the same seed.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The number of sequences to generate.
+    ///
+    /// `rng_seed` - The seed from which the random number generators are
+    /// derived.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let batch = model.generate_batch_par(10, 42);
+    /// assert_eq!(batch, model.generate_batch(10, 42));
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn generate_batch_par(&self, n: usize, rng_seed: u64) -> Vec<Vec<TSymbol>>
+    where
+        TSymbol: Send + Sync,
+        THasher: Sync,
+    {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        (0..n)
+            .into_par_iter()
+            .map(|i| self.generate_seeded(rng_seed, i))
+            .collect()
+    }
+
+    fn generate_seeded(&self, rng_seed: u64, index: usize) -> Vec<TSymbol> {
+        let seed = crate::random::mix_seed(rng_seed, index as u64);
+        crate::Generator::seeded(self, seed).generate_sequence()
+    }
+
+    #[cfg(test)]
     pub(crate) fn add(&mut self, seq: &crate::Sequence<TSymbol>, next_symbol: Option<TSymbol>) {
-        match self.tables_by_seq.get_mut(seq) {
-            Some(t) => {
-                t.add(next_symbol);
-            }
-            None => {
-                let mut t = crate::Table::empty();
-                t.add(next_symbol);
-                self.tables_by_seq.insert(seq.clone(), t);
+        self.add_weighted(seq, next_symbol, 1);
+    }
+
+    pub(crate) fn add_weighted<S>(&mut self, seq: &S, next_symbol: Option<TSymbol>, weight: usize)
+    where
+        S: std::borrow::Borrow<[TSymbol]> + ?Sized,
+    {
+        self.add_weighted_to_context(seq.borrow(), next_symbol, weight);
+    }
+
+    fn add_weighted_to_context(
+        &mut self,
+        context: &[TSymbol],
+        next_symbol: Option<TSymbol>,
+        weight: usize,
+    ) {
+        let overflow = self.count_overflow;
+        self.table_or_insert(context)
+            .add_weighted_with(next_symbol, weight, overflow);
+    }
+
+    // Adds the training data of another model to this one, moving rather than
+    // copying the tables of contexts this model has not yet observed
+    pub(crate) fn absorb(&mut self, mut other: Model<TSymbol, THasher>) {
+        let overflow = self.count_overflow;
+        for (seq, t) in other.take_tables() {
+            let table = self.table_or_insert(seq.symbols());
+            if table.is_empty() {
+                *table = t;
+            } else {
+                table.merge(&t, overflow);
             }
-        };
+        }
+    }
+
+    // Tables emptied by halving are kept in place, as when removing sequences,
+    // so that the IDs of other contexts stay stable during training
+    pub(crate) fn halve_counts(&mut self) {
+        for t in &mut self.tables {
+            t.halve();
+        }
+    }
+
+    fn rebuild_context_filter(&mut self, capacity: usize, false_positive_rate: f64) {
+        let mut filter =
+            crate::bloom_filter::BloomFilter::with_capacity(capacity, false_positive_rate);
+        for seq in self.contexts.iter() {
+            filter.insert(self.contexts.hasher().hash_one(seq.symbols()));
+        }
+        self.context_filter = Some(filter);
+    }
+
+    // Whether a context may have been observed, according to the context
+    // filter if there is one
+    fn may_contain_context(&self, context: &[TSymbol]) -> bool {
+        match &self.context_filter {
+            Some(filter) => filter.may_contain(self.contexts.hasher().hash_one(context)),
+            None => true,
+        }
+    }
+
+    pub(crate) fn hasher(&self) -> &THasher {
+        self.contexts.hasher()
+    }
+
+    pub(crate) fn into_normalizer(self) -> Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>> {
+        self.normalizer
+    }
+
+    pub(crate) fn with_normalizer(
+        mut self,
+        normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+    ) -> Model<TSymbol, THasher> {
+        self.normalizer = normalizer;
+        self
+    }
+
+    pub(crate) fn count_overflow(&self) -> crate::CountOverflow {
+        self.count_overflow
+    }
+
+    pub(crate) fn context_filter_rate(&self) -> Option<f64> {
+        self.context_filter
+            .as_ref()
+            .map(|f| f.false_positive_rate())
+    }
+
+    pub(crate) fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        match &self.normalizer {
+            Some(normalizer) => normalizer(symbol),
+            None => symbol,
+        }
     }
 
     pub(crate) fn advance_sequence(
@@ -52,51 +1131,266 @@ where
         seq.with_next(next_symbol, self.order)
     }
 
-    pub(crate) fn predict(&self, seq: &crate::Sequence<TSymbol>) -> Option<&TSymbol> {
-        match self.tables_by_seq.get(seq) {
-            Some(t) => t.most_frequent(),
+    // Lookups accept any form of context which can be borrowed as a slice,
+    // such as the window of a sequence, so that it can be hashed directly
+    pub(crate) fn table<S>(&self, seq: &S) -> Option<&crate::Table<TSymbol, THasher>>
+    where
+        S: std::borrow::Borrow<[TSymbol]> + ?Sized,
+    {
+        self.table_with_id(seq.borrow()).map(|(_, t)| t)
+    }
+
+    pub(crate) fn table_with_id(
+        &self,
+        context: &[TSymbol],
+    ) -> Option<(u32, &crate::Table<TSymbol, THasher>)> {
+        if !self.may_contain_context(context) {
+            return None;
+        }
+
+        self.contexts
+            .get(context)
+            .map(|id| (id, &self.tables[id as usize]))
+            .filter(|(_, t)| !t.is_empty())
+    }
+
+    // Returns the table of every context, including empty ones, indexed by
+    // context ID
+    pub(crate) fn tables_by_id(&self) -> &[crate::Table<TSymbol, THasher>] {
+        &self.tables
+    }
+
+    fn table_mut(
+        &mut self,
+        seq: &crate::Sequence<TSymbol>,
+    ) -> Option<&mut crate::Table<TSymbol, THasher>> {
+        match self.contexts.get(seq.symbols()) {
+            Some(id) => Some(&mut self.tables[id as usize]),
             None => None,
         }
     }
 
-    pub(crate) fn sample(
+    fn table_or_insert(&mut self, context: &[TSymbol]) -> &mut crate::Table<TSymbol, THasher> {
+        let id = self.contexts.intern(context) as usize;
+        if id == self.tables.len() {
+            let hasher = self.contexts.hasher().clone();
+            self.tables.push(crate::Table::with_hasher(hasher));
+
+            // The filter is rebuilt at twice the size once it fills up, so
+            // that its false positive rate is kept
+            match &mut self.context_filter {
+                Some(filter) if filter.is_full() => {
+                    let rate = filter.false_positive_rate();
+                    self.rebuild_context_filter(self.tables.len() * 2, rate);
+                }
+                Some(filter) => filter.insert(self.contexts.hasher().hash_one(context)),
+                None => {}
+            }
+        }
+        &mut self.tables[id]
+    }
+
+    pub(crate) fn tables(
         &self,
-        seq: &crate::Sequence<TSymbol>,
-        sample_value: f64,
-    ) -> Option<&TSymbol> {
-        match self.tables_by_seq.get(seq) {
-            Some(t) => t.sample(sample_value),
+    ) -> impl Iterator<Item = (&crate::Sequence<TSymbol>, &crate::Table<TSymbol, THasher>)> {
+        self.contexts
+            .iter()
+            .zip(&self.tables)
+            .filter(|(_, t)| !t.is_empty())
+    }
+
+    // Removes all contexts and their tables from this model, so that it can
+    // be rebuilt from them
+    fn take_tables(
+        &mut self,
+    ) -> impl Iterator<Item = (crate::Sequence<TSymbol>, crate::Table<TSymbol, THasher>)> {
+        let hasher = self.contexts.hasher().clone();
+        let contexts = std::mem::replace(
+            &mut self.contexts,
+            crate::interner::Interner::with_hasher(hasher),
+        );
+        let tables = std::mem::take(&mut self.tables);
+
+        contexts.into_values().into_iter().zip(tables)
+    }
+
+    pub(crate) fn predict<S>(&self, seq: &S) -> Option<&TSymbol>
+    where
+        S: std::borrow::Borrow<[TSymbol]> + ?Sized,
+    {
+        match self.table(seq) {
+            Some(t) => t.most_frequent(),
             None => None,
         }
     }
+
+    pub(crate) fn probability<S>(&self, seq: &S, next_symbol: &Option<TSymbol>) -> f64
+    where
+        S: std::borrow::Borrow<[TSymbol]> + ?Sized,
+    {
+        match self.table(seq) {
+            Some(t) => t.probability_of(next_symbol),
+            None => 0.0,
+        }
+    }
+
+    pub(crate) fn distribution<S>(&self, seq: &S) -> Option<crate::Distribution<TSymbol>>
+    where
+        S: std::borrow::Borrow<[TSymbol]> + ?Sized,
+    {
+        self.table(seq).map(crate::Distribution::from_table)
+    }
+}
+
+impl<TSymbol, THasher> crate::ReadModel<TSymbol> for Model<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    fn order(&self) -> usize {
+        self.order
+    }
+
+    fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        Model::normalize(self, symbol)
+    }
+
+    fn find_context(&self, context: &[TSymbol]) -> Option<u32> {
+        self.table_with_id(context).map(|(id, _)| id)
+    }
+
+    fn contexts(&self) -> Box<dyn Iterator<Item = (u32, &[TSymbol])> + '_> {
+        Box::new(
+            self.contexts
+                .iter()
+                .zip(&self.tables)
+                .enumerate()
+                .filter(|(_, (_, t))| !t.is_empty())
+                .map(|(id, (seq, _))| (id as u32, seq.symbols())),
+        )
+    }
+
+    fn symbol_count(&self, id: u32) -> usize {
+        self.tables[id as usize].len()
+    }
+
+    fn ranked_symbol(&self, id: u32, rank: usize) -> (&Option<TSymbol>, usize) {
+        self.tables[id as usize].ranked(rank)
+    }
+
+    fn frequency_of(&self, id: u32, symbol: &Option<TSymbol>) -> usize {
+        self.tables[id as usize].frequency_of(symbol)
+    }
+
+    fn total(&self, id: u32) -> usize {
+        self.tables[id as usize].total()
+    }
+
+    fn sample(&self, id: u32, sample_value: f64) -> Option<&TSymbol> {
+        self.tables[id as usize].sample(sample_value)
+    }
+}
+
+impl<THasher> Model<u8, THasher>
+where
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Trains this model on the bytes read from the specified reader, treating
+    /// the entire stream as a single sequence.
+    ///
+    /// Input is read in chunks, and contexts are tracked without allocating
+    /// for each byte, making this much faster than adding bytes individually
+    /// via an [Accumulator](struct.Accumulator.html).
+    ///
+    /// # Arguments
+    ///
+    /// `reader` - The source of training bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(2);
+    /// model.train_bytes(&b"GET / HTTP/1.1\r\n"[..]).unwrap();
+    /// ```
+    pub fn train_bytes<R>(&mut self, mut reader: R) -> std::io::Result<()>
+    where
+        R: std::io::Read,
+    {
+        let mut buffer = [0u8; 8192];
+        let mut context: Vec<u8> = Vec::with_capacity(self.order);
+
+        loop {
+            let len = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            for byte in &buffer[..len] {
+                let byte = self.normalize(*byte);
+                self.add_weighted_to_context(&context, Some(byte), 1);
+
+                if self.order > 0 {
+                    if context.len() == self.order {
+                        context.remove(0);
+                    }
+                    context.push(byte);
+                }
+            }
+        }
+
+        self.add_weighted_to_context(&context, None, 1);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::error::Error;
+    use crate::evaluation::Unseen;
     use crate::model::Model;
+    use crate::read_model;
+    use crate::redistribution::Redistribution;
     use crate::sequence::Sequence;
 
+    // Asserts that two models have the same contexts, each followed by the
+    // same symbols with the same frequencies
+    fn assert_same_counts<T>(actual: &Model<T>, expected: &Model<T>)
+    where
+        T: Copy + std::hash::Hash + Eq + std::fmt::Debug,
+    {
+        assert_eq!(actual.context_count(), expected.context_count());
+        for (seq, t) in expected.tables() {
+            let table = actual.table(seq).unwrap();
+            assert_eq!(table.total(), t.total());
+            for (symbol, frequency) in t.iter() {
+                assert_eq!(table.frequency_of(symbol), frequency);
+            }
+        }
+    }
+
     #[test]
     fn it_adds_tables_for_each_new_added_sequence() {
         let mut m = Model::empty(1);
 
-        assert!(m.tables_by_seq.is_empty());
+        assert_eq!(m.context_count(), 0);
 
         let seq = Sequence::empty();
         m.add(&seq, Some('a'));
 
-        assert_eq!(m.tables_by_seq.len(), 1);
+        assert_eq!(m.context_count(), 1);
 
-        let t = m.tables_by_seq.get(&seq);
+        let t = m.table(&seq);
         assert!(t.is_some());
         assert_eq!(t.unwrap().most_frequent(), Some(&'a'));
 
         let seq = m.advance_sequence(&seq, 'a');
         m.add(&seq, Some('b'));
 
-        assert_eq!(m.tables_by_seq.len(), 2);
+        assert_eq!(m.context_count(), 2);
 
-        let t = m.tables_by_seq.get(&seq);
+        let t = m.table(&seq);
         assert!(t.is_some());
         assert_eq!(t.unwrap().most_frequent(), Some(&'b'));
     }
@@ -110,10 +1404,414 @@ mod test {
         m.add(&seq, Some('b'));
         m.add(&seq, Some('b'));
 
-        assert_eq!(m.tables_by_seq.len(), 1);
+        assert_eq!(m.context_count(), 1);
 
-        let t = m.tables_by_seq.get(&seq);
+        let t = m.table(&seq);
         assert!(t.is_some());
         assert_eq!(t.unwrap().most_frequent(), Some(&'b'));
     }
+
+    #[test]
+    fn it_exposes_end_probability_for_contexts() {
+        let mut m = Model::empty(2);
+
+        let seq = Sequence::empty();
+        m.add(&seq, Some('a'));
+        let seq = m.advance_sequence(&seq, 'a');
+        m.add(&seq, Some('b'));
+        let seq = m.advance_sequence(&seq, 'b');
+        m.add(&seq, None);
+        m.add(&seq, Some('c'));
+
+        assert_eq!(m.end_probability(&[]), Some(0.0));
+        assert_eq!(m.end_probability(&['a', 'b']), Some(0.5));
+        assert_eq!(m.end_probability(&['z', 'a', 'b']), Some(0.5));
+        assert_eq!(m.end_probability(&['b', 'c']), None);
+    }
+
+    #[test]
+    fn it_evaluates_test_sequences() {
+        let mut m = Model::empty(1);
+
+        let seq = Sequence::empty();
+        m.add(&seq, Some('a'));
+        m.add(&seq, Some('b'));
+        let seq = m.advance_sequence(&seq, 'a');
+        m.add(&seq, None);
+
+        let eval = m.evaluate(vec![vec!['a'], vec!['c']], Unseen::Skip);
+        assert_eq!(eval.scored, 2);
+        assert_eq!(eval.skipped, 2);
+        assert_eq!(eval.total_bits, 1.0);
+
+        let eval = m.evaluate(vec![vec!['a'], vec!['c']], Unseen::Floor(0.25));
+        assert_eq!(eval.scored, 4);
+        assert_eq!(eval.skipped, 0);
+        assert_eq!(eval.total_bits, 5.0);
+    }
+
+    #[test]
+    fn it_measures_kl_divergence_over_shared_contexts() {
+        let mut p = Model::empty(1);
+        let mut q = Model::empty(1);
+
+        let seq = Sequence::empty();
+        p.add(&seq, Some('a'));
+        p.add(&seq, Some('b'));
+        q.add(&seq, Some('a'));
+        q.add(&seq, Some('a'));
+        q.add(&seq, Some('a'));
+        q.add(&seq, Some('b'));
+        p.add(&p.advance_sequence(&seq, 'z'), Some('z'));
+
+        assert_eq!(p.kl_divergence(&p, Unseen::Skip), 0.0);
+
+        let expected = 0.5 * (0.5f64 / 0.75).log2() + 0.5 * (0.5f64 / 0.25).log2();
+        assert_eq!(p.kl_divergence(&q, Unseen::Skip), expected);
+
+        q.add(&seq, Some('c'));
+        p.add(&seq, Some('d'));
+        p.add(&seq, Some('d'));
+
+        let skipped = p.kl_divergence(&q, Unseen::Skip);
+        let floored = p.kl_divergence(&q, Unseen::Floor(0.01));
+        assert!(floored > skipped);
+    }
+
+    #[test]
+    fn it_measures_js_divergence_over_shared_contexts() {
+        let mut p = Model::empty(1);
+        let mut q = Model::empty(1);
+
+        let seq = Sequence::empty();
+        p.add(&seq, Some('a'));
+        q.add(&seq, Some('b'));
+
+        assert_eq!(p.js_divergence(&p), 0.0);
+        assert_eq!(p.js_divergence(&q), 1.0);
+
+        q.add(&seq, Some('a'));
+
+        let d = p.js_divergence(&q);
+        assert!(d > 0.0 && d < 1.0);
+        assert!((d - q.js_divergence(&p)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_trains_from_sequences() {
+        let m = Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.0), Some(&'a'));
+        let seq = m.advance_sequence(&seq, 'a');
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.0), Some(&'b'));
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.5), Some(&'c'));
+        assert_eq!(m.end_probability(&['b']), Some(1.0));
+        assert_eq!(m.end_probability(&['c']), Some(1.0));
+    }
+
+    #[test]
+    fn it_merges_other_models() {
+        let mut m = Model::train_from(1, vec!["ab".chars()]);
+        let other = Model::train_from(1, vec!["ac".chars(), "ac".chars()]);
+
+        m.merge(&other);
+
+        let seq = Sequence::empty();
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.0), Some(&'a'));
+        let seq = m.advance_sequence(&seq, 'a');
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.0), Some(&'b'));
+        assert_eq!(read_model::sample(&m, seq.symbols(), 0.34), Some(&'c'));
+        assert_eq!(m.end_probability(&['b']), Some(1.0));
+        assert_eq!(m.end_probability(&['c']), Some(1.0));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn it_trains_from_sequences_in_parallel() {
+        let corpus: Vec<_> = (0..100).map(|i| vec![i % 3, i % 5]).collect();
+
+        let m = Model::train_from_par(1, corpus.clone());
+        let expected = Model::train_from(1, corpus);
+
+        assert_same_counts(&m, &expected);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn it_merges_many_models_in_parallel() {
+        let corpus: Vec<String> = (0..100).map(|i| format!("seq{}", i % 23)).collect();
+        let shards: Vec<Model<char>> = corpus
+            .chunks(10)
+            .map(|chunk| Model::train_from(2, chunk.iter().map(|l| l.chars())))
+            .collect();
+
+        let m = Model::merge_all(&shards);
+        let expected = Model::train_from(2, corpus.iter().map(|l| l.chars()));
+
+        assert_same_counts(&m, &expected);
+    }
+
+    #[test]
+    fn it_generates_reproducible_batches() {
+        let m = Model::train_from(1, vec!["ab".chars(), "ac".chars(), "ad".chars()]);
+
+        let batch = m.generate_batch(20, 7);
+        assert_eq!(batch.len(), 20);
+        assert!(batch.iter().all(|s| s.len() == 2 && s[0] == 'a'));
+        assert_eq!(batch, m.generate_batch(20, 7));
+        assert_ne!(batch, m.generate_batch(20, 8));
+        assert!(m.generate_batch(0, 7).is_empty());
+
+        #[cfg(feature = "parallel")]
+        assert_eq!(m.generate_batch_par(20, 7), batch);
+    }
+
+    #[test]
+    fn it_generates_unrelated_batches_for_adjacent_seeds() {
+        let m = Model::train_from(
+            1,
+            vec![
+                "the cat sat on the mat".chars(),
+                "a rat and a bat ate the hat".chars(),
+            ],
+        );
+
+        let batch = m.generate_batch(20, 7);
+        let next = m.generate_batch(20, 8);
+
+        for i in 0..20 {
+            assert_ne!(next[i], batch[i]);
+            if i + 1 < 20 {
+                assert_ne!(next[i], batch[i + 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn it_keeps_context_ids_when_halving_counts() {
+        let mut m = Model::train_from(1, vec!["cd".chars(), "ab".chars(), "ab".chars()]);
+        let id = m.context_id(&['a']);
+
+        m.halve_counts();
+
+        assert_eq!(m.context_id(&['a']), id);
+        assert_eq!(m.context_id(&['c']), None);
+        assert_eq!(m.context_count(), 3);
+    }
+
+    #[test]
+    fn it_removes_trained_sequences() {
+        let mut m = Model::train_from(1, vec!["aab".chars(), "ac".chars()]);
+
+        assert_eq!(m.remove_sequence("aab".chars()), Ok(()));
+
+        let expected = Model::train_from(1, vec!["ac".chars()]);
+        assert_same_counts(&m, &expected);
+
+        assert_eq!(m.remove_sequence("ab".chars()), Err(Error::NotTrained));
+        assert_eq!(m.remove_sequence("acc".chars()), Err(Error::NotTrained));
+        assert_eq!(m.end_probability(&['c']), Some(1.0));
+
+        assert_eq!(m.remove_sequence("ac".chars()), Ok(()));
+        assert_eq!(m.context_count(), 0);
+    }
+
+    #[test]
+    fn it_normalizes_symbols_used_for_queries() {
+        let mut m = Model::empty(1);
+        m.set_normalizer(|c: char| c.to_ascii_lowercase());
+
+        let seq = Sequence::empty();
+        let seq = m.advance_sequence(&seq, 'a');
+        m.add(&seq, None);
+
+        assert_eq!(m.normalize('A'), 'a');
+        assert_eq!(m.end_probability(&['A']), Some(1.0));
+        assert_eq!(m.remove_sequence("A".chars()), Err(Error::NotTrained));
+    }
+
+    #[test]
+    fn it_caps_vocabulary_to_most_frequent_symbols() {
+        let mut m = Model::train_from(1, vec!["abab".chars(), "acd".chars()]);
+        m.cap_vocabulary(2, '?');
+
+        let expected = Model::train_from(1, vec!["abab".chars(), "a??".chars()]);
+        assert_same_counts(&m, &expected);
+    }
+
+    #[test]
+    fn it_sorts_contexts_independently_of_training_order() {
+        let corpus = vec!["abc".chars(), "bca".chars(), "cab".chars(), "acb".chars()];
+        let mut a = Model::train_from(2, corpus.clone());
+        let mut b = Model::train_from(2, corpus.into_iter().rev());
+        a.sort();
+        b.sort();
+
+        let transitions: Vec<_> = a.transitions().collect();
+        assert_eq!(transitions, b.transitions().collect::<Vec<_>>());
+        assert_eq!(transitions[0], (&[][..], Some(&'a'), 2));
+        assert_eq!(transitions[1], (&[][..], Some(&'b'), 1));
+        assert_eq!(transitions[2], (&[][..], Some(&'c'), 1));
+        assert_eq!(transitions[3], (&['a'][..], Some(&'b'), 1));
+
+        for (id, (seq, _)) in a.tables().enumerate() {
+            assert_eq!(b.context_id(seq.symbols()), Some(id as u32));
+        }
+    }
+
+    #[test]
+    fn it_prunes_rare_transitions() {
+        let corpus = vec!["ab".chars(), "ab".chars(), "ac".chars(), "d".chars()];
+
+        let mut m = Model::train_from(1, corpus.clone());
+        m.prune(2, Redistribution::Discard);
+
+        let seq = Sequence::empty();
+        assert_eq!(m.probability(&seq, &Some('a')), 1.0);
+        let seq = m.advance_sequence(&seq, 'a');
+        assert_eq!(m.probability(&seq, &Some('b')), 1.0);
+        assert_eq!(m.end_probability(&['c']), Some(1.0));
+        assert_eq!(m.end_probability(&['d']), Some(1.0));
+
+        let mut m = Model::train_from(1, corpus);
+        m.prune(2, Redistribution::Replace('?'));
+
+        let seq = Sequence::empty();
+        assert_eq!(m.probability(&seq, &Some('a')), 0.75);
+        assert_eq!(m.probability(&seq, &Some('?')), 0.25);
+        let seq = m.advance_sequence(&seq, 'a');
+        assert_eq!(m.probability(&seq, &Some('?')), 1.0 / 3.0);
+        assert_eq!(m.end_probability(&['?']), Some(1.0));
+
+        // Counts move to the replacement rather than being copied to it
+        let expected = Model::train_from(
+            1,
+            vec!["ab".chars(), "ab".chars(), "a?".chars(), "?".chars()],
+        );
+        assert_same_counts(&m, &expected);
+        assert_eq!(m.end_probability(&['c']), None);
+
+        // Symbols still reachable through kept transitions keep their contexts
+        let corpus = vec![
+            "ab".chars(),
+            "ab".chars(),
+            "cb".chars(),
+            "cb".chars(),
+            "ac".chars(),
+        ];
+        let mut m = Model::train_from(1, corpus.clone());
+        m.prune(2, Redistribution::Replace('?'));
+
+        assert_eq!(
+            m.context_count(),
+            Model::train_from(1, corpus).context_count()
+        );
+        assert_eq!(m.end_probability(&['c']), Some(1.0 / 3.0));
+        assert_eq!(m.end_probability(&['?']), None);
+    }
+
+    #[test]
+    fn it_generates_through_replacement_symbols() {
+        let corpus = vec!["abx".chars(), "abx".chars(), "acy".chars(), "cy".chars()];
+
+        let mut m = Model::train_from(1, corpus);
+        m.prune(2, Redistribution::Replace('?'));
+
+        // "c" is pruned after "a" and at the start, but is still followed by
+        // "y", which the replacement takes on
+        assert_eq!(
+            m.probability(&Sequence::from_slice(&['?'], 1), &Some('y')),
+            1.0
+        );
+
+        let mut values = vec![0.0, 0.9, 0.0, 0.0].into_iter();
+        let generated: Vec<char> =
+            crate::Generator::new(&m, Box::new(move || values.next().unwrap())).collect();
+        assert_eq!(generated, vec!['a', '?', 'y']);
+    }
+
+    #[test]
+    fn it_trains_from_byte_streams() {
+        let mut m = Model::empty(2);
+        m.train_bytes(&b"abcab"[..]).unwrap();
+
+        let expected = Model::train_from(2, vec![b"abcab".iter().copied()]);
+        assert_same_counts(&m, &expected);
+    }
+
+    #[test]
+    fn it_uses_the_specified_hasher() {
+        type Hasher = std::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+
+        let mut m = Model::with_hasher(1, Hasher::default());
+        crate::Accumulator::new(&mut m).train_sequence("ab".chars());
+
+        let mut other = Model::with_hasher(1, Hasher::default());
+        crate::Accumulator::new(&mut other).train_sequence("ac".chars());
+        m.merge(&other);
+        m.merge(&other);
+        m.prune(2, Redistribution::Discard);
+
+        let seq = m.advance_sequence(&Sequence::empty(), 'a');
+        assert_eq!(m.probability(&seq, &Some('b')), 0.0);
+        assert_eq!(m.probability(&seq, &Some('c')), 1.0);
+    }
+
+    #[test]
+    fn it_keeps_context_ids_when_transitions_are_removed() {
+        let mut m = Model::train_from(1, vec!["ab".chars(), "cd".chars()]);
+        let id = m.context_id(&['c']).unwrap();
+
+        m.remove_sequence("cd".chars()).unwrap();
+        assert_eq!(m.context_id(&['c']), None);
+        assert_eq!(m.context_symbols(id), Some(&['c'][..]));
+
+        crate::Accumulator::new(&mut m).train_sequence("ce".chars());
+        assert_eq!(m.context_id(&['c']), Some(id));
+        assert_eq!(m.context_count(), 5);
+    }
+
+    #[test]
+    fn it_filters_out_unseen_contexts() {
+        let mut m = Model::empty(2);
+        m.set_context_filter(Some(0.01));
+
+        // Enough contexts are added for the filter to be rebuilt as it grows
+        let mut acc = crate::Accumulator::new(&mut m);
+        for i in 0..1000u32 {
+            acc.train_sequence(vec![i, i + 1]);
+        }
+
+        let filter = m.context_filter.as_ref().unwrap();
+        assert!(!filter.is_full());
+
+        for i in 0..1000u32 {
+            let seq = Sequence::from_slice(&[i, i + 1], 2);
+            assert!(m.may_contain_context(seq.symbols()));
+            assert_eq!(m.probability(&seq, &None), 1.0);
+        }
+
+        let false_positives = (1000..11000u32)
+            .filter(|i| m.may_contain_context(&[*i, *i]))
+            .count();
+        assert!(false_positives < 300);
+        assert_eq!(m.predict(&Sequence::from_slice(&[5, 5], 2)), None);
+    }
+
+    #[test]
+    fn it_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Model<char>>();
+        assert_send_sync::<crate::FrozenModel<char>>();
+        assert_send_sync::<crate::QuantizedModel<char>>();
+        assert_send_sync::<crate::SuccinctModel<char>>();
+        assert_send_sync::<crate::InternedModel<String>>();
+        assert_send_sync::<crate::TrieModel<char>>();
+        assert_send_sync::<crate::OrdModel<char>>();
+        assert_send_sync::<crate::WeightedModel<char>>();
+        assert_send_sync::<crate::hmm::HiddenMarkovModel<char, char>>();
+        assert_send_sync::<crate::SuffixModel<char>>();
+    }
 }
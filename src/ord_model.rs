@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+/// A Markov model which orders its contexts and symbols rather than hashing
+/// them, as an alternative to [Model](struct.Model.html).
+///
+/// Symbols need only implement `Ord`, so that types without a `Hash`
+/// implementation, or with one which hashes poorly, can be modelled. Contexts
+/// and the symbols following each are held in sorted maps, so
+/// [transitions](#method.transitions) are always listed in sorted order, but
+/// each lookup takes logarithmic rather than constant time.
+///
+/// OrdModels can be converted to a [Model](struct.Model.html) with
+/// [`to_model`](#method.to_model) for use with Predictors and Generators, if
+/// their symbols can be hashed.
+pub struct OrdModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::cmp::Ord,
+{
+    order: usize,
+    tables: BTreeMap<Vec<TSymbol>, BTreeMap<Option<TSymbol>, usize>>,
+}
+
+impl<TSymbol> OrdModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::cmp::Ord,
+{
+    /// Creates an empty OrdModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    pub fn empty(order: usize) -> OrdModel<TSymbol> {
+        OrdModel {
+            order,
+            tables: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an OrdModel trained on the specified sequences.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    ///
+    /// `sequences` - The training sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::OrdModel::train_from(2, vec!["abcd".chars(), "abce".chars()]);
+    ///
+    /// assert_eq!(model.probability(&['b', 'c'], Some('d')), 0.5);
+    /// ```
+    pub fn train_from<I, S>(order: usize, sequences: I) -> OrdModel<TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        let mut model = OrdModel::empty(order);
+        for sequence in sequences {
+            model.train_sequence(sequence);
+        }
+        model
+    }
+
+    /// Trains the model on a complete sequence, including its end.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    pub fn train_sequence<I>(&mut self, sequence: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut context = vec![];
+        for symbol in sequence {
+            self.add(&context, Some(symbol));
+
+            context.push(symbol);
+            if context.len() > self.order {
+                context.remove(0);
+            }
+        }
+        self.add(&context, None);
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first. Only
+    /// the last `order` symbols are considered, and an empty context refers to
+    /// the beginning of a sequence.
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        match self.table(context) {
+            Some(t) => {
+                let total: usize = t.values().sum();
+                t.get(&symbol).map_or(0.0, |f| *f as f64 / total as f64)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// Ties are broken in favour of the least symbol, with the end of a
+    /// sequence being less than any symbol. `None` is returned if the context
+    /// has never been observed, or if it is most likely to be followed by the
+    /// end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`probability`](#method.probability).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::OrdModel::train_from(1, vec!["ac".chars(), "ab".chars()]);
+    ///
+    /// assert_eq!(model.predict(&['a']), Some(&'b'));
+    /// assert_eq!(model.predict(&['c']), None);
+    /// ```
+    pub fn predict(&self, context: &[TSymbol]) -> Option<&TSymbol> {
+        let t = self.table(context)?;
+
+        // Keys are visited in ascending order, so the first of several equally
+        // frequent symbols is kept
+        let mut best: Option<(&Option<TSymbol>, usize)> = None;
+        for (symbol, frequency) in t {
+            if best.map_or(true, |(_, f)| *frequency > f) {
+                best = Some((symbol, *frequency));
+            }
+        }
+        best.and_then(|(symbol, _)| symbol.as_ref())
+    }
+
+    /// Returns every transition of this model, as per
+    /// [`Model::transitions`](struct.Model.html#method.transitions), but with
+    /// contexts and the symbols following each in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::OrdModel::train_from(1, vec!["b".chars(), "ab".chars()]);
+    /// let transitions: Vec<_> = model.transitions().collect();
+    ///
+    /// assert_eq!(transitions[0], (&[][..], Some(&'a'), 1));
+    /// assert_eq!(transitions[1], (&[][..], Some(&'b'), 1));
+    /// assert_eq!(transitions[2], (&['a'][..], Some(&'b'), 1));
+    /// assert_eq!(transitions[3], (&['b'][..], None, 2));
+    /// ```
+    pub fn transitions(&self) -> impl Iterator<Item = (&[TSymbol], Option<&TSymbol>, usize)> {
+        self.tables.iter().flat_map(|(context, t)| {
+            t.iter()
+                .map(move |(symbol, frequency)| (&context[..], symbol.as_ref(), *frequency))
+        })
+    }
+
+    /// Generates a complete sequence from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::OrdModel::train_from(1, vec!["abc".chars()]);
+    ///
+    /// assert_eq!(model.generate(Box::new(|| 0.0)), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate(&self, mut rand_source: Box<dyn FnMut() -> f64 + '_>) -> Vec<TSymbol> {
+        let mut sequence: Vec<TSymbol> = vec![];
+
+        loop {
+            let context = &sequence[sequence.len().saturating_sub(self.order)..];
+            match self.sample(context, rand_source()) {
+                Some(symbol) => sequence.push(symbol),
+                None => return sequence,
+            }
+        }
+    }
+
+    /// Returns a [Model](struct.Model.html) with the same contexts and counts
+    /// as this one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::OrdModel::train_from(1, vec!["abc".chars()]).to_model();
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn to_model(&self) -> crate::Model<TSymbol>
+    where
+        TSymbol: std::hash::Hash,
+    {
+        let mut model = crate::Model::empty(self.order);
+        for (context, symbol, frequency) in self.transitions() {
+            model.add_weighted(context, symbol.copied(), frequency);
+        }
+        model
+    }
+
+    fn sample(&self, context: &[TSymbol], sample_value: f64) -> Option<TSymbol> {
+        let t = self.table(context)?;
+        let total: usize = t.values().sum();
+
+        let mut remaining = crate::random::scale(sample_value, total);
+        for (symbol, frequency) in t {
+            if remaining < *frequency {
+                return *symbol;
+            }
+            remaining -= frequency;
+        }
+        None
+    }
+
+    fn add(&mut self, context: &[TSymbol], symbol: Option<TSymbol>) {
+        let t = match self.tables.get_mut(context) {
+            Some(t) => t,
+            None => self.tables.entry(context.to_vec()).or_default(),
+        };
+        let frequency = t.entry(symbol).or_insert(0);
+        *frequency = frequency.saturating_add(1);
+    }
+
+    fn table(&self, context: &[TSymbol]) -> Option<&BTreeMap<Option<TSymbol>, usize>> {
+        let context = &context[context.len().saturating_sub(self.order)..];
+        self.tables.get(context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+    use crate::ord_model::OrdModel;
+
+    // A symbol which can be ordered but not hashed
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Token(u8);
+
+    #[test]
+    fn it_models_symbols_which_cannot_be_hashed() {
+        let corpus = vec![vec![Token(1), Token(2)], vec![Token(1), Token(3)]];
+        let m = OrdModel::train_from(1, corpus);
+
+        assert_eq!(m.context_count(), 4);
+        assert_eq!(m.probability(&[Token(1)], Some(Token(2))), 0.5);
+        assert_eq!(m.probability(&[Token(2)], None), 1.0);
+        assert_eq!(m.probability(&[Token(4)], None), 0.0);
+        assert_eq!(m.predict(&[Token(1)]), Some(&Token(2)));
+        assert_eq!(m.generate(Box::new(|| 0.9)), vec![Token(1), Token(3)]);
+    }
+
+    #[test]
+    fn it_matches_an_equivalent_model() {
+        let corpus = vec!["abcab".chars(), "abd".chars(), "cab".chars()];
+        let ord = OrdModel::train_from(2, corpus.clone());
+        let model = Model::train_from(2, corpus);
+
+        assert_eq!(ord.context_count(), model.context_count());
+        assert_eq!(ord.probability(&['a', 'b'], Some('c')), 0.25);
+        assert_eq!(ord.probability(&['x', 'a', 'b'], Some('d')), 0.25);
+
+        let converted = ord.to_model();
+        assert_eq!(converted.context_count(), model.context_count());
+        for (seq, t) in model.tables() {
+            let actual = converted.table(seq).unwrap();
+            for (symbol, frequency) in t.iter() {
+                assert_eq!(actual.frequency_of(symbol), frequency);
+            }
+        }
+    }
+}
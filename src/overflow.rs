@@ -0,0 +1,13 @@
+/// Strategies for stopping a [Generator](struct.Generator.html) once it
+/// reaches its maximum length.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    /// Generation stops abruptly once the maximum length is reached, even in
+    /// the middle of a sequence.
+    Truncate,
+
+    /// The final symbol is restricted to those after which the model has
+    /// observed a sequence ending, so that output stops at a natural ending
+    /// where possible. If no such symbol exists, the output is truncated.
+    ForceEnd,
+}
@@ -1,35 +1,250 @@
 /// A Predictor for finding the most probable future outcomes given past history
 /// based on a [Model](struct.Model.html).
 ///
-/// Predictors do not modify the underlying model.
-pub struct Predictor<'a, TSymbol>
+/// Predictors do not modify the underlying model. As models are `Send` and
+/// `Sync`, one model can be shared by many threads at once, each with its own
+/// Predictors, either by reference or through an `Arc`.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// let model = Arc::new(markov::Model::train_from(1, vec!["abc".chars()]));
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let model = Arc::clone(&model);
+///         std::thread::spawn(move || {
+///             let mut pre = markov::Predictor::new(&model);
+///             pre.given('a');
+///             pre.predict().copied()
+///         })
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap(), Some('b'));
+/// }
+/// ```
+pub struct Predictor<'a, TSymbol, M = crate::Model<TSymbol>>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
 {
-    model: &'a crate::Model<TSymbol>,
-    current_sequence: crate::Sequence<TSymbol>,
+    model: &'a M,
+    current_sequence: crate::Window<TSymbol>,
+    backoff: Option<f64>,
+    tie_break: crate::TieBreak<'a, TSymbol>,
+    unknown: crate::Unknown<TSymbol>,
+    vocabulary: Option<std::collections::HashSet<TSymbol>>,
+    given_unknown: bool,
+    cache_capacity: usize,
+    cache: std::cell::RefCell<Vec<(crate::Sequence<TSymbol>, Option<u32>)>>,
 }
 
-impl<'a, TSymbol> Predictor<'a, TSymbol>
+/// A snapshot of a [Predictor](struct.Predictor.html)'s context, which can be
+/// restored later to roll back any symbols given or predicted since.
+#[derive(Clone, Debug)]
+pub struct PredictorState<TSymbol>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
 {
-    /// Creates a Predictor which uses the specified model.
+    current_sequence: crate::Window<TSymbol>,
+    given_unknown: bool,
+}
+
+impl<'a, TSymbol, M> Predictor<'a, TSymbol, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq + 'a,
+    M: crate::ReadModel<TSymbol>,
+{
+    /// Creates a Predictor which uses the specified model, which may be either
+    /// a [Model](struct.Model.html) or a [FrozenModel](struct.FrozenModel.html).
     ///
     /// # Arguments
     ///
     /// `model` - The Markov model to base predictions on.
-    pub fn new(model: &crate::Model<TSymbol>) -> Predictor<TSymbol> {
+    pub fn new(model: &M) -> Predictor<'_, TSymbol, M> {
         Predictor {
             model,
-            current_sequence: crate::Sequence::empty(),
+            current_sequence: crate::Window::empty(model.order()),
+            backoff: None,
+            tie_break: crate::TieBreak::first_seen(),
+            unknown: crate::Unknown::Keep,
+            vocabulary: None,
+            given_unknown: false,
+            cache_capacity: 0,
+            cache: std::cell::RefCell::new(vec![]),
+        }
+    }
+
+    /// Enables a small cache of the most recently used contexts, so that
+    /// repeated predictions from the same few contexts avoid hashing them to
+    /// look them up in the model. Cached contexts are compared directly, so
+    /// the cache should be kept small.
+    ///
+    /// # Arguments
+    ///
+    /// `capacity` - The number of contexts to cache, or zero to disable
+    /// caching (the default).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abab".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.set_cache_capacity(4);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// assert_eq!(pre.probability_of('b'), 1.0);
+    /// ```
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache_capacity = capacity;
+        self.cache.get_mut().truncate(capacity);
+    }
+
+    /// Sets how symbols which the model has never observed are handled when
+    /// passed to [`given`](#method.given), and enables reporting of them via
+    /// [`given_unknown`](#method.given_unknown).
+    ///
+    /// By default, unknown symbols are kept without being detected. Enabling
+    /// detection collects the model's vocabulary, which takes time
+    /// proportional to the size of the model.
+    ///
+    /// # Arguments
+    ///
+    /// `unknown` - The strategy for handling unknown symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["the quick fox".split(' ')]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.set_unknown(markov::Unknown::Skip);
+    /// pre.given("quick");
+    /// pre.given("brown");
+    ///
+    /// assert!(pre.given_unknown());
+    /// assert_eq!(pre.predict(), Some(&"fox"));
+    /// ```
+    pub fn set_unknown(&mut self, unknown: crate::Unknown<TSymbol>) {
+        self.unknown = unknown;
+        if self.vocabulary.is_none() {
+            self.vocabulary = Some(crate::read_model::vocabulary(self.model));
         }
     }
 
+    /// Returns whether any symbol given since the beginning of the sequence
+    /// was never observed by the model, in which case predictions may no
+    /// longer be conditioned on the full context. This is only detected once
+    /// enabled via [`set_unknown`](#method.set_unknown).
+    pub fn given_unknown(&self) -> bool {
+        self.given_unknown
+    }
+
+    /// Sets the policy for choosing between equally frequent symbols when
+    /// predicting via [`predict`](#method.predict) or [`next`](#method.next),
+    /// so that predictions are reproducible regardless of the order in which
+    /// the model was trained.
+    ///
+    /// # Arguments
+    ///
+    /// `tie_break` - The tie-breaking policy to use. The default is
+    /// [`TieBreak::first_seen`](struct.TieBreak.html#method.first_seen).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ac".chars(), "ab".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), Some(&'c'));
+    ///
+    /// pre.set_tie_break(markov::TieBreak::lexicographic());
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn set_tie_break(&mut self, tie_break: crate::TieBreak<'a, TSymbol>) {
+        self.tie_break = tie_break;
+    }
+
+    /// Enables backing off to shorter contexts when the current context was
+    /// never observed during training, in the style of "stupid backoff". The
+    /// oldest symbols of the context are dropped one at a time until a known
+    /// context is found, and the probabilities reported for it are multiplied
+    /// by `discount` for each symbol dropped.
+    ///
+    /// Likewise, [`probability_of`](#method.probability_of) backs off for
+    /// symbols never observed following the current context.
+    ///
+    /// # Arguments
+    ///
+    /// `discount` - The factor applied for each symbol dropped from the
+    /// context, such as 0.4, or `None` to disable backoff (the default).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(2, vec!["abc".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('x');
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), None);
+    ///
+    /// pre.set_backoff(Some(0.4));
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// assert_eq!(pre.probability_of('b'), 0.4);
+    /// ```
+    pub fn set_backoff(&mut self, discount: Option<f64>) {
+        self.backoff = discount;
+    }
+
     /// Resets this Predictor so that the next symbol predicted will be the
     /// beginning of a sequence.
     pub fn end(&mut self) {
-        self.current_sequence = crate::Sequence::empty();
+        self.current_sequence.clear();
+        self.given_unknown = false;
+    }
+
+    /// Returns a snapshot of the current context, so that hypothetical
+    /// continuations can be explored and then rolled back via
+    /// [`restore_state`](#method.restore_state).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars(), "axd".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// let state = pre.save_state();
+    ///
+    /// pre.given('x');
+    /// assert_eq!(pre.predict(), Some(&'d'));
+    ///
+    /// pre.restore_state(&state);
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn save_state(&self) -> crate::PredictorState<TSymbol> {
+        crate::PredictorState {
+            current_sequence: self.current_sequence.clone(),
+            given_unknown: self.given_unknown,
+        }
+    }
+
+    /// Restores a context previously saved via
+    /// [`save_state`](#method.save_state), discarding any symbols given or
+    /// predicted since.
+    ///
+    /// # Arguments
+    ///
+    /// `state` - The snapshot to restore.
+    pub fn restore_state(&mut self, state: &crate::PredictorState<TSymbol>) {
+        self.current_sequence = state.current_sequence.clone();
+        self.given_unknown = state.given_unknown;
     }
 
     /// Specifies a prior symbol upon which future predictions will be based.
@@ -51,7 +266,52 @@ where
     /// let prediction = pre.predict(); // returns Some("fox")
     /// ```
     pub fn given(&mut self, symbol: TSymbol) {
-        self.current_sequence = self.model.advance_sequence(&self.current_sequence, symbol);
+        let mut symbol = self.model.normalize(symbol);
+
+        if let Some(vocabulary) = &self.vocabulary {
+            if !vocabulary.contains(&symbol) {
+                self.given_unknown = true;
+                match self.unknown {
+                    crate::Unknown::Keep => {}
+                    crate::Unknown::Skip => return,
+                    crate::Unknown::Replace(unk) => symbol = unk,
+                }
+            }
+        }
+        self.current_sequence.push(symbol);
+    }
+
+    /// Specifies several prior symbols at once, in order, upon which future
+    /// predictions will be based, as though each had been passed to
+    /// [`given`](#method.given).
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - The past symbols on which to base predictions, from least
+    /// to most recent. These may be given either by value or by reference,
+    /// such as from a slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(3, vec!["the quick brown fox".split(' ')]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given_all(&["the", "quick", "brown"]);
+    /// assert_eq!(pre.predict(), Some(&"fox"));
+    ///
+    /// pre.end();
+    /// pre.given_all("the quick".split(' '));
+    /// assert_eq!(pre.predict(), Some(&"brown"));
+    /// ```
+    pub fn given_all<I>(&mut self, symbols: I)
+    where
+        I: IntoIterator,
+        I::Item: std::borrow::Borrow<TSymbol>,
+    {
+        for symbol in symbols {
+            self.given(*std::borrow::Borrow::borrow(&symbol));
+        }
     }
 
     /// Predicts and returns the most probable next symbol based on previous
@@ -72,10 +332,11 @@ where
     /// }
     /// println!();
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<&TSymbol> {
-        match self.model.predict(&self.current_sequence) {
+        match self.most_frequent() {
             Some(s) => {
-                self.current_sequence = self.model.advance_sequence(&self.current_sequence, *s);
+                self.current_sequence.push(*s);
                 Some(s)
             }
             None => None,
@@ -89,7 +350,443 @@ where
     ///
     /// `None` is returned when the end of a sequence is reached.
     pub fn predict(&self) -> Option<&TSymbol> {
-        self.model.predict(&self.current_sequence)
+        self.most_frequent()
+    }
+
+    /// Greedily completes the current sequence by repeatedly taking the most
+    /// probable next symbol until the end of the sequence becomes the most
+    /// probable outcome, without advancing through the sequence. This is
+    /// useful for autocompletion; for completions ranked by their overall
+    /// probability instead, see [BeamSearch](struct.BeamSearch.html).
+    ///
+    /// # Arguments
+    ///
+    /// `max_len` - The maximum number of symbols to complete, guarding against
+    /// models which cycle without ever reaching an end.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["quick".chars(), "quiet".chars(), "quit".chars(), "quit".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given_all("qu".chars());
+    /// assert_eq!(pre.complete(10), vec!['i', 't']);
+    /// assert_eq!(pre.complete(1), vec!['i']);
+    /// ```
+    pub fn complete(&self, max_len: usize) -> Vec<TSymbol> {
+        self.lookahead(max_len)
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect()
+    }
+
+    /// Returns the next `n` symbols which [`next`](#method.next) would
+    /// predict, each with its probability given the symbols before it, while
+    /// leaving this Predictor's state untouched. This allows a preview of
+    /// where predictions are heading.
+    ///
+    /// Fewer than `n` symbols are returned if the end of the sequence is
+    /// predicted first, or the context becomes unknown.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The maximum number of symbols to look ahead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars(), "abd".chars(), "abd".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.lookahead(5), vec![('b', 1.0), ('d', 2.0 / 3.0)]);
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn lookahead(&self, n: usize) -> Vec<(TSymbol, f64)> {
+        let mut seq = self.current_sequence.clone();
+        let mut predictions = vec![];
+
+        while predictions.len() < n {
+            match self.most_frequent_after(seq.symbols()) {
+                Some(s) => {
+                    let (context, discount) = self.context_of(seq.symbols());
+                    let p = self.probability_after(context, &Some(*s)) * discount;
+                    predictions.push((*s, p));
+                    seq.push(*s);
+                }
+                None => break,
+            }
+        }
+
+        predictions
+    }
+
+    /// Returns whether the model has observed the current context (after any
+    /// [backoff](#method.set_backoff)), so that callers can tell whether
+    /// `None` from [`predict`](#method.predict) or [`next`](#method.next)
+    /// marks the natural end of a sequence or a context about which the model
+    /// knows nothing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('b');
+    /// assert_eq!(pre.predict(), None);
+    /// assert!(pre.knows_context());
+    ///
+    /// pre.given('x');
+    /// assert_eq!(pre.predict(), None);
+    /// assert!(!pre.knows_context());
+    /// ```
+    pub fn knows_context(&self) -> bool {
+        let (context, _) = self.context();
+        self.find_context(context).is_some()
+    }
+
+    /// Predicts the most probable continuation of `k` symbols by their joint
+    /// probability, based on previous symbols either predicted via
+    /// [`next`](#method.next) or specified via [`given`](#method.given),
+    /// without advancing through the sequence.
+    ///
+    /// Unlike repeated calls to [`next`](#method.next), which greedily choose
+    /// the most probable symbol one step at a time, this finds the best path
+    /// overall. Continuations which end before reaching `k` symbols are not
+    /// considered, and `None` is returned if there are none of length `k`.
+    ///
+    /// # Arguments
+    ///
+    /// `k` - The number of symbols to predict.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(
+    ///     1,
+    ///     vec!["xa".chars(), "xb".chars(), "xc".chars(), "yz".chars(), "yz".chars()],
+    /// );
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// assert_eq!(pre.predict(), Some(&'x'));
+    /// assert_eq!(pre.predict_path(2), Some(vec!['y', 'z']));
+    /// ```
+    pub fn predict_path(&self, k: usize) -> Option<Vec<TSymbol>> {
+        // Paths ending in the same context share the same possible
+        // continuations, so only the most probable of each need be kept
+        let mut paths = vec![(crate::Sequence::from(self.context().0), 0.0, vec![])];
+
+        for _ in 0..k {
+            let mut next_paths: Vec<(crate::Sequence<TSymbol>, f64, Vec<TSymbol>)> = vec![];
+            let mut indices = std::collections::HashMap::new();
+
+            for (sequence, log_p, symbols) in &paths {
+                let distribution = match self
+                    .find_context(sequence.symbols())
+                    .map(|id| crate::Distribution::from_context(self.model, id))
+                {
+                    Some(d) => d,
+                    None => continue,
+                };
+
+                for (symbol, p) in distribution.probabilities() {
+                    let s = match symbol {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    let next_sequence = sequence.with_next(s, self.model.order());
+                    let log_p = log_p + p.ln();
+
+                    match indices.get(&next_sequence) {
+                        Some(&i) => {
+                            let path: &mut (_, f64, Vec<TSymbol>) = &mut next_paths[i];
+                            if log_p > path.1 {
+                                path.1 = log_p;
+                                path.2 = symbols.clone();
+                                path.2.push(s);
+                            }
+                        }
+                        None => {
+                            let mut next_symbols = symbols.clone();
+                            next_symbols.push(s);
+                            indices.insert(next_sequence.clone(), next_paths.len());
+                            next_paths.push((next_sequence, log_p, next_symbols));
+                        }
+                    }
+                }
+            }
+
+            paths = next_paths;
+        }
+
+        let mut best: Option<(f64, Vec<TSymbol>)> = None;
+        for (_, log_p, symbols) in paths {
+            if best
+                .as_ref()
+                .map_or(true, |(best_log_p, _)| log_p > *best_log_p)
+            {
+                best = Some((log_p, symbols));
+            }
+        }
+        best.map(|(_, symbols)| symbols)
+    }
+
+    /// Returns up to `n` of the most probable next symbols based on previous
+    /// symbols either predicted via [`next`](#method.next) or specified via
+    /// [`given`](#method.given), along with their probabilities, ordered from
+    /// most to least probable.
+    ///
+    /// The end of a sequence is included as a candidate, represented by
+    /// `None`.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The maximum number of candidates to return.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars(), "a".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict_top(2), vec![(Some('b'), 0.5), (Some('c'), 0.25)]);
+    /// ```
+    pub fn predict_top(&self, n: usize) -> Vec<(Option<TSymbol>, f64)> {
+        let mut distribution = self.distribution();
+        distribution.truncate(n);
+        distribution
+    }
+
+    /// Returns the probabilities of all possible next symbols based on
+    /// previous symbols either predicted via [`next`](#method.next) or
+    /// specified via [`given`](#method.given), ordered from most to least
+    /// probable, so that callers can apply their own decision rules.
+    ///
+    /// The end of a sequence is included, represented by `None`. An empty
+    /// distribution is returned if the current context was never observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "a".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    ///
+    /// let distribution = pre.distribution();
+    /// assert_eq!(distribution.len(), 2);
+    /// assert!(distribution.contains(&(None, 0.5)));
+    /// ```
+    pub fn distribution(&self) -> Vec<(Option<TSymbol>, f64)> {
+        let (context, discount) = self.context();
+        match self
+            .find_context(context)
+            .map(|id| crate::Distribution::from_context(self.model, id))
+        {
+            Some(d) => d.probabilities().map(|(s, p)| (s, p * discount)).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the probabilities of the possible next symbols which satisfy a
+    /// predicate, renormalized among just those symbols and ordered from most
+    /// to least probable. This enables type-ahead completion, such as by
+    /// restricting a word model to words starting with the letters typed so
+    /// far.
+    ///
+    /// The end of a sequence is never included. An empty list is returned if
+    /// no possible next symbol satisfies the predicate.
+    ///
+    /// # Arguments
+    ///
+    /// `predicate` - A function returning whether a candidate symbol should be
+    /// considered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec![
+    ///     vec!["the", "quick", "fox"],
+    ///     vec!["the", "quiet", "fox"],
+    ///     vec!["the", "quiet", "owl"],
+    ///     vec!["the", "lazy", "dog"],
+    /// ]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given("the");
+    ///
+    /// let candidates = pre.predict_matching(|word| word.starts_with("qu"));
+    /// assert_eq!(candidates, vec![("quiet", 2.0 / 3.0), ("quick", 1.0 / 3.0)]);
+    /// ```
+    pub fn predict_matching<F>(&self, predicate: F) -> Vec<(TSymbol, f64)>
+    where
+        F: Fn(&TSymbol) -> bool,
+    {
+        let matches: Vec<_> = self
+            .distribution()
+            .into_iter()
+            .filter_map(|(s, p)| match s {
+                Some(s) if predicate(&s) => Some((s, p)),
+                _ => None,
+            })
+            .collect();
+
+        let total: f64 = matches.iter().map(|&(_, p)| p).sum();
+        matches.into_iter().map(|(s, p)| (s, p / total)).collect()
+    }
+
+    /// Returns the probability of the most likely next symbol (or end of
+    /// sequence), so that callers can decline to predict when the model is
+    /// unsure. Zero is returned if the current context was never observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars(), "a".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.confidence(), 0.5);
+    /// ```
+    pub fn confidence(&self) -> f64 {
+        match self.distribution().first() {
+            Some(&(_, p)) => p,
+            None => 0.0,
+        }
+    }
+
+    /// Returns the entropy, in bits, of the distribution of possible next
+    /// symbols (including the end of a sequence). Zero indicates that the
+    /// next symbol is certain, with larger values indicating more uncertainty.
+    /// Infinity is returned if the current context was never observed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars(), "bc".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.uncertainty(), 1.0);
+    ///
+    /// pre.given('c');
+    /// assert_eq!(pre.uncertainty(), 0.0);
+    /// ```
+    pub fn uncertainty(&self) -> f64 {
+        let (context, _) = self.context();
+        match self
+            .find_context(context)
+            .map(|id| crate::Distribution::from_context(self.model, id))
+        {
+            Some(d) => d.probabilities().map(|(_, p)| -p * p.log2()).sum(),
+            None => f64::INFINITY,
+        }
+    }
+
+    /// Returns the probability of the specified symbol being next, based on
+    /// previous symbols either predicted via [`next`](#method.next) or
+    /// specified via [`given`](#method.given), such as to score a user's
+    /// guess.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The candidate next symbol.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.probability_of('b'), 0.5);
+    /// assert_eq!(pre.probability_of('d'), 0.0);
+    /// ```
+    pub fn probability_of(&self, symbol: TSymbol) -> f64 {
+        let symbol = Some(self.model.normalize(symbol));
+
+        let discount = match self.backoff {
+            Some(d) => d,
+            None => return self.probability_after(self.current_sequence.symbols(), &symbol),
+        };
+
+        let mut context = self.current_sequence.symbols();
+        let mut factor = 1.0;
+        loop {
+            let p = self.probability_after(context, &symbol);
+            if p > 0.0 || context.is_empty() {
+                return p * factor;
+            }
+            context = &context[1..];
+            factor *= discount;
+        }
+    }
+
+    fn most_frequent(&self) -> Option<&'a TSymbol> {
+        self.most_frequent_after(self.current_sequence.symbols())
+    }
+
+    fn most_frequent_after(&self, seq: &[TSymbol]) -> Option<&'a TSymbol> {
+        let (context, _) = self.context_of(seq);
+        let model: &'a M = self.model;
+        let ties = match self.find_context(context) {
+            Some(id) => crate::read_model::most_frequent_ties(model, id),
+            None => vec![],
+        };
+        match self.tie_break.choose(&ties) {
+            Some(s) => s.as_ref(),
+            None => None,
+        }
+    }
+
+    fn probability_after(&self, seq: &[TSymbol], symbol: &Option<TSymbol>) -> f64 {
+        match self.find_context(seq) {
+            Some(id) => self.model.frequency_of(id, symbol) as f64 / self.model.total(id) as f64,
+            None => 0.0,
+        }
+    }
+
+    // Looks up the ID of a context, consulting the cache of most recently
+    // used contexts first
+    fn find_context(&self, seq: &[TSymbol]) -> Option<u32> {
+        if self.cache_capacity == 0 {
+            return self.model.find_context(seq);
+        }
+
+        let mut cache = self.cache.borrow_mut();
+        let entry = match cache.iter().position(|(s, _)| s.symbols() == seq) {
+            Some(i) => cache.remove(i),
+            None => {
+                if cache.len() == self.cache_capacity {
+                    cache.pop();
+                }
+                (crate::Sequence::from(seq), self.model.find_context(seq))
+            }
+        };
+
+        let id = entry.1;
+        cache.insert(0, entry);
+        id
+    }
+
+    // Returns the context on which predictions are based, along with the
+    // discount incurred by backing off to it
+    fn context(&self) -> (&[TSymbol], f64) {
+        self.context_of(self.current_sequence.symbols())
+    }
+
+    fn context_of<'s>(&self, seq: &'s [TSymbol]) -> (&'s [TSymbol], f64) {
+        match self.backoff {
+            Some(discount) => {
+                let context = crate::read_model::back_off(self.model, seq);
+                let dropped = seq.len() - context.len();
+                (context, discount.powi(dropped as i32))
+            }
+            None => (seq, 1.0),
+        }
     }
 }
 
@@ -98,6 +795,8 @@ mod test {
     use crate::model::Model;
     use crate::predictor::Predictor;
     use crate::sequence::Sequence;
+    use crate::tie_break::TieBreak;
+    use crate::unknown::Unknown;
 
     #[test]
     fn it_predicts_most_probable_sequences() {
@@ -152,4 +851,353 @@ mod test {
         assert_eq!(pre.next(), Some(&"penguin"));
         assert_eq!(pre.next(), None);
     }
+
+    #[test]
+    fn it_accepts_several_given_symbols_at_once() {
+        let model = Model::train_from(2, vec!["abcd".chars(), "xbce".chars()]);
+
+        let context: &[char] = &['x', 'b'];
+
+        let mut pre = Predictor::new(&model);
+        pre.given_all(context);
+        assert_eq!(pre.next(), Some(&'c'));
+
+        pre.end();
+        pre.given_all("zab".chars());
+        assert_eq!(pre.next(), Some(&'c'));
+        assert_eq!(pre.next(), Some(&'d'));
+    }
+
+    #[test]
+    fn it_predicts_top_candidates_with_probabilities() {
+        let model = Model::train_from(
+            1,
+            vec!["ab".chars(), "ac".chars(), "ac".chars(), "a".chars()],
+        );
+
+        let mut pre = Predictor::new(&model);
+        assert_eq!(pre.predict_top(3), vec![(Some('a'), 1.0)]);
+
+        pre.given('a');
+        assert_eq!(
+            pre.predict_top(5),
+            vec![(Some('c'), 0.5), (Some('b'), 0.25), (None, 0.25)]
+        );
+        assert_eq!(pre.predict_top(0), vec![]);
+
+        pre.given('z');
+        assert_eq!(pre.predict_top(3), vec![]);
+    }
+
+    #[test]
+    fn it_exposes_probability_of_candidate_symbols() {
+        let model = Model::train_from(
+            1,
+            vec!["ab".chars(), "ac".chars(), "ac".chars(), "a".chars()],
+        );
+
+        let mut pre = Predictor::new(&model);
+        assert_eq!(pre.probability_of('a'), 1.0);
+        assert_eq!(pre.probability_of('b'), 0.0);
+
+        pre.given('a');
+        assert_eq!(pre.probability_of('c'), 0.5);
+        assert_eq!(pre.probability_of('b'), 0.25);
+        assert_eq!(pre.probability_of('z'), 0.0);
+
+        pre.given('z');
+        assert_eq!(pre.probability_of('a'), 0.0);
+    }
+
+    #[test]
+    fn it_exposes_the_full_distribution_of_next_symbols() {
+        let model = Model::train_from(
+            1,
+            vec!["ab".chars(), "ac".chars(), "ac".chars(), "a".chars()],
+        );
+
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+
+        let distribution = pre.distribution();
+        assert_eq!(
+            distribution,
+            vec![(Some('c'), 0.5), (Some('b'), 0.25), (None, 0.25)]
+        );
+        assert_eq!(distribution.iter().map(|(_, p)| p).sum::<f64>(), 1.0);
+
+        pre.given('z');
+        assert!(pre.distribution().is_empty());
+    }
+
+    #[test]
+    fn it_predicts_the_most_probable_path() {
+        let model = Model::train_from(
+            1,
+            vec![
+                "xab".chars(),
+                "xcd".chars(),
+                "xef".chars(),
+                "yzw".chars(),
+                "yzw".chars(),
+            ],
+        );
+
+        let mut pre = Predictor::new(&model);
+        assert_eq!(pre.predict_path(0), Some(vec![]));
+        assert_eq!(pre.predict_path(1), Some(vec!['x']));
+        assert_eq!(pre.predict_path(3), Some(vec!['y', 'z', 'w']));
+        assert_eq!(pre.predict_path(4), None);
+
+        pre.given('x');
+        assert_eq!(pre.predict_path(2), Some(vec!['a', 'b']));
+        assert_eq!(pre.predict(), Some(&'a'));
+    }
+
+    #[test]
+    fn it_backs_off_to_shorter_contexts_with_a_discount() {
+        let model = Model::train_from(2, vec!["abc".chars(), "bd".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        pre.given_all("xb".chars());
+        assert_eq!(pre.predict(), None);
+        assert!(pre.distribution().is_empty());
+        assert_eq!(pre.probability_of('d'), 0.0);
+
+        pre.set_backoff(Some(0.5));
+        assert_eq!(pre.predict(), Some(&'d'));
+        assert_eq!(pre.distribution(), vec![(Some('d'), 0.5)]);
+        assert_eq!(pre.probability_of('a'), 0.125);
+
+        pre.end();
+        pre.given_all("ab".chars());
+        assert_eq!(pre.probability_of('c'), 1.0);
+        assert_eq!(pre.probability_of('d'), 0.5);
+
+        pre.end();
+        pre.given_all("xy".chars());
+        assert_eq!(pre.next(), Some(&'a'));
+        assert_eq!(pre.next(), Some(&'b'));
+        assert_eq!(pre.next(), Some(&'c'));
+    }
+
+    #[test]
+    fn it_reports_confidence_and_uncertainty() {
+        let model = Model::train_from(
+            1,
+            vec![
+                "ab".chars(),
+                "ac".chars(),
+                "ad".chars(),
+                "ae".chars(),
+                "bc".chars(),
+            ],
+        );
+
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+        assert_eq!(pre.confidence(), 0.25);
+        assert_eq!(pre.uncertainty(), 2.0);
+
+        pre.given('c');
+        assert_eq!(pre.confidence(), 1.0);
+        assert_eq!(pre.uncertainty(), 0.0);
+
+        pre.given('x');
+        assert_eq!(pre.confidence(), 0.0);
+        assert_eq!(pre.uncertainty(), f64::INFINITY);
+    }
+
+    #[test]
+    fn it_breaks_ties_according_to_the_configured_policy() {
+        let model = Model::train_from(
+            1,
+            vec![
+                "xc".chars(),
+                "xb".chars(),
+                "xa".chars(),
+                "xa".chars(),
+                "xb".chars(),
+            ],
+        );
+
+        let mut pre = Predictor::new(&model);
+        pre.given('x');
+        assert_eq!(pre.predict(), Some(&'b'));
+
+        pre.set_tie_break(TieBreak::lexicographic());
+        assert_eq!(pre.predict(), Some(&'a'));
+
+        let mut values = vec![0.9, 0.1].into_iter();
+        let mut rand_source = move || values.next().unwrap();
+        pre.set_tie_break(TieBreak::random(&mut rand_source));
+        assert_eq!(pre.predict(), Some(&'a'));
+        assert_eq!(pre.next(), Some(&'b'));
+    }
+
+    #[test]
+    fn it_restores_saved_states() {
+        let model = Model::train_from(2, vec!["abc".chars(), "xbd".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        let start = pre.save_state();
+
+        pre.given('a');
+        pre.given('b');
+        let branch = pre.save_state();
+        assert_eq!(pre.next(), Some(&'c'));
+        assert_eq!(pre.next(), None);
+
+        pre.restore_state(&branch);
+        assert_eq!(pre.predict(), Some(&'c'));
+
+        pre.restore_state(&start);
+        assert_eq!(pre.predict(), Some(&'a'));
+    }
+
+    #[test]
+    fn it_completes_sequences_without_advancing() {
+        let model = Model::train_from(1, vec!["abab".chars(), "ab".chars(), "ab".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+        assert_eq!(pre.complete(10), vec!['b']);
+        assert_eq!(pre.predict(), Some(&'b'));
+
+        let model = Model::train_from(1, vec!["abababa".chars()]);
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+        assert_eq!(pre.complete(3), vec!['b', 'a', 'b']);
+
+        pre.given('x');
+        assert!(pre.complete(3).is_empty());
+    }
+
+    #[test]
+    fn it_predicts_among_matching_symbols() {
+        let model = Model::train_from(
+            1,
+            vec![
+                "ab".chars(),
+                "ac".chars(),
+                "ac".chars(),
+                "ad".chars(),
+                "a".chars(),
+            ],
+        );
+
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+        assert_eq!(
+            pre.predict_matching(|&s| s != 'c'),
+            vec![('b', 0.5), ('d', 0.5)]
+        );
+        assert_eq!(pre.predict_matching(|&s| s == 'c'), vec![('c', 1.0)]);
+        assert!(pre.predict_matching(|&s| s == 'z').is_empty());
+    }
+
+    #[test]
+    fn it_handles_unknown_given_symbols() {
+        let model = Model::train_from(2, vec!["ab?c".chars(), "abd".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        pre.given_all("ax".chars());
+        assert!(!pre.given_unknown());
+        assert_eq!(pre.predict(), None);
+
+        pre.end();
+        pre.set_unknown(Unknown::Keep);
+        pre.given_all("ax".chars());
+        assert!(pre.given_unknown());
+        assert_eq!(pre.predict(), None);
+
+        pre.end();
+        assert!(!pre.given_unknown());
+        pre.set_unknown(Unknown::Skip);
+        pre.given_all("axb".chars());
+        assert!(pre.given_unknown());
+        assert_eq!(pre.predict(), Some(&'?'));
+
+        pre.end();
+        pre.set_unknown(Unknown::Replace('?'));
+        pre.given_all("bx".chars());
+        assert_eq!(pre.predict(), Some(&'c'));
+
+        pre.end();
+        pre.given_all("ab".chars());
+        assert!(!pre.given_unknown());
+    }
+
+    #[test]
+    fn it_caches_recently_used_contexts() {
+        let model = Model::train_from(1, vec!["abcab".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        pre.set_cache_capacity(2);
+        pre.given('a');
+        assert_eq!(pre.next(), Some(&'b'));
+        assert_eq!(pre.next(), Some(&'c'));
+        assert_eq!(pre.next(), Some(&'a'));
+        assert_eq!(pre.next(), Some(&'b'));
+        assert_eq!(pre.probability_of('c'), 0.5);
+
+        let cached: Vec<_> = pre.cache.borrow().iter().map(|(s, _)| s.clone()).collect();
+        assert_eq!(
+            cached,
+            vec![
+                Sequence::from_slice(&['b'], 1),
+                Sequence::from_slice(&['a'], 1)
+            ]
+        );
+
+        pre.given('x');
+        assert_eq!(pre.predict(), None);
+        assert_eq!(pre.cache.borrow().len(), 2);
+    }
+
+    #[test]
+    fn it_distinguishes_sequence_ends_from_unknown_contexts() {
+        let model = Model::train_from(2, vec!["abc".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        assert!(pre.knows_context());
+        pre.given_all("abc".chars());
+        assert!(pre.knows_context());
+        assert_eq!(pre.next(), None);
+
+        pre.given('x');
+        assert!(!pre.knows_context());
+
+        pre.set_backoff(Some(0.4));
+        assert!(pre.knows_context());
+    }
+
+    #[test]
+    fn it_looks_ahead_without_advancing() {
+        let model = Model::train_from(1, vec!["abcd".chars(), "abce".chars()]);
+
+        let mut pre = Predictor::new(&model);
+        pre.given('a');
+        assert_eq!(pre.lookahead(2), vec![('b', 1.0), ('c', 1.0)]);
+        assert_eq!(pre.lookahead(5), vec![('b', 1.0), ('c', 1.0), ('d', 0.5)]);
+        assert_eq!(pre.next(), Some(&'b'));
+        assert!(pre.lookahead(0).is_empty());
+
+        pre.given('x');
+        assert!(pre.lookahead(3).is_empty());
+    }
+
+    #[test]
+    fn it_can_be_moved_between_threads() {
+        let model = Model::train_from(1, vec!["abc".chars()]);
+
+        let mut values = vec![0.0].into_iter();
+        let mut rand_source = move || values.next().unwrap();
+        let mut pre = Predictor::new(&model);
+        pre.set_tie_break(TieBreak::random(&mut rand_source));
+        pre.given('a');
+
+        let output = std::thread::scope(|s| s.spawn(move || pre.next().copied()).join());
+        assert_eq!(output.unwrap(), Some('b'));
+    }
 }
@@ -0,0 +1,12 @@
+/// A snapshot of the progress made by an [Accumulator](struct.Accumulator.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of symbols added so far.
+    pub symbols: usize,
+
+    /// The number of sequences ended so far.
+    pub sequences: usize,
+
+    /// The number of distinct contexts currently in the model.
+    pub contexts: usize,
+}
@@ -0,0 +1,221 @@
+// Probabilities are held as multiples of 1/65535, so that a certainty fits in
+// 16 bits
+const SCALE: f64 = u16::MAX as f64;
+
+/// A compact, read-only form of a [Model](struct.Model.html), whose
+/// distributions are quantized to 16-bit fixed-point probabilities, as
+/// returned by [`Model::quantize`](struct.Model.html#method.quantize).
+///
+/// Raw counts are discarded, and the symbols of every context are held in a
+/// single contiguous array, making quantized models suitable for
+/// memory-constrained deployments where exact counts no longer matter.
+/// Every symbol observed in a context keeps a non-zero probability, but
+/// probabilities are otherwise only accurate to within about 1/65535.
+pub struct QuantizedModel<TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    order: usize,
+    contexts: crate::interner::Interner<crate::Sequence<TSymbol>, THasher>,
+    offsets: Vec<u32>,
+    symbols: Vec<Option<TSymbol>>,
+    weights: Vec<u16>,
+    normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+}
+
+impl<TSymbol, THasher> QuantizedModel<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    pub(crate) fn new(model: crate::Model<TSymbol, THasher>) -> QuantizedModel<TSymbol, THasher> {
+        let mut contexts = crate::interner::Interner::with_hasher(model.hasher().clone());
+        let mut offsets = vec![0];
+        let mut symbols = vec![];
+        let mut weights = vec![];
+
+        for (seq, t) in model.tables() {
+            contexts.intern(seq.symbols());
+
+            let total = t.total() as f64;
+            for (symbol, frequency) in t.iter() {
+                let weight = (frequency as f64 * SCALE / total).round().max(1.0);
+                symbols.push(*symbol);
+                weights.push(weight as u16);
+            }
+            offsets.push(symbols.len() as u32);
+        }
+
+        symbols.shrink_to_fit();
+        weights.shrink_to_fit();
+
+        QuantizedModel {
+            order: model.order(),
+            contexts,
+            offsets,
+            symbols,
+            weights,
+            normalizer: model.into_normalizer(),
+        }
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first, as per
+    /// [`Model::end_probability`](struct.Model.html#method.end_probability).
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]).quantize();
+    ///
+    /// assert_eq!(model.probability(&['a'], Some('b')), 0.5);
+    /// ```
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        let symbol = symbol.map(|s| self.normalize(s));
+
+        match self.entries(context) {
+            Some((symbols, weights)) => {
+                let total: u32 = weights.iter().map(|w| *w as u32).sum();
+                symbols
+                    .iter()
+                    .position(|s| *s == symbol)
+                    .map_or(0.0, |i| weights[i] as f64 / total as f64)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// `None` is returned if the context has never been observed, or if it is
+    /// most likely to be followed by the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`probability`](#method.probability).
+    pub fn predict(&self, context: &[TSymbol]) -> Option<&TSymbol> {
+        self.entries(context)
+            .and_then(|(symbols, _)| symbols.first())
+            .and_then(|s| s.as_ref())
+    }
+
+    /// Generates a complete sequence from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars()]).quantize();
+    ///
+    /// assert_eq!(model.generate(Box::new(|| 0.0)), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate(&self, mut rand_source: Box<dyn FnMut() -> f64 + '_>) -> Vec<TSymbol> {
+        let mut sequence = vec![];
+        let mut seq = crate::Sequence::empty();
+
+        while let Some(symbol) = self.sample(seq.symbols(), rand_source()) {
+            sequence.push(symbol);
+            seq = seq.with_next(symbol, self.order);
+        }
+
+        sequence
+    }
+
+    fn sample(&self, context: &[TSymbol], sample_value: f64) -> Option<TSymbol> {
+        let (symbols, weights) = self.entries(context)?;
+        let total: u32 = weights.iter().map(|w| *w as u32).sum();
+
+        let mut remaining = crate::random::scale(sample_value, total as usize) as u32;
+        for (symbol, weight) in symbols.iter().zip(weights) {
+            if remaining < *weight as u32 {
+                return *symbol;
+            }
+            remaining -= *weight as u32;
+        }
+        None
+    }
+
+    fn entries(&self, context: &[TSymbol]) -> Option<(&[Option<TSymbol>], &[u16])> {
+        let context: Vec<TSymbol> = context.iter().map(|s| self.normalize(*s)).collect();
+        let seq = crate::Sequence::from_slice(&context, self.order);
+
+        let id = self.contexts.get(seq.symbols())? as usize;
+        let start = self.offsets[id] as usize;
+        let end = self.offsets[id + 1] as usize;
+        Some((&self.symbols[start..end], &self.weights[start..end]))
+    }
+
+    fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        match &self.normalizer {
+            Some(normalizer) => normalizer(symbol),
+            None => symbol,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+
+    #[test]
+    fn it_quantizes_distributions_to_sixteen_bits() {
+        let mut m = Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ab".chars()]);
+        crate::Accumulator::new(&mut m).train_sequence("ac".chars());
+        let q = m.quantize();
+
+        assert_eq!(q.context_count(), 4);
+        assert_eq!(q.weights, vec![65535, 49151, 16384, 65535, 65535]);
+        assert!((q.probability(&['a'], Some('b')) - 0.75).abs() < 1e-4);
+        assert!((q.probability(&['a'], Some('c')) - 0.25).abs() < 1e-4);
+        assert_eq!(q.probability(&['a'], Some('d')), 0.0);
+        assert_eq!(q.probability(&['d'], Some('a')), 0.0);
+        assert_eq!(q.predict(&['a']), Some(&'b'));
+        assert_eq!(q.predict(&['b']), None);
+    }
+
+    #[test]
+    fn it_keeps_rare_symbols_probable() {
+        let mut m = Model::empty(0);
+        let mut acc = crate::Accumulator::new(&mut m);
+        for _ in 0..100_000 {
+            acc.add('a');
+        }
+        acc.add('b');
+        let q = m.quantize();
+
+        assert!(q.probability(&[], Some('b')) > 0.0);
+    }
+
+    #[test]
+    fn it_samples_in_proportion_to_quantized_weights() {
+        let q = Model::train_from(0, vec!["aaab".chars()]).quantize();
+
+        // "a", "b" and the end are quantized to 3/5, 1/5 and 1/5
+        let mut values = vec![0.0, 0.5, 0.59, 0.7, 0.9].into_iter();
+        let generated = q.generate(Box::new(move || values.next().unwrap()));
+        assert_eq!(generated, vec!['a', 'a', 'a', 'b']);
+    }
+}
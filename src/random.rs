@@ -0,0 +1,170 @@
+/// A source of random values which determines how a
+/// [Generator](struct.Generator.html) samples symbols.
+///
+/// This is implemented for any `FnMut() -> f64` closure, and for any
+/// [`rand::Rng`](https://docs.rs/rand) via [RngSource](struct.RngSource.html).
+pub trait RandomSource {
+    /// Returns the next random value, in the [0.0, 1.0) range.
+    fn next_f64(&mut self) -> f64;
+}
+
+impl<F> RandomSource for F
+where
+    F: FnMut() -> f64,
+{
+    fn next_f64(&mut self) -> f64 {
+        self()
+    }
+}
+
+/// A [RandomSource](trait.RandomSource.html) which draws values from a
+/// [`rand::Rng`](https://docs.rs/rand).
+pub struct RngSource<G>(pub G);
+
+impl<G> RandomSource for RngSource<G>
+where
+    G: rand::Rng,
+{
+    fn next_f64(&mut self) -> f64 {
+        self.0.gen::<f64>()
+    }
+}
+
+/// A [RandomSource](trait.RandomSource.html) which produces a fixed sequence
+/// of values from a seed, as used by
+/// [`Generator::seeded`](struct.Generator.html#method.seeded).
+///
+/// Values are generated with the xoshiro256** algorithm, whose state is
+/// initialised from the seed by SplitMix64. Unlike the generators of the
+/// `rand` crate, which may change between its versions, this algorithm is
+/// fixed, so a seed produces the same values with every version of this crate.
+#[derive(Clone, Debug)]
+pub struct SeededSource {
+    state: [u64; 4],
+}
+
+impl SeededSource {
+    /// Creates a source whose values are determined by the specified seed.
+    ///
+    /// # Arguments
+    ///
+    /// `seed` - The seed from which values are generated.
+    pub fn new(seed: u64) -> SeededSource {
+        let mut seed = seed;
+        SeededSource {
+            state: [
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+            ],
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+}
+
+impl RandomSource for SeededSource {
+    fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa, giving every multiple of 2^-53
+        (self.next_u64() >> 11) as f64 / (1u64 << f64::MANTISSA_DIGITS) as f64
+    }
+}
+
+// Advances a SplitMix64 state and returns its next output, which is well mixed
+// even for states that differ in only a few bits
+pub(crate) fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Derives a seed from both a seed and an index, such as the position of a
+// sequence in a batch, so that neighbouring seeds and indices give unrelated
+// results rather than, say, the same seeds in a shifted order
+pub(crate) fn mix_seed(seed: u64, index: u64) -> u64 {
+    let mut index = index;
+    let mut state = seed ^ split_mix(&mut index);
+    split_mix(&mut state)
+}
+
+// Scales a value in the [0, 1) range to an index in the [0, n) range, as the
+// exact floor of `sample_value * n`. Multiplying in floating point instead can
+// round up across an integer boundary, skewing which index is drawn, or even
+// produce `n` itself for values just below 1. Values outside the range are
+// clamped into it.
+pub(crate) fn scale(sample_value: f64, n: usize) -> usize {
+    const BITS: u32 = f64::MANTISSA_DIGITS;
+
+    if n == 0 {
+        return 0;
+    }
+
+    // Values from a uniform source are multiples of 2^-53, so this is exact
+    let numerator = (sample_value.clamp(0.0, 1.0) * (1u64 << BITS) as f64) as u128;
+    let index = (numerator * n as u128) >> BITS;
+    (index as usize).min(n - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::random::{scale, RandomSource, RngSource, SeededSource};
+    use rand::SeedableRng;
+
+    #[test]
+    fn it_draws_values_in_unit_range_from_rngs() {
+        let mut source = RngSource(rand::rngs::StdRng::seed_from_u64(1));
+
+        for _ in 0..100 {
+            let v = source.next_f64();
+            assert!((0.0..1.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn it_draws_fixed_values_from_seeds() {
+        let mut source = SeededSource::new(0);
+        assert_eq!(source.next_u64(), 0x99ec_5f36_cb75_f2b4);
+        assert_eq!(source.next_u64(), 0xbf6e_1f78_4956_452a);
+        assert_eq!(source.next_u64(), 0x1a5f_849d_4933_e6e0);
+
+        let mut source = SeededSource::new(42);
+        assert_eq!(source.next_f64(), 0.08386297105988216);
+        assert_eq!(source.next_u64(), 0x6104_d986_6d11_3a7e);
+
+        let mut source = SeededSource::new(0);
+        for _ in 0..100 {
+            let v = source.next_f64();
+            assert!((0.0..1.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn it_scales_values_exactly_to_indices() {
+        assert_eq!(scale(0.0, 3), 0);
+        assert_eq!(scale(1.0 / 3.0, 3), 0);
+        assert_eq!(scale(2.0 / 3.0, 3), 1);
+        assert_eq!(scale(0.5, 3), 1);
+        assert!(scale(1.0 - f64::EPSILON / 2.0, usize::MAX) < usize::MAX);
+
+        assert_eq!(scale(1.0, 3), 2);
+        assert_eq!(scale(-0.5, 3), 0);
+        assert_eq!(scale(f64::NAN, 3), 0);
+        assert_eq!(scale(0.5, 0), 0);
+    }
+}
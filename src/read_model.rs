@@ -0,0 +1,319 @@
+/// Read access to a trained Markov model, through which
+/// [Predictors](struct.Predictor.html) and [Generators](struct.Generator.html)
+/// can use either a [Model](struct.Model.html) or a
+/// [FrozenModel](struct.FrozenModel.html).
+///
+/// Contexts are identified by the IDs returned by
+/// [`find_context`](#tymethod.find_context). The symbols which have followed
+/// each context are ranked from most to least frequent, with ties in the order
+/// in which they were first seen, and `None` refers to the end of a sequence.
+///
+/// # Example
+///
+/// ```
+/// use markov::ReadModel;
+///
+/// let model = markov::Model::train_from(1, vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+///
+/// let id = model.find_context(&['a']).unwrap();
+/// assert_eq!(model.symbol_count(id), 2);
+/// assert_eq!(model.ranked_symbol(id, 0), (&Some('b'), 2));
+/// assert_eq!(model.frequency_of(id, &Some('c')), 1);
+/// assert_eq!(model.total(id), 3);
+/// ```
+pub trait ReadModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Returns the order of the model.
+    fn order(&self) -> usize;
+
+    /// Returns a symbol in the form in which the model holds it, as per
+    /// [`Model::set_normalizer`](struct.Model.html#method.set_normalizer).
+    fn normalize(&self, symbol: TSymbol) -> TSymbol;
+
+    /// Returns the ID of a context, or `None` if it has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The symbols of the context, oldest first, which must
+    /// already be normalized and be no more than the order of the model.
+    fn find_context(&self, context: &[TSymbol]) -> Option<u32>;
+
+    /// Returns every context which has been observed, along with its ID.
+    fn contexts(&self) -> Box<dyn Iterator<Item = (u32, &[TSymbol])> + '_>;
+
+    /// Returns the number of distinct symbols which have followed a context.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    fn symbol_count(&self, id: u32) -> usize;
+
+    /// Returns a symbol which has followed a context, along with the number
+    /// of times it did so.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    ///
+    /// `rank` - The rank of the symbol, from zero for the most frequent to
+    /// one less than the [`symbol_count`](#tymethod.symbol_count).
+    fn ranked_symbol(&self, id: u32, rank: usize) -> (&Option<TSymbol>, usize);
+
+    /// Returns the number of times a symbol has followed a context.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    ///
+    /// `symbol` - The symbol, where `None` refers to the end of a sequence.
+    fn frequency_of(&self, id: u32, symbol: &Option<TSymbol>) -> usize;
+
+    /// Returns the number of times any symbol has followed a context.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    fn total(&self, id: u32) -> usize;
+
+    /// Samples a symbol to follow a context in proportion to its frequency,
+    /// returning `None` for the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The ID of the context.
+    ///
+    /// `sample_value` - A value in the [0.0, 1.0) range.
+    fn sample(&self, id: u32, sample_value: f64) -> Option<&TSymbol>;
+}
+
+impl<TSymbol, M> ReadModel<TSymbol> for std::sync::Arc<M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    fn order(&self) -> usize {
+        (**self).order()
+    }
+
+    fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        (**self).normalize(symbol)
+    }
+
+    fn find_context(&self, context: &[TSymbol]) -> Option<u32> {
+        (**self).find_context(context)
+    }
+
+    fn contexts(&self) -> Box<dyn Iterator<Item = (u32, &[TSymbol])> + '_> {
+        (**self).contexts()
+    }
+
+    fn symbol_count(&self, id: u32) -> usize {
+        (**self).symbol_count(id)
+    }
+
+    fn ranked_symbol(&self, id: u32, rank: usize) -> (&Option<TSymbol>, usize) {
+        (**self).ranked_symbol(id, rank)
+    }
+
+    fn frequency_of(&self, id: u32, symbol: &Option<TSymbol>) -> usize {
+        (**self).frequency_of(id, symbol)
+    }
+
+    fn total(&self, id: u32) -> usize {
+        (**self).total(id)
+    }
+
+    fn sample(&self, id: u32, sample_value: f64) -> Option<&TSymbol> {
+        (**self).sample(id, sample_value)
+    }
+}
+
+// Returns the probability of a symbol following a context, being zero if the
+// context has never been observed
+pub(crate) fn probability<TSymbol, M>(
+    model: &M,
+    context: &[TSymbol],
+    symbol: &Option<TSymbol>,
+) -> f64
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    match model.find_context(context) {
+        Some(id) => model.frequency_of(id, symbol) as f64 / model.total(id) as f64,
+        None => 0.0,
+    }
+}
+
+pub(crate) fn distribution<TSymbol, M>(
+    model: &M,
+    context: &[TSymbol],
+) -> Option<crate::Distribution<TSymbol>>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    model
+        .find_context(context)
+        .map(|id| crate::Distribution::from_context(model, id))
+}
+
+pub(crate) fn sample<'m, TSymbol, M>(
+    model: &'m M,
+    context: &[TSymbol],
+    sample_value: f64,
+) -> Option<&'m TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    model
+        .find_context(context)
+        .and_then(|id| model.sample(id, sample_value))
+}
+
+// Returns the longest suffix of a context which a model has observed
+pub(crate) fn back_off<'s, TSymbol, M>(model: &M, context: &'s [TSymbol]) -> &'s [TSymbol]
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    let mut context = context;
+    while !context.is_empty() && model.find_context(context).is_none() {
+        context = &context[1..];
+    }
+    context
+}
+
+// Returns all symbols sharing the highest frequency after a context, in the
+// order in which they were first seen
+pub(crate) fn most_frequent_ties<TSymbol, M>(model: &M, id: u32) -> Vec<&Option<TSymbol>>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    let mut ties = vec![];
+    let mut top = None;
+
+    for rank in 0..model.symbol_count(id) {
+        let (symbol, frequency) = model.ranked_symbol(id, rank);
+        if *top.get_or_insert(frequency) != frequency {
+            break;
+        }
+        ties.push(symbol);
+    }
+    ties
+}
+
+pub(crate) fn vocabulary<TSymbol, M>(model: &M) -> std::collections::HashSet<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    model
+        .contexts()
+        .flat_map(|(id, _)| {
+            (0..model.symbol_count(id)).filter_map(move |rank| *model.ranked_symbol(id, rank).0)
+        })
+        .collect()
+}
+
+// Returns each context of the full order along with the number of times it
+// was observed, in an order which is consistent between runs
+pub(crate) fn full_contexts<TSymbol, M>(model: &M) -> Vec<(crate::Sequence<TSymbol>, usize)>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    M: ReadModel<TSymbol> + ?Sized,
+{
+    use std::hash::{Hash, Hasher};
+
+    let mut contexts: Vec<_> = model
+        .contexts()
+        .filter(|(_, symbols)| symbols.len() == model.order())
+        .map(|(id, symbols)| {
+            let seq = crate::Sequence::from(symbols);
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            seq.hash(&mut hasher);
+            (hasher.finish(), seq, model.total(id))
+        })
+        .collect();
+
+    contexts.sort_by_key(|(hash, _, total)| (std::cmp::Reverse(*total), *hash));
+    contexts
+        .into_iter()
+        .map(|(_, seq, total)| (seq, total))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+    use crate::read_model::{self, ReadModel};
+    use crate::sequence::Sequence;
+
+    #[test]
+    fn it_backs_off_to_the_longest_known_context() {
+        let train = || Model::train_from(2, vec!["abc".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        for model in [&m as &dyn ReadModel<char>, &frozen] {
+            assert_eq!(
+                read_model::back_off(model, &['x', 'a', 'b'][1..]),
+                &['a', 'b']
+            );
+            assert_eq!(read_model::back_off(model, &['x', 'a']), &['a']);
+            assert_eq!(read_model::back_off(model, &['x', 'y']), &[] as &[char]);
+        }
+    }
+
+    #[test]
+    fn it_lists_full_order_contexts_by_frequency() {
+        let train = || Model::train_from(1, vec!["aababc".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        let contexts = read_model::full_contexts(&m);
+        assert_eq!(
+            contexts,
+            vec![
+                (Sequence::from_slice(&['a'], 1), 3),
+                (Sequence::from_slice(&['b'], 1), 2),
+                (Sequence::from_slice(&['c'], 1), 1),
+            ]
+        );
+        assert_eq!(contexts, read_model::full_contexts(&m));
+        assert_eq!(contexts, read_model::full_contexts(&frozen));
+    }
+
+    #[test]
+    fn it_lists_ties_for_most_frequent_in_first_seen_order() {
+        let train = || Model::train_from(1, vec!["xaxbxcxbxa".chars(), "y".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        for model in [&m as &dyn ReadModel<char>, &frozen] {
+            let id = model.find_context(&['x']).unwrap();
+            assert_eq!(
+                read_model::most_frequent_ties(model, id),
+                vec![&Some('a'), &Some('b')]
+            );
+
+            let id = model.find_context(&['y']).unwrap();
+            assert_eq!(read_model::most_frequent_ties(model, id), vec![&None]);
+        }
+    }
+
+    #[test]
+    fn it_collects_the_vocabulary() {
+        let train = || Model::train_from(1, vec!["abc".chars(), "cd".chars()]);
+        let m = train();
+        let frozen = train().freeze();
+
+        let expected = "abcd".chars().collect();
+        assert_eq!(read_model::vocabulary(&m), expected);
+        assert_eq!(read_model::vocabulary(&frozen), expected);
+    }
+}
@@ -0,0 +1,16 @@
+/// Strategies for redistributing the counts of transitions which are pruned
+/// from a [Model](struct.Model.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Redistribution<TSymbol> {
+    /// Pruned counts are discarded, and the probabilities of the remaining
+    /// transitions in each context are scaled up proportionally.
+    Discard,
+
+    /// Pruned counts are reassigned to the specified symbol (such as an
+    /// "unknown" symbol) within the same context. Symbols whose every
+    /// transition is pruned can no longer be generated, so the contexts
+    /// following them are merged into those following the replacement, and
+    /// sequences continue past the replacement as they would have past any of
+    /// the symbols it replaced.
+    Replace(TSymbol),
+}
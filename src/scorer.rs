@@ -0,0 +1,103 @@
+/// A Scorer for measuring how surprising each symbol in a sequence is under a
+/// [Model](struct.Model.html).
+///
+/// Scorers do not modify the underlying model.
+pub struct Scorer<'a, TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    model: &'a crate::Model<TSymbol, THasher>,
+    current_sequence: crate::Sequence<TSymbol>,
+}
+
+impl<'a, TSymbol, THasher> Scorer<'a, TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    /// Creates a Scorer which uses the specified model.
+    ///
+    /// # Arguments
+    ///
+    /// `model` - The Markov model to score symbols against.
+    pub fn new(model: &crate::Model<TSymbol, THasher>) -> Scorer<'_, TSymbol, THasher> {
+        Scorer {
+            model,
+            current_sequence: crate::Sequence::empty(),
+        }
+    }
+
+    /// Scores the next observed symbol in the current sequence, returning its
+    /// surprisal (-log2 P) in bits given the previously scored symbols.
+    ///
+    /// Symbols which the model considers impossible have infinite surprisal.
+    ///
+    /// # Arguments
+    ///
+    /// `symbol` - The next observed symbol in the current sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::Model::empty(1);
+    ///
+    /// let mut acc = markov::Accumulator::new(&mut model);
+    /// acc.add("login");
+    /// acc.add("read");
+    /// acc.end();
+    /// acc.add("login");
+    /// acc.add("write");
+    /// acc.end();
+    ///
+    /// let mut scorer = markov::Scorer::new(&model);
+    /// assert_eq!(scorer.score("login"), 0.0);
+    /// assert_eq!(scorer.score("write"), 1.0);
+    /// assert_eq!(scorer.score("delete"), f64::INFINITY);
+    /// ```
+    pub fn score(&mut self, symbol: TSymbol) -> f64 {
+        let symbol = self.model.normalize(symbol);
+        let p = self
+            .model
+            .probability(&self.current_sequence, &Some(symbol));
+        self.current_sequence = self.model.advance_sequence(&self.current_sequence, symbol);
+        -p.log2()
+    }
+
+    /// Scores the end of the current sequence, returning its surprisal in bits,
+    /// and resets this Scorer for a new sequence.
+    pub fn end(&mut self) -> f64 {
+        let p = self.model.probability(&self.current_sequence, &None);
+        self.current_sequence = crate::Sequence::empty();
+        -p.log2()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+    use crate::scorer::Scorer;
+    use crate::sequence::Sequence;
+
+    #[test]
+    fn it_scores_surprisal_of_observed_symbols() {
+        let mut model = Model::empty(1);
+
+        let seq = Sequence::empty();
+        model.add(&seq, Some('a'));
+        model.add(&seq, Some('a'));
+        model.add(&seq, Some('a'));
+        model.add(&seq, Some('b'));
+        let seq = model.advance_sequence(&seq, 'a');
+        model.add(&seq, None);
+
+        let mut scorer = Scorer::new(&model);
+        assert_eq!(scorer.score('b'), 2.0);
+        assert_eq!(scorer.end(), f64::INFINITY);
+
+        assert_eq!(scorer.score('a'), -(0.75f64.log2()));
+        assert_eq!(scorer.end(), 0.0);
+
+        assert_eq!(scorer.score('c'), f64::INFINITY);
+        assert_eq!(scorer.score('a'), f64::INFINITY);
+    }
+}
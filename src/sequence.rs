@@ -6,6 +6,26 @@ where
     symbols: Vec<TSymbol>,
 }
 
+impl<TSymbol> std::borrow::Borrow<[TSymbol]> for Sequence<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn borrow(&self) -> &[TSymbol] {
+        &self.symbols
+    }
+}
+
+impl<TSymbol> From<&[TSymbol]> for Sequence<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn from(symbols: &[TSymbol]) -> Sequence<TSymbol> {
+        Sequence {
+            symbols: symbols.to_vec(),
+        }
+    }
+}
+
 impl<TSymbol> Sequence<TSymbol>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
@@ -14,7 +34,71 @@ where
         Sequence { symbols: vec![] }
     }
 
+    pub(crate) fn symbols(&self) -> &[TSymbol] {
+        &self.symbols
+    }
+
+    pub(crate) fn from_slice(symbols: &[TSymbol], order: usize) -> Sequence<TSymbol> {
+        let last_symbols = if symbols.len() <= order {
+            symbols
+        } else {
+            &symbols[(symbols.len() - order)..]
+        };
+
+        Sequence {
+            symbols: last_symbols.to_vec(),
+        }
+    }
+
+    pub(crate) fn skip_grams(symbols: &[TSymbol], order: usize) -> Vec<Sequence<TSymbol>> {
+        let mut skip_grams = vec![];
+
+        if order == 0 || symbols.len() <= order {
+            return skip_grams;
+        }
+
+        let mut indices: Vec<usize> = (0..order).collect();
+
+        loop {
+            if indices[0] != symbols.len() - order {
+                skip_grams.push(Sequence {
+                    symbols: indices.iter().map(|i| symbols[*i]).collect(),
+                });
+            }
+
+            // Advance to the next combination of indices in lexicographic order
+            let mut i = order;
+            loop {
+                if i == 0 {
+                    return skip_grams;
+                }
+                i -= 1;
+                if indices[i] < symbols.len() - order + i {
+                    break;
+                }
+            }
+
+            indices[i] += 1;
+            for j in (i + 1)..order {
+                indices[j] = indices[j - 1] + 1;
+            }
+        }
+    }
+
+    pub(crate) fn map<F>(&self, f: F) -> Sequence<TSymbol>
+    where
+        F: Fn(TSymbol) -> TSymbol,
+    {
+        Sequence {
+            symbols: self.symbols.iter().map(|s| f(*s)).collect(),
+        }
+    }
+
     pub(crate) fn with_next(&self, next_symbol: TSymbol, order: usize) -> Sequence<TSymbol> {
+        if order == 0 {
+            return Sequence::empty();
+        }
+
         let last_symbols = if self.symbols.len() < order {
             &self.symbols[..]
         } else {
@@ -51,6 +135,50 @@ mod test {
         assert_eq!(seq.symbols, vec!['a', 'b', 'c']);
     }
 
+    #[test]
+    fn it_creates_sequences_from_the_last_symbols_of_a_slice() {
+        let seq = Sequence::from_slice(&['a', 'b'], 3);
+        assert_eq!(seq.symbols, vec!['a', 'b']);
+
+        let seq = Sequence::from_slice(&['a', 'b', 'c', 'd'], 3);
+        assert_eq!(seq.symbols, vec!['b', 'c', 'd']);
+    }
+
+    #[test]
+    fn it_derives_non_contiguous_skip_grams() {
+        let skip_grams = Sequence::skip_grams(&['a', 'b', 'c', 'd'], 2);
+        let symbols: Vec<_> = skip_grams.into_iter().map(|s| s.symbols).collect();
+
+        assert_eq!(
+            symbols,
+            vec![
+                vec!['a', 'b'],
+                vec!['a', 'c'],
+                vec!['a', 'd'],
+                vec!['b', 'c'],
+                vec!['b', 'd'],
+            ]
+        );
+
+        assert!(Sequence::skip_grams(&['a', 'b'], 2).is_empty());
+        assert!(Sequence::skip_grams(&['a', 'b'], 0).is_empty());
+    }
+
+    #[test]
+    fn it_can_be_looked_up_by_slice() {
+        let mut map = std::collections::HashMap::new();
+        map.insert(Sequence::from_slice(&['a', 'b'], 2), 1);
+
+        assert_eq!(map.get(&['a', 'b'][..]), Some(&1));
+        assert_eq!(map.get(&['a'][..]), None);
+    }
+
+    #[test]
+    fn it_maps_symbols() {
+        let seq = Sequence::from_slice(&['a', 'B'], 2);
+        assert_eq!(seq.map(|c| c.to_ascii_uppercase()).symbols, vec!['A', 'B']);
+    }
+
     #[test]
     fn it_limits_length_to_specified_order() {
         let mut seq: Sequence<char> = Sequence {
@@ -62,5 +190,8 @@ mod test {
 
         seq = seq.with_next('e', 2);
         assert_eq!(seq.symbols, vec!['d', 'e']);
+
+        seq = seq.with_next('f', 0);
+        assert_eq!(seq.symbols, vec![]);
     }
 }
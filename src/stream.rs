@@ -0,0 +1,93 @@
+/// An asynchronous [`Stream`](https://docs.rs/futures/0.3/futures/stream/trait.Stream.html)
+/// of the symbols produced by a [Generator](struct.Generator.html), such as for
+/// streaming generated text from an async server.
+///
+/// Symbols are generated only as they are polled for, so slow consumers apply
+/// backpressure naturally. The stream ends when the end of a sequence is
+/// reached.
+///
+/// GeneratorStreams are created by
+/// [`Generator::into_stream`](struct.Generator.html#method.into_stream).
+pub struct GeneratorStream<'a, TSymbol, R, M = crate::Model<TSymbol>>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+{
+    generator: crate::Generator<'a, TSymbol, R, M>,
+}
+
+impl<'a, TSymbol, R, M> GeneratorStream<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
+{
+    pub(crate) fn new(
+        generator: crate::Generator<'a, TSymbol, R, M>,
+    ) -> GeneratorStream<'a, TSymbol, R, M> {
+        GeneratorStream { generator }
+    }
+
+    /// Returns the underlying Generator, such as to reset it and stream
+    /// another sequence.
+    pub fn into_inner(self) -> crate::Generator<'a, TSymbol, R, M> {
+        self.generator
+    }
+}
+
+// The Generator is never pinned structurally, so the stream can be moved
+// freely regardless of its symbol, random source and hasher types
+impl<'a, TSymbol, R, M> Unpin for GeneratorStream<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+{
+}
+
+impl<'a, TSymbol, R, M> futures_core::Stream for GeneratorStream<'a, TSymbol, R, M>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    R: crate::RandomSource,
+    M: crate::ReadModel<TSymbol>,
+{
+    type Item = TSymbol;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<TSymbol>> {
+        std::task::Poll::Ready(self.get_mut().generator.next())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::generator::Generator;
+    use crate::model::Model;
+    use futures::executor::block_on;
+    use futures::stream::StreamExt;
+
+    #[test]
+    fn it_streams_generated_symbols() {
+        let model = Model::train_from(1, vec!["abc".chars()]);
+
+        let stream = Generator::new(&model, || 0.0).into_stream();
+        let symbols: Vec<char> = block_on(stream.collect());
+        assert_eq!(symbols, vec!['a', 'b', 'c']);
+    }
+
+    #[test]
+    fn it_returns_the_generator_for_reuse() {
+        let model = Model::train_from(1, vec!["abc".chars()]);
+
+        let mut stream = Generator::new(&model, || 0.0).into_stream();
+        assert_eq!(block_on(stream.next()), Some('a'));
+
+        let mut gen = stream.into_inner();
+        assert_eq!(gen.next(), Some('b'));
+        gen.end();
+
+        let symbols: String = block_on(gen.into_stream().collect());
+        assert_eq!(symbols, "abc");
+    }
+}
@@ -0,0 +1,215 @@
+// Succinct encodings used to hold the counts of very large models in little
+// more memory than their information content.
+
+// Unsigned integers of a fixed bit width, packed end to end into words
+pub(crate) struct BitPacked {
+    width: u32,
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitPacked {
+    pub(crate) fn new<I>(width: u32, values: I) -> BitPacked
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut packed = BitPacked {
+            width,
+            len: 0,
+            words: vec![],
+        };
+
+        for value in values {
+            let bit = packed.len * width as usize;
+            let (word, offset) = (bit / 64, bit % 64);
+            packed.words.resize((bit + width as usize).div_ceil(64), 0);
+
+            if width > 0 {
+                packed.words[word] |= value << offset;
+                if offset + width as usize > 64 {
+                    packed.words[word + 1] |= value >> (64 - offset);
+                }
+            }
+            packed.len += 1;
+        }
+
+        packed.words.shrink_to_fit();
+        packed
+    }
+
+    // Returns the number of bits needed to hold values up to `max`
+    pub(crate) fn width_of(max: u64) -> u32 {
+        u64::BITS - max.leading_zeros()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> u64 {
+        if self.width == 0 {
+            return 0;
+        }
+
+        let bit = index * self.width as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+
+        let mut value = self.words[word] >> offset;
+        if offset + self.width as usize > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+
+        if self.width == 64 {
+            value
+        } else {
+            value & ((1 << self.width) - 1)
+        }
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+    }
+}
+
+// The position of every this many set bits of the upper half of an
+// Elias-Fano encoding is sampled, so that finding any set bit means scanning
+// only a few words from the nearest sample
+const SELECT_SAMPLE_RATE: usize = 256;
+
+// A non-decreasing sequence of integers in Elias-Fano form, taking at most
+// 2 + log2(max / len) bits per value while allowing any value to be read in
+// near-constant time. The low bits of each value are bit-packed, and the high
+// bits are stored as gaps in unary in a bit vector.
+pub(crate) struct EliasFano {
+    low_width: u32,
+    low: BitPacked,
+    high: Vec<u64>,
+    samples: Vec<usize>,
+}
+
+impl EliasFano {
+    pub(crate) fn new(values: &[u64]) -> EliasFano {
+        let max = values.last().copied().unwrap_or(0);
+        let low_width = match values.len() {
+            0 => 0,
+            n => (max / n as u64).checked_ilog2().unwrap_or(0),
+        };
+
+        let low_mask = if low_width == 0 {
+            0
+        } else {
+            (1 << low_width) - 1
+        };
+        let low = BitPacked::new(low_width, values.iter().map(|v| v & low_mask));
+
+        let high_len = values.len() + (max >> low_width) as usize + 1;
+        let mut high = vec![0u64; high_len.div_ceil(64)];
+        let mut samples = vec![];
+
+        for (i, value) in values.iter().enumerate() {
+            let bit = (value >> low_width) as usize + i;
+            high[bit / 64] |= 1 << (bit % 64);
+
+            if i % SELECT_SAMPLE_RATE == 0 {
+                samples.push(bit);
+            }
+        }
+
+        EliasFano {
+            low_width,
+            low,
+            high,
+            samples,
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> u64 {
+        let high = (self.select(index) - index) as u64;
+        (high << self.low_width) | self.low.get(index)
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.low.size_in_bytes()
+            + self.high.len() * std::mem::size_of::<u64>()
+            + self.samples.len() * std::mem::size_of::<usize>()
+    }
+
+    // Returns the position of the set bit of the upper half with the
+    // specified rank
+    fn select(&self, rank: usize) -> usize {
+        let start = self.samples[rank / SELECT_SAMPLE_RATE];
+        let mut remaining = rank % SELECT_SAMPLE_RATE;
+
+        let mut word_index = start / 64;
+        let mut word = self.high[word_index] & (!0 << (start % 64));
+
+        loop {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                for _ in 0..remaining {
+                    word &= word - 1;
+                }
+                return word_index * 64 + word.trailing_zeros() as usize;
+            }
+
+            remaining -= ones;
+            word_index += 1;
+            word = self.high[word_index];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::succinct::{BitPacked, EliasFano};
+
+    #[test]
+    fn it_packs_values_of_any_width() {
+        for width in [0, 1, 7, 13, 33, 64] {
+            let max = if width == 64 {
+                u64::MAX
+            } else {
+                (1 << width) - 1
+            };
+            let values: Vec<u64> = (0..200u64)
+                .map(|i| i.wrapping_mul(0x9e37_79b9) & max)
+                .collect();
+
+            let packed = BitPacked::new(width, values.iter().copied());
+            for (i, value) in values.iter().enumerate() {
+                assert_eq!(packed.get(i), *value);
+            }
+        }
+
+        assert_eq!(BitPacked::width_of(0), 0);
+        assert_eq!(BitPacked::width_of(1), 1);
+        assert_eq!(BitPacked::width_of(255), 8);
+        assert_eq!(BitPacked::width_of(256), 9);
+    }
+
+    #[test]
+    fn it_encodes_non_decreasing_sequences() {
+        let mut total = 0;
+        let values: Vec<u64> = (0..2000u64)
+            .map(|i| {
+                total += (i * i) % 97;
+                total
+            })
+            .collect();
+
+        let ef = EliasFano::new(&values);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(ef.get(i), *value);
+        }
+        assert!(ef.size_in_bytes() < values.len() * 2);
+    }
+
+    #[test]
+    fn it_encodes_repeated_and_sparse_values() {
+        let values = vec![0, 0, 0, 5, 5, 1 << 40, 1 << 40];
+
+        let ef = EliasFano::new(&values);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(ef.get(i), *value);
+        }
+
+        let ef = EliasFano::new(&[]);
+        assert_eq!(ef.size_in_bytes(), std::mem::size_of::<u64>());
+    }
+}
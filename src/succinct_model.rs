@@ -0,0 +1,369 @@
+/// A read-only form of a [FrozenModel](struct.FrozenModel.html) whose
+/// transitions are held in a succinct encoding, as returned by
+/// [`FrozenModel::into_succinct`](struct.FrozenModel.html#method.into_succinct).
+///
+/// Each symbol following a context is stored as a bit-packed index into the
+/// model's vocabulary, and the frequencies of all transitions as Elias-Fano
+/// encoded cumulative counts. The most frequent symbol of each context is
+/// stored first, so that it can be predicted directly, and the rest in order of
+/// their indices, so that any of them can be found in O(log n) decodes. This takes only a few bytes per transition, so
+/// that models with billions of transitions can be queried from a few
+/// gigabytes of memory, but each query decodes the counts it needs rather than
+/// reading them directly. Contexts themselves are stored as in a
+/// [FrozenModel](struct.FrozenModel.html).
+///
+/// Unlike a [QuantizedModel](struct.QuantizedModel.html), exact counts are
+/// kept, so probabilities are the same as those of the model it was created
+/// from.
+pub struct SuccinctModel<TSymbol, THasher = std::collections::hash_map::RandomState>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    order: usize,
+    contexts: crate::interner::Interner<crate::Sequence<TSymbol>, THasher>,
+    vocabulary: crate::interner::Interner<Option<TSymbol>, THasher>,
+    // The position of the first transition of each context, followed by the
+    // total number of transitions
+    offsets: crate::succinct::EliasFano,
+    symbols: crate::succinct::BitPacked,
+    // The total frequency of each transition and all those stored before it
+    cumulative: crate::succinct::EliasFano,
+    normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+}
+
+impl<TSymbol, THasher> SuccinctModel<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher + std::clone::Clone,
+{
+    // Encodes the arrays of a frozen model, whose entries for each context are
+    // found at offsets[id]..offsets[id + 1], with frequencies accumulated
+    // within that context only
+    pub(crate) fn new(
+        order: usize,
+        contexts: crate::interner::Interner<crate::Sequence<TSymbol>, THasher>,
+        offsets: &[usize],
+        symbols: &[Option<TSymbol>],
+        cumulative: &[usize],
+        normalizer: Option<Box<dyn Fn(TSymbol) -> TSymbol + Send + Sync>>,
+    ) -> SuccinctModel<TSymbol, THasher> {
+        let mut vocabulary = crate::interner::Interner::with_hasher(contexts.hasher().clone());
+        let mut packed = Vec::with_capacity(symbols.len());
+        let mut running = Vec::with_capacity(symbols.len());
+        let mut total = 0u64;
+
+        for range in offsets.windows(2).map(|w| w[0]..w[1]) {
+            let mut previous = 0;
+            let mut entries: Vec<(u64, u64)> = range
+                .map(|i| {
+                    let frequency = (cumulative[i] - previous) as u64;
+                    previous = cumulative[i];
+                    (vocabulary.intern_value(symbols[i]) as u64, frequency)
+                })
+                .collect();
+
+            // The most frequent entry is kept first, and the rest are ordered
+            // by symbol so that they can be found by binary search
+            if let Some((_, rest)) = entries.split_first_mut() {
+                rest.sort_unstable_by_key(|(symbol, _)| *symbol);
+            }
+
+            for (symbol, frequency) in entries {
+                packed.push(symbol);
+                total += frequency;
+                running.push(total);
+            }
+        }
+
+        let offsets: Vec<u64> = offsets.iter().map(|&o| o as u64).collect();
+        let width = crate::succinct::BitPacked::width_of(packed.iter().max().copied().unwrap_or(0));
+
+        SuccinctModel {
+            order,
+            contexts,
+            vocabulary,
+            offsets: crate::succinct::EliasFano::new(&offsets),
+            symbols: crate::succinct::BitPacked::new(width, packed),
+            cumulative: crate::succinct::EliasFano::new(&running),
+            normalizer,
+        }
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.contexts.iter().len()
+    }
+
+    /// Returns the number of distinct transitions in this model, being the
+    /// number of distinct symbols following each context, summed over all
+    /// contexts.
+    pub fn transition_count(&self) -> usize {
+        self.offsets.get(self.context_count()) as usize
+    }
+
+    /// Returns the approximate number of bytes used to store the transitions
+    /// of this model, excluding its contexts and vocabulary.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let corpus: Vec<String> = (0..1000).map(|i| format!("{}", i * 7919)).collect();
+    /// let model = markov::Model::train_from(2, corpus.iter().map(|s| s.chars()));
+    ///
+    /// let model = model.freeze().into_succinct();
+    /// assert!(model.transition_bytes() < model.transition_count() * 4);
+    /// ```
+    pub fn transition_bytes(&self) -> usize {
+        self.offsets.size_in_bytes()
+            + self.symbols.size_in_bytes()
+            + self.cumulative.size_in_bytes()
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first, as per
+    /// [`Model::end_probability`](struct.Model.html#method.end_probability).
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["ab".chars(), "ac".chars()]);
+    /// let model = model.freeze().into_succinct();
+    ///
+    /// assert_eq!(model.probability(&['a'], Some('b')), 0.5);
+    /// ```
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        let symbol = symbol.map(|s| self.normalize(s));
+
+        let (start, end) = match self.transitions(context) {
+            Some(range) => range,
+            None => return 0.0,
+        };
+        let id = match self.vocabulary.get(&symbol) {
+            Some(id) => id as u64,
+            None => return 0.0,
+        };
+
+        match self.position_of(start, end, id) {
+            Some(i) => {
+                let frequency = self.cumulative_before(i + 1) - self.cumulative_before(i);
+                let total = self.cumulative_before(end) - self.cumulative_before(start);
+                frequency as f64 / total as f64
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// `None` is returned if the context has never been observed, or if it is
+    /// most likely to be followed by the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`probability`](#method.probability).
+    pub fn predict(&self, context: &[TSymbol]) -> Option<&TSymbol> {
+        let (start, _) = self.transitions(context)?;
+        self.symbol_at(start).as_ref()
+    }
+
+    /// Generates a complete sequence from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::Model::train_from(1, vec!["abc".chars()]).freeze();
+    ///
+    /// let model = model.into_succinct();
+    /// assert_eq!(model.generate(Box::new(|| 0.0)), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate(&self, mut rand_source: Box<dyn FnMut() -> f64 + '_>) -> Vec<TSymbol> {
+        let mut sequence = vec![];
+        let mut seq = crate::Sequence::empty();
+
+        while let Some(symbol) = self.sample(seq.symbols(), rand_source()) {
+            sequence.push(symbol);
+            seq = seq.with_next(symbol, self.order);
+        }
+
+        sequence
+    }
+
+    fn sample(&self, context: &[TSymbol], sample_value: f64) -> Option<TSymbol> {
+        let (start, end) = self.transitions(context)?;
+
+        let base = self.cumulative_before(start);
+        let total = self.cumulative_before(end) - base;
+        let value = base + crate::random::scale(sample_value, total as usize) as u64;
+
+        // Finds the first transition whose cumulative frequency exceeds the
+        // value drawn
+        let (mut low, mut high) = (start, end - 1);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.cumulative.get(mid) <= value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        *self.symbol_at(low)
+    }
+
+    // Returns the range of positions of the transitions following a context
+    fn transitions(&self, context: &[TSymbol]) -> Option<(usize, usize)> {
+        let context: Vec<TSymbol> = context.iter().map(|s| self.normalize(*s)).collect();
+        let seq = crate::Sequence::from_slice(&context, self.order);
+
+        let id = self.contexts.get(seq.symbols())? as usize;
+        Some((
+            self.offsets.get(id) as usize,
+            self.offsets.get(id + 1) as usize,
+        ))
+    }
+
+    // Finds the position of a symbol among the transitions of a context, which
+    // holds its most frequent symbol first and the rest in order of their IDs
+    fn position_of(&self, start: usize, end: usize, id: u64) -> Option<usize> {
+        if start == end {
+            return None;
+        }
+        if self.symbols.get(start) == id {
+            return Some(start);
+        }
+
+        let (mut low, mut high) = (start + 1, end);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.symbols.get(mid).cmp(&id) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    fn cumulative_before(&self, position: usize) -> u64 {
+        match position {
+            0 => 0,
+            p => self.cumulative.get(p - 1),
+        }
+    }
+
+    fn symbol_at(&self, position: usize) -> &Option<TSymbol> {
+        self.vocabulary
+            .resolve(self.symbols.get(position) as u32)
+            .unwrap()
+    }
+
+    fn normalize(&self, symbol: TSymbol) -> TSymbol {
+        match &self.normalizer {
+            Some(normalizer) => normalizer(symbol),
+            None => symbol,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+
+    #[test]
+    fn it_keeps_exact_probabilities() {
+        let corpus = vec!["abcab".chars(), "abd".chars(), "cab".chars(), "abd".chars()];
+        let model = Model::train_from(2, corpus.clone()).freeze();
+        let expected = Model::train_from(2, corpus);
+        let s = model.into_succinct();
+
+        assert_eq!(s.context_count(), expected.context_count());
+        for (seq, t) in expected.tables() {
+            for (symbol, _) in t.iter() {
+                assert_eq!(
+                    s.probability(seq.symbols(), *symbol),
+                    t.probability_of(symbol)
+                );
+            }
+            assert_eq!(s.predict(seq.symbols()), t.most_frequent());
+        }
+
+        assert_eq!(s.probability(&['a', 'b'], Some('x')), 0.0);
+        assert_eq!(s.probability(&['x', 'x'], Some('a')), 0.0);
+        assert_eq!(s.predict(&['x', 'x']), None);
+    }
+
+    #[test]
+    fn it_samples_in_proportion_to_counts() {
+        let model = Model::train_from(0, vec!["aaab".chars()]).freeze();
+        let s = model.into_succinct();
+
+        // "a", "b" and the end follow the empty context 3, 1 and 1 times
+        let drawn: Vec<_> = (0..5)
+            .map(|i| s.sample(&[], (i * 2 + 1) as f64 / 10.0))
+            .collect();
+        assert_eq!(
+            drawn,
+            vec![Some('a'), Some('a'), Some('a'), Some('b'), None]
+        );
+        assert_eq!(s.transition_count(), 3);
+    }
+
+    #[test]
+    fn it_finds_symbols_among_many_transitions() {
+        let corpus: Vec<Vec<u32>> = (0..200u32)
+            .map(|i| vec![i % 3, (i * 37) % 41, (i * 11) % 29])
+            .collect();
+        let expected = Model::train_from(1, corpus.clone());
+        let s = Model::train_from(1, corpus).freeze().into_succinct();
+
+        for (seq, t) in expected.tables() {
+            for (symbol, _) in t.iter() {
+                assert_eq!(
+                    s.probability(seq.symbols(), *symbol),
+                    t.probability_of(symbol)
+                );
+            }
+            assert_eq!(s.predict(seq.symbols()), t.most_frequent());
+            assert_eq!(s.probability(seq.symbols(), Some(1000)), 0.0);
+        }
+    }
+
+    #[test]
+    fn it_stores_large_models_compactly() {
+        let mut state = 1u32;
+        let corpus: Vec<Vec<u32>> = (0..2000)
+            .map(|_| {
+                (0..20)
+                    .map(|_| {
+                        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                        (state >> 16) % 101
+                    })
+                    .collect()
+            })
+            .collect();
+        let model = Model::train_from(1, corpus).freeze();
+        let s = model.into_succinct();
+
+        assert!(s.transition_count() > 5000);
+        assert!(s.transition_bytes() < s.transition_count() * 4);
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{HashMap, HashSet};
+
+/// A Markov model of unbounded order, which conditions each prediction on the
+/// longest context matching any part of its training data.
+///
+/// Training sequences are held in a suffix automaton, so that every context
+/// observed during training, no matter how long, can be found efficiently.
+/// Predictions blend the longest matching context with successively shorter
+/// ones by escaping to them, as in PPM (prediction by partial matching) with
+/// escape method C and exclusion of symbols already predicted by longer
+/// contexts.
+pub struct SuffixModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    states: Vec<State<TSymbol>>,
+    last: u32,
+    counts: std::sync::OnceLock<Vec<usize>>,
+}
+
+// Each state represents the set of substrings of the training stream which
+// end at the same positions. Sequences are separated in the stream by `None`,
+// which also precedes the first sequence so that contexts can be anchored to
+// the beginning of a sequence. The symbols of a state's transitions are also
+// listed in the order in which they were added, so that ties between equally
+// probable symbols are broken in the order in which they were first seen.
+struct State<TSymbol> {
+    len: usize,
+    link: Option<u32>,
+    next: HashMap<Option<TSymbol>, u32>,
+    symbols: Vec<Option<TSymbol>>,
+    is_prefix: bool,
+}
+
+impl<TSymbol> SuffixModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Creates an empty SuffixModel.
+    pub fn empty() -> SuffixModel<TSymbol> {
+        let mut model = SuffixModel {
+            states: vec![State {
+                len: 0,
+                link: None,
+                next: HashMap::new(),
+                symbols: vec![],
+                is_prefix: false,
+            }],
+            last: 0,
+            counts: Default::default(),
+        };
+        model.extend(None);
+        model
+    }
+
+    /// Creates a SuffixModel trained on the specified sequences.
+    ///
+    /// # Arguments
+    ///
+    /// `sequences` - The training sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::SuffixModel::train_from(vec!["abcd".chars(), "xbce".chars()]);
+    ///
+    /// assert_eq!(model.predict(&['a', 'b', 'c']), Some('d'));
+    /// assert_eq!(model.predict(&['x', 'b', 'c']), Some('e'));
+    /// ```
+    pub fn train_from<I, S>(sequences: I) -> SuffixModel<TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        let mut model = SuffixModel::empty();
+        for sequence in sequences {
+            model.train_sequence(sequence);
+        }
+        model
+    }
+
+    /// Trains the model on a complete sequence, including its end.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    pub fn train_sequence<I>(&mut self, sequence: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        for symbol in sequence {
+            self.extend(Some(symbol));
+        }
+        self.extend(None);
+    }
+
+    /// Returns the length of the longest suffix of a context which was
+    /// observed during training.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The symbols of a sequence so far, from its beginning.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::SuffixModel::train_from(vec!["abcd".chars()]);
+    ///
+    /// assert_eq!(model.longest_match(&['x', 'b', 'c']), 2);
+    /// ```
+    pub fn longest_match(&self, context: &[TSymbol]) -> usize {
+        // A match including the beginning of the sequence is one longer than
+        // the context itself
+        let (_, len) = self.find(context);
+        len.min(context.len())
+    }
+
+    /// Returns the probability of each symbol following a context, from most
+    /// to least probable, where `None` refers to the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The symbols of a sequence so far, from its beginning. An
+    /// empty context refers to the beginning of a sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::SuffixModel::train_from(vec!["ab".chars(), "ab".chars(), "ac".chars()]);
+    ///
+    /// let distribution = model.distribution(&['a']);
+    /// assert_eq!(distribution[0].0, Some('b'));
+    /// assert!(distribution[0].1 > distribution[1].1);
+    /// ```
+    pub fn distribution(&self, context: &[TSymbol]) -> Vec<(Option<TSymbol>, f64)> {
+        let counts = self.counts();
+        let (state, _) = self.find(context);
+
+        let mut probabilities: Vec<(Option<TSymbol>, f64)> = vec![];
+        let mut excluded: HashSet<Option<TSymbol>> = HashSet::new();
+        let mut escape = 1.0;
+
+        let mut current = Some(state);
+        while let Some(s) = current {
+            let state = &self.states[s as usize];
+            let next: Vec<(Option<TSymbol>, usize)> = state
+                .symbols
+                .iter()
+                .filter(|symbol| !excluded.contains(*symbol))
+                .map(|symbol| {
+                    // The separator at the start of the stream does not follow
+                    // any context, so is not counted as an ending
+                    let mut count = counts[state.next[symbol] as usize];
+                    if s == 0 && symbol.is_none() {
+                        count -= 1;
+                    }
+                    (*symbol, count)
+                })
+                .filter(|(_, count)| *count > 0)
+                .collect();
+
+            let total: usize = next.iter().map(|(_, count)| count).sum();
+            if total > 0 {
+                // The shortest context has nowhere to escape to
+                let denominator = match state.link {
+                    Some(_) => (total + next.len()) as f64,
+                    None => total as f64,
+                };
+
+                for (symbol, count) in &next {
+                    probabilities.push((*symbol, escape * *count as f64 / denominator));
+                    excluded.insert(*symbol);
+                }
+                escape *= next.len() as f64 / denominator;
+            }
+
+            current = state.link;
+        }
+
+        let total: f64 = probabilities.iter().map(|(_, p)| p).sum();
+        if total > 0.0 {
+            for (_, p) in &mut probabilities {
+                *p /= total;
+            }
+        }
+
+        probabilities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        probabilities
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The symbols of a sequence so far, as per
+    /// [`distribution`](#method.distribution).
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        self.distribution(context)
+            .into_iter()
+            .find(|(s, _)| *s == symbol)
+            .map_or(0.0, |(_, p)| p)
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// `None` is returned if the end of a sequence is most probable, or if the
+    /// model is untrained.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The symbols of a sequence so far, as per
+    /// [`distribution`](#method.distribution).
+    pub fn predict(&self, context: &[TSymbol]) -> Option<TSymbol> {
+        self.distribution(context)
+            .first()
+            .and_then(|(symbol, _)| *symbol)
+    }
+
+    // Finds the state of the longest suffix of the anchored context which
+    // occurs in the training stream, along with the length of that suffix
+    fn find(&self, context: &[TSymbol]) -> (u32, usize) {
+        let mut state = 0;
+        let mut len = 0;
+
+        for symbol in std::iter::once(None).chain(context.iter().map(|s| Some(*s))) {
+            while state != 0 && !self.states[state as usize].next.contains_key(&symbol) {
+                state = self.states[state as usize].link.unwrap_or(0);
+                len = self.states[state as usize].len;
+            }
+            match self.states[state as usize].next.get(&symbol) {
+                Some(next) => {
+                    state = *next;
+                    len += 1;
+                }
+                None => len = 0,
+            }
+        }
+
+        (state, len)
+    }
+
+    // Returns the number of times the substrings of each state occur in the
+    // training stream
+    fn counts(&self) -> &[usize] {
+        self.counts.get_or_init(|| {
+            let mut counts: Vec<usize> = self
+                .states
+                .iter()
+                .map(|s| if s.is_prefix { 1 } else { 0 })
+                .collect();
+
+            let mut by_len: Vec<u32> = (0..self.states.len() as u32).collect();
+            by_len.sort_by_key(|s| std::cmp::Reverse(self.states[*s as usize].len));
+            for s in by_len {
+                if let Some(link) = self.states[s as usize].link {
+                    counts[link as usize] += counts[s as usize];
+                }
+            }
+
+            counts
+        })
+    }
+
+    fn extend(&mut self, symbol: Option<TSymbol>) {
+        self.counts.take();
+
+        let current = self.states.len() as u32;
+        self.states.push(State {
+            len: self.states[self.last as usize].len + 1,
+            link: None,
+            next: HashMap::new(),
+            symbols: vec![],
+            is_prefix: true,
+        });
+
+        let mut p = Some(self.last);
+        while let Some(s) = p {
+            if self.states[s as usize].next.contains_key(&symbol) {
+                break;
+            }
+            self.states[s as usize].next.insert(symbol, current);
+            self.states[s as usize].symbols.push(symbol);
+            p = self.states[s as usize].link;
+        }
+
+        let link = match p {
+            None => 0,
+            Some(p) => {
+                let q = self.states[p as usize].next[&symbol];
+                if self.states[p as usize].len + 1 == self.states[q as usize].len {
+                    q
+                } else {
+                    let clone = self.states.len() as u32;
+                    self.states.push(State {
+                        len: self.states[p as usize].len + 1,
+                        link: self.states[q as usize].link,
+                        next: self.states[q as usize].next.clone(),
+                        symbols: self.states[q as usize].symbols.clone(),
+                        is_prefix: false,
+                    });
+
+                    let mut p = Some(p);
+                    while let Some(s) = p {
+                        match self.states[s as usize].next.get_mut(&symbol) {
+                            Some(target) if *target == q => *target = clone,
+                            _ => break,
+                        }
+                        p = self.states[s as usize].link;
+                    }
+
+                    self.states[q as usize].link = Some(clone);
+                    clone
+                }
+            }
+        };
+
+        self.states[current as usize].link = Some(link);
+        self.last = current;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::suffix_model::SuffixModel;
+
+    #[test]
+    fn it_counts_occurrences_of_contexts() {
+        let m = SuffixModel::train_from(vec!["abab".chars()]);
+
+        let (state, len) = m.find(&['x', 'a', 'b']);
+        assert_eq!(len, 2);
+        assert_eq!(m.counts()[state as usize], 2);
+    }
+
+    #[test]
+    fn it_conditions_on_the_longest_matching_context() {
+        let m = SuffixModel::train_from(vec!["abcdef".chars(), "xbcdeg".chars()]);
+
+        assert_eq!(m.longest_match(&['a', 'b', 'c', 'd', 'e']), 5);
+        assert_eq!(m.longest_match(&['z', 'b', 'c', 'd', 'e']), 4);
+        assert_eq!(m.predict(&['a', 'b', 'c', 'd', 'e']), Some('f'));
+        assert_eq!(m.predict(&['x', 'b', 'c', 'd', 'e']), Some('g'));
+        assert_eq!(m.predict(&['a', 'b', 'c', 'd', 'e', 'f']), None);
+    }
+
+    #[test]
+    fn it_escapes_to_shorter_contexts() {
+        let m = SuffixModel::train_from(vec!["ab".chars(), "ab".chars(), "cd".chars()]);
+
+        // After "a", only "b" has been seen, so the remaining probability is
+        // given by escaping to the empty context, excluding "b"
+        let p = m.probability(&['a'], Some('b'));
+        assert!((p - 2.0 / 3.0).abs() < 1e-12);
+
+        let total: f64 = m.distribution(&['a']).iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert!(m.probability(&['a'], Some('d')) > 0.0);
+    }
+
+    #[test]
+    fn it_anchors_contexts_to_the_beginning_of_sequences() {
+        let m =
+            SuffixModel::train_from(vec!["ab".chars(), "ab".chars(), "ab".chars(), "ba".chars()]);
+
+        assert_eq!(m.predict(&[]), Some('a'));
+        assert!(m.probability(&[], Some('a')) > m.probability(&[], Some('b')));
+        assert!(m.probability(&['b'], Some('a')) > m.probability(&['b'], Some('b')));
+    }
+
+    #[test]
+    fn it_breaks_ties_in_first_seen_order() {
+        // Each model hashes symbols differently, so a tie broken by hash order
+        // would eventually come out the other way
+        for _ in 0..20 {
+            let m = SuffixModel::train_from(["ab", "ac"].repeat(3).iter().map(|s| s.chars()));
+
+            let symbols: Vec<_> = m.distribution(&['a']).iter().map(|(s, _)| *s).collect();
+            assert_eq!(symbols[..2], [Some('b'), Some('c')]);
+            assert_eq!(m.predict(&['a']), Some('b'));
+        }
+    }
+
+    #[test]
+    fn it_handles_untrained_models() {
+        let m = SuffixModel::<char>::empty();
+
+        assert_eq!(m.predict(&['a']), None);
+        assert_eq!(m.longest_match(&['a']), 0);
+        assert!(m.distribution(&['a']).is_empty());
+    }
+}
@@ -1,102 +1,280 @@
 use std::collections::HashMap;
 
-pub(crate) struct Table<TSymbol> {
+#[cfg(not(feature = "wide-counts"))]
+type Count = u32;
+
+#[cfg(feature = "wide-counts")]
+type Count = u64;
+
+// Entries are held as parallel arrays of frequencies and symbols, so that scans
+// over frequencies (to sum, order or sample them) touch only the memory they
+// need. They are kept in the order in which they were first seen, so that their
+// positions in the cumulative counts used for sampling are stable, and adding to
+// a table is O(log n).
+pub(crate) struct Table<TSymbol, THasher = std::collections::hash_map::RandomState> {
     total_symbols: usize,
-    entries: Vec<TableEntry<TSymbol>>,
-    entry_indices: HashMap<Option<TSymbol>, usize>,
+    frequencies: Vec<Count>,
+    symbols: Vec<Option<TSymbol>>,
+    entry_indices: HashMap<Option<TSymbol>, usize, THasher>,
+    cumulative: crate::fenwick::Fenwick,
+    // The positions of entries from most to least frequent, which are only
+    // needed to iterate over them, so are computed on demand and kept until the
+    // table is next modified
+    order: std::sync::OnceLock<Vec<usize>>,
 }
 
+#[cfg(test)]
 impl<TSymbol> Table<TSymbol>
 where
     TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
 {
     pub(crate) fn empty() -> Table<TSymbol> {
+        Table::with_hasher(Default::default())
+    }
+}
+
+impl<TSymbol, THasher> Table<TSymbol, THasher>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    THasher: std::hash::BuildHasher,
+{
+    pub(crate) fn with_hasher(hasher: THasher) -> Table<TSymbol, THasher> {
         Table {
             total_symbols: 0,
-            entries: vec![],
-            entry_indices: Default::default(),
+            frequencies: vec![],
+            symbols: vec![],
+            entry_indices: HashMap::with_hasher(hasher),
+            cumulative: Default::default(),
+            order: Default::default(),
         }
     }
 
+    #[cfg(test)]
     pub(crate) fn add(&mut self, s: Option<TSymbol>) {
-        match self.entry_indices.get(&s) {
-            Some(i) => {
-                let index = *i;
-                let entry = &mut self.entries[index];
-                entry.frequency += 1;
-                self.sort_entry(index);
+        self.add_weighted(s, 1);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn add_weighted(&mut self, s: Option<TSymbol>, weight: usize) {
+        self.add_weighted_with(s, weight, crate::CountOverflow::Saturate);
+    }
+
+    pub(crate) fn add_weighted_with(
+        &mut self,
+        s: Option<TSymbol>,
+        weight: usize,
+        overflow: crate::CountOverflow,
+    ) {
+        self.invalidate_order();
+
+        let max = Count::MAX as usize;
+        let mut weight = weight.min(max);
+        let mut index = self.index_or_insert(s);
+        let mut current = self.frequencies[index] as usize;
+
+        if current > max - weight {
+            match overflow {
+                crate::CountOverflow::Saturate => weight = max - current,
+                crate::CountOverflow::Halve => {
+                    while current > max - weight {
+                        self.halve();
+                        index = self.index_or_insert(s);
+                        current = self.frequencies[index] as usize;
+                    }
+                }
             }
+        }
 
-            None => {
-                let index = self.entries.len();
+        self.frequencies[index] += weight as Count;
+        self.cumulative.add(index, weight);
+        self.total_symbols = self.total_symbols.saturating_add(weight);
+    }
 
-                self.entries.push(TableEntry {
-                    frequency: 1,
-                    symbol: s,
-                });
+    // Returns the index of the entry for the specified symbol, adding an empty
+    // entry if there is none, while hashing the symbol only once
+    fn index_or_insert(&mut self, s: Option<TSymbol>) -> usize {
+        let next_index = self.symbols.len();
+        let index = *self.entry_indices.entry(s).or_insert(next_index);
 
-                self.entry_indices.insert(s, index);
-            }
-        };
+        if index == next_index {
+            self.frequencies.push(0);
+            self.symbols.push(s);
+            self.cumulative.push(0);
+        }
+        index
+    }
 
-        self.total_symbols += 1;
+    // Returns the position at which an entry is stored, which is stable until
+    // this table is next modified
+    pub(crate) fn index_of(&self, s: &Option<TSymbol>) -> Option<usize> {
+        self.entry_indices.get(s).copied()
     }
 
-    pub(crate) fn most_frequent(&self) -> Option<&TSymbol> {
-        match self.entries.first() {
-            Some(e) => e.symbol.as_ref(),
-            None => None,
+    fn reindex(&mut self) {
+        self.entry_indices.clear();
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            self.entry_indices.insert(*symbol, i);
         }
     }
 
-    pub(crate) fn sample(&self, sample_value: f64) -> Option<&TSymbol> {
-        let mut remaining = (sample_value * self.total_symbols as f64) as usize;
+    pub(crate) fn remove_weighted(&mut self, s: &Option<TSymbol>, weight: usize) {
+        let index = match self.index_of(s) {
+            Some(i) => i,
+            None => return,
+        };
+
+        self.invalidate_order();
+
+        let weight = weight.min(self.frequencies[index] as usize);
+        self.frequencies[index] -= weight as Count;
+        self.cumulative.subtract(index, weight);
+        self.total_symbols -= weight;
 
-        for entry in &self.entries {
-            if remaining < entry.frequency {
-                return entry.symbol.as_ref();
+        if self.frequencies[index] == 0 {
+            self.frequencies.remove(index);
+            self.symbols.remove(index);
+            self.entry_indices.remove(s);
+            for i in index..self.symbols.len() {
+                self.entry_indices.insert(self.symbols[i], i);
             }
-            remaining -= entry.frequency;
+            self.rebuild_cumulative();
+        }
+    }
+
+    pub(crate) fn halve(&mut self) {
+        self.invalidate_order();
+
+        for frequency in &mut self.frequencies {
+            *frequency /= 2;
         }
 
-        None
+        let frequencies = &self.frequencies;
+        let mut i = 0;
+        self.symbols.retain(|_| {
+            i += 1;
+            frequencies[i - 1] > 0
+        });
+        self.frequencies.retain(|f| *f > 0);
+        self.reindex();
+        self.rebuild_cumulative();
+
+        self.total_symbols = self.frequencies.iter().map(|f| *f as usize).sum();
     }
 
-    fn sort_entry(&mut self, index: usize) {
-        let mut j = index;
+    pub(crate) fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
 
-        for i in (0..index).rev() {
-            if self.entries[j].frequency <= self.entries[i].frequency {
-                break;
-            }
+    pub(crate) fn len(&self) -> usize {
+        self.symbols.len()
+    }
 
-            let tmp = self.entries[i];
-            self.entries[i] = self.entries[j];
-            self.entries[j] = tmp;
-            j = i;
+    pub(crate) fn merge(
+        &mut self,
+        other: &Table<TSymbol, THasher>,
+        overflow: crate::CountOverflow,
+    ) {
+        for (symbol, frequency) in other.iter() {
+            self.add_weighted_with(*symbol, frequency, overflow);
         }
+    }
+
+    pub(crate) fn total(&self) -> usize {
+        self.total_symbols
+    }
+
+    // Iterates over entries from most to least frequent, with ties in the
+    // order in which they were first seen
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Option<TSymbol>, usize)> {
+        self.order()
+            .iter()
+            .map(move |&i| (&self.symbols[i], self.frequencies[i] as usize))
+    }
 
-        for i in j..=index {
-            self.entry_indices.insert(self.entries[i].symbol, i);
+    // Returns the entry at the specified position from most to least frequent
+    pub(crate) fn ranked(&self, rank: usize) -> (&Option<TSymbol>, usize) {
+        let i = self.order()[rank];
+        (&self.symbols[i], self.frequencies[i] as usize)
+    }
+
+    pub(crate) fn frequency_of(&self, s: &Option<TSymbol>) -> usize {
+        match self.index_of(s) {
+            Some(i) => self.frequencies[i] as usize,
+            None => 0,
         }
     }
-}
 
-#[derive(Copy, Clone)]
-struct TableEntry<TSymbol> {
-    frequency: usize,
-    symbol: Option<TSymbol>,
+    // Returns the symbol of each entry in the order in which entries are
+    // stored, so that it can be found by `index_of`
+    pub(crate) fn stored_symbols(&self) -> &[Option<TSymbol>] {
+        &self.symbols
+    }
+
+    // Returns the surprisal, in bits, of each entry in the order in which
+    // entries are stored, so that it can be found by `index_of`
+    pub(crate) fn surprisals(&self) -> Vec<f64> {
+        let total = self.total_symbols as f64;
+        self.frequencies
+            .iter()
+            .map(|f| -(*f as f64 / total).log2())
+            .collect()
+    }
+
+    pub(crate) fn probability_of(&self, s: &Option<TSymbol>) -> f64 {
+        if self.total_symbols == 0 {
+            return 0.0;
+        }
+        self.frequency_of(s) as f64 / self.total_symbols as f64
+    }
+
+    pub(crate) fn most_frequent(&self) -> Option<&TSymbol> {
+        match self.iter().next() {
+            Some((s, _)) => s.as_ref(),
+            None => None,
+        }
+    }
+
+    pub(crate) fn sample(&self, sample_value: f64) -> Option<&TSymbol> {
+        let value = crate::random::scale(sample_value, self.total_symbols);
+        let index = self.cumulative.find(value);
+
+        match self.symbols.get(index) {
+            Some(s) => s.as_ref(),
+            None => None,
+        }
+    }
+
+    fn rebuild_cumulative(&mut self) {
+        self.cumulative =
+            crate::fenwick::Fenwick::from_counts(self.frequencies.iter().map(|&f| f as usize));
+    }
+
+    fn order(&self) -> &[usize] {
+        self.order.get_or_init(|| self.compute_order())
+    }
+
+    fn compute_order(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.frequencies.len()).collect();
+        indices.sort_by_key(|&i| std::cmp::Reverse(self.frequencies[i]));
+        indices
+    }
+
+    fn invalidate_order(&mut self) {
+        self.order.take();
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::count_overflow::CountOverflow;
     use crate::table::Table;
 
     #[test]
     fn it_initialises_an_empty_table() {
         let t = Table::<i32>::empty();
 
-        assert!(t.entries.is_empty());
+        assert!(t.symbols.is_empty());
+        assert!(t.frequencies.is_empty());
         assert!(t.entry_indices.is_empty());
         assert_eq!(t.total_symbols, 0);
     }
@@ -107,29 +285,107 @@ mod test {
 
         t.add(Some('a'));
 
-        let entry = t.entries[*t.entry_indices.get(&Some('a')).unwrap()];
-        assert_eq!(entry.frequency, 1);
-        assert_eq!(entry.symbol, Some('a'));
+        let i = t.index_of(&Some('a')).unwrap();
+        assert_eq!(t.frequencies[i], 1);
+        assert_eq!(t.symbols[i], Some('a'));
 
         t.add(Some('b'));
 
-        let entry = t.entries[*t.entry_indices.get(&Some('a')).unwrap()];
-        assert_eq!(entry.frequency, 1);
-        assert_eq!(entry.symbol, Some('a'));
+        let i = t.index_of(&Some('a')).unwrap();
+        assert_eq!(t.frequencies[i], 1);
+        assert_eq!(t.symbols[i], Some('a'));
+
+        let i = t.index_of(&Some('b')).unwrap();
+        assert_eq!(t.frequencies[i], 1);
+        assert_eq!(t.symbols[i], Some('b'));
+
+        t.add(Some('a'));
+
+        let i = t.index_of(&Some('a')).unwrap();
+        assert_eq!(t.frequencies[i], 2);
+        assert_eq!(t.symbols[i], Some('a'));
+
+        let i = t.index_of(&Some('b')).unwrap();
+        assert_eq!(t.frequencies[i], 1);
+        assert_eq!(t.symbols[i], Some('b'));
+    }
+
+    #[test]
+    fn it_tracks_weighted_additions() {
+        let mut t = Table::empty();
 
-        let entry = t.entries[*t.entry_indices.get(&Some('b')).unwrap()];
-        assert_eq!(entry.frequency, 1);
-        assert_eq!(entry.symbol, Some('b'));
+        t.add(Some('a'));
+        t.add_weighted(Some('b'), 3);
+        assert_eq!(t.frequency_of(&Some('b')), 3);
+        assert_eq!(t.total_symbols, 4);
+        assert_eq!(t.most_frequent(), Some(&'b'));
 
+        t.add_weighted(Some('a'), 3);
+        assert_eq!(t.frequency_of(&Some('a')), 4);
+        assert_eq!(t.total_symbols, 7);
+        assert_eq!(t.most_frequent(), Some(&'a'));
+    }
+
+    #[test]
+    fn it_merges_frequencies_from_other_tables() {
+        let mut t = Table::empty();
         t.add(Some('a'));
+        t.add(Some('b'));
+
+        let mut other = Table::empty();
+        other.add(Some('b'));
+        other.add(Some('b'));
+        other.add(None);
 
-        let entry = t.entries[*t.entry_indices.get(&Some('a')).unwrap()];
-        assert_eq!(entry.frequency, 2);
-        assert_eq!(entry.symbol, Some('a'));
+        t.merge(&other, CountOverflow::Saturate);
 
-        let entry = t.entries[*t.entry_indices.get(&Some('b')).unwrap()];
-        assert_eq!(entry.frequency, 1);
-        assert_eq!(entry.symbol, Some('b'));
+        assert_eq!(t.frequency_of(&Some('a')), 1);
+        assert_eq!(t.frequency_of(&Some('b')), 3);
+        assert_eq!(t.frequency_of(&None), 1);
+        assert_eq!(t.total_symbols, 5);
+        assert_eq!(t.most_frequent(), Some(&'b'));
+    }
+
+    #[test]
+    fn it_removes_frequencies_of_symbols() {
+        let mut t = Table::empty();
+        t.add_weighted(Some('a'), 3);
+        t.add_weighted(Some('b'), 2);
+        t.add(Some('c'));
+
+        t.remove_weighted(&Some('a'), 2);
+        assert_eq!(t.frequency_of(&Some('a')), 1);
+        assert_eq!(t.total_symbols, 4);
+        assert_eq!(t.most_frequent(), Some(&'b'));
+
+        t.remove_weighted(&Some('b'), 2);
+        assert_eq!(t.frequency_of(&Some('b')), 0);
+        assert_eq!(t.total_symbols, 2);
+        assert_eq!(t.symbols.len(), 2);
+        assert_eq!(t.sample(0.0), Some(&'a'));
+        assert_eq!(t.sample(0.5), Some(&'c'));
+
+        t.remove_weighted(&Some('a'), 1);
+        t.remove_weighted(&Some('c'), 1);
+        assert!(t.is_empty());
+        assert!(t.entry_indices.is_empty());
+    }
+
+    #[test]
+    fn it_halves_frequencies_and_discards_emptied_entries() {
+        let mut t = Table::empty();
+        t.add_weighted(Some('a'), 5);
+        t.add(Some('b'));
+        t.add_weighted(Some('c'), 2);
+
+        t.halve();
+
+        assert_eq!(t.frequency_of(&Some('a')), 2);
+        assert_eq!(t.frequency_of(&Some('b')), 0);
+        assert_eq!(t.frequency_of(&Some('c')), 1);
+        assert_eq!(t.total_symbols, 3);
+        assert_eq!(t.symbols.len(), 2);
+        assert_eq!(t.sample(0.7), Some(&'c'));
     }
 
     #[test]
@@ -146,6 +402,24 @@ mod test {
         assert_eq!(t.total_symbols, 3);
     }
 
+    #[test]
+    fn it_exposes_probability_of_symbols() {
+        let mut t = Table::empty();
+
+        assert_eq!(t.probability_of(&Some('a')), 0.0);
+
+        t.add(Some('a'));
+        t.add(Some('b'));
+        t.add(Some('a'));
+        t.add(None);
+
+        assert_eq!(t.frequency_of(&Some('a')), 2);
+        assert_eq!(t.probability_of(&Some('a')), 0.5);
+        assert_eq!(t.probability_of(&Some('b')), 0.25);
+        assert_eq!(t.probability_of(&None), 0.25);
+        assert_eq!(t.probability_of(&Some('c')), 0.0);
+    }
+
     #[test]
     fn it_exposes_most_frequent_symbol() {
         let mut t = Table::empty();
@@ -190,17 +464,92 @@ mod test {
         assert_eq!(t.sample(0.67), Some(&'c'));
 
         t.add(Some('b'));
-        assert_eq!(t.sample(0.0), Some(&'b'));
-        assert_eq!(t.sample(0.5), Some(&'a'));
+        assert_eq!(t.sample(0.0), Some(&'a'));
+        assert_eq!(t.sample(0.25), Some(&'b'));
+        assert_eq!(t.sample(0.5), Some(&'b'));
         assert_eq!(t.sample(0.75), Some(&'c'));
 
         t.add(Some('c'));
-        assert_eq!(t.sample(0.0), Some(&'b'));
-        assert_eq!(t.sample(0.4), Some(&'c'));
-        assert_eq!(t.sample(0.8), Some(&'a'));
+        assert_eq!(t.sample(0.0), Some(&'a'));
+        assert_eq!(t.sample(0.21), Some(&'b'));
+        assert_eq!(t.sample(0.61), Some(&'c'));
 
         t.add(Some('c'));
 
-        assert_eq!(t.sample(0.0), Some(&'c'));
+        assert_eq!(t.sample(0.5), Some(&'c'));
+    }
+
+    #[test]
+    fn it_keeps_entries_ordered_across_weighted_changes() {
+        let mut t = Table::empty();
+        for (s, w) in [('a', 5), ('b', 4), ('c', 4), ('d', 3), ('e', 1), ('f', 1)] {
+            t.add_weighted(Some(s), w);
+        }
+        assert_eq!(t.sample(0.99), Some(&'f'));
+
+        t.add_weighted(Some('f'), 4);
+        t.remove_weighted(&Some('a'), 4);
+        t.add_weighted(Some('e'), 2);
+
+        let entries: Vec<_> = t.iter().map(|(s, f)| (s.unwrap(), f)).collect();
+        assert_eq!(
+            entries,
+            vec![('f', 5), ('b', 4), ('c', 4), ('d', 3), ('e', 3), ('a', 1)]
+        );
+        assert_eq!(t.sample(0.0), Some(&'a'));
+        assert_eq!(t.sample(0.06), Some(&'b'));
+        assert_eq!(t.sample(0.99), Some(&'f'));
+    }
+
+    #[test]
+    fn it_samples_symbols_in_exact_proportion() {
+        let mut t = Table::empty();
+        t.add_weighted(Some('a'), 2);
+        t.add_weighted(Some('b'), 1);
+
+        assert_eq!(t.sample(2.0 / 3.0 - f64::EPSILON), Some(&'a'));
+        assert_eq!(t.sample(2.0 / 3.0), Some(&'a'));
+        assert_eq!(t.sample(0.67), Some(&'b'));
+        assert_eq!(t.sample(1.0 - f64::EPSILON / 2.0), Some(&'b'));
+        assert_eq!(t.sample(1.0), Some(&'b'));
+
+        t.add_weighted(Some('a'), 1);
+        let steps = 1 << 10;
+        let drawn_a = (0..steps)
+            .filter(|i| t.sample(*i as f64 / steps as f64) == Some(&'a'))
+            .count();
+        assert_eq!(drawn_a, 3 << 8);
+    }
+
+    #[test]
+    #[cfg(not(feature = "wide-counts"))]
+    fn it_saturates_overflowing_counts() {
+        let max = super::Count::MAX as usize;
+
+        let mut t = Table::empty();
+        t.add_weighted_with(Some('a'), max - 1, CountOverflow::Saturate);
+        t.add_weighted_with(Some('b'), 1, CountOverflow::Saturate);
+        t.add_weighted_with(Some('a'), 5, CountOverflow::Saturate);
+
+        assert_eq!(t.frequency_of(&Some('a')), max);
+        assert_eq!(t.frequency_of(&Some('b')), 1);
+        assert_eq!(t.total(), max + 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "wide-counts"))]
+    fn it_halves_counts_to_fit_overflowing_counts() {
+        let max = super::Count::MAX as usize;
+
+        let mut t = Table::empty();
+        t.add_weighted_with(Some('a'), max - 1, CountOverflow::Halve);
+        t.add_weighted_with(Some('b'), 4, CountOverflow::Halve);
+        t.add_weighted_with(Some('c'), 1, CountOverflow::Halve);
+        t.add_weighted_with(Some('a'), 5, CountOverflow::Halve);
+
+        assert_eq!(t.frequency_of(&Some('a')), (max - 1) / 2 + 5);
+        assert_eq!(t.frequency_of(&Some('b')), 2);
+        assert_eq!(t.frequency_of(&Some('c')), 0);
+        assert_eq!(t.total(), (max - 1) / 2 + 7);
     }
 }
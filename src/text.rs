@@ -0,0 +1,17 @@
+//! Helpers for training models on, and generating, natural language text.
+
+mod char_model;
+#[cfg(feature = "graphemes")]
+mod grapheme_model;
+mod sentences;
+#[cfg(feature = "tokenizers")]
+mod token_model;
+mod word_model;
+
+pub use self::char_model::CharModel;
+#[cfg(feature = "graphemes")]
+pub use self::grapheme_model::GraphemeModel;
+pub use self::sentences::{RuleBasedSplitter, SentenceSplitter};
+#[cfg(feature = "tokenizers")]
+pub use self::token_model::TokenModel;
+pub use self::word_model::{Tokenizer, WordModel};
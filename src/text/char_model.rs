@@ -0,0 +1,135 @@
+/// A character-level Markov model for generating text such as names.
+pub struct CharModel {
+    model: crate::Model<char>,
+}
+
+impl CharModel {
+    /// Creates an empty CharModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](../struct.Model.html#method.empty).
+    pub fn new(order: usize) -> CharModel {
+        CharModel {
+            model: crate::Model::empty(order),
+        }
+    }
+
+    /// Trains the model on the characters of a string, treating the string as
+    /// a complete sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `text` - The text to train on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::text::CharModel::new(2);
+    /// model.train("Amanda");
+    /// model.train("Miranda");
+    /// ```
+    pub fn train(&mut self, text: &str) {
+        crate::Accumulator::new(&mut self.model).train_sequence(text.chars());
+    }
+
+    /// Generates a string from the model, stopping at the end of a sequence or
+    /// after the specified number of characters, whichever comes first.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// `max_len` - The maximum number of characters to generate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::Rng;
+    ///
+    /// let mut model = markov::text::CharModel::new(2);
+    /// model.train("Amanda");
+    /// model.train("Miranda");
+    ///
+    /// let mut rng = rand::thread_rng();
+    /// let name = model.generate_string(Box::new(move || rng.gen::<f64>()), 20);
+    /// ```
+    pub fn generate_string(
+        &self,
+        rand_source: Box<dyn FnMut() -> f64 + '_>,
+        max_len: usize,
+    ) -> String {
+        crate::Generator::new(&self.model, rand_source)
+            .take(max_len)
+            .collect()
+    }
+
+    /// Completes a prefix with the most probable following characters,
+    /// stopping at the end of a sequence or after the specified number of
+    /// characters, whichever comes first.
+    ///
+    /// The returned string includes the prefix.
+    ///
+    /// # Arguments
+    ///
+    /// `prefix` - The beginning of the string to complete.
+    ///
+    /// `max_len` - The maximum number of characters to add to the prefix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::text::CharModel::new(2);
+    /// model.train("Amanda");
+    ///
+    /// assert_eq!(model.complete("Am", 10), "Amanda");
+    /// ```
+    pub fn complete(&self, prefix: &str, max_len: usize) -> String {
+        let mut pre = crate::Predictor::new(&self.model);
+        let mut text = String::from(prefix);
+
+        for c in prefix.chars() {
+            pre.given(c);
+        }
+
+        for _ in 0..max_len {
+            match pre.next() {
+                Some(c) => text.push(*c),
+                None => break,
+            }
+        }
+
+        text
+    }
+
+    /// Returns the underlying model.
+    pub fn model(&self) -> &crate::Model<char> {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::char_model::CharModel;
+
+    #[test]
+    fn it_generates_strings_up_to_maximum_length() {
+        let mut model = CharModel::new(1);
+        model.train("abc");
+
+        assert_eq!(model.generate_string(Box::new(|| 0.0), 10), "abc");
+        assert_eq!(model.generate_string(Box::new(|| 0.0), 2), "ab");
+    }
+
+    #[test]
+    fn it_completes_prefixes() {
+        let mut model = CharModel::new(2);
+        model.train("banana");
+
+        assert_eq!(model.complete("ban", 3), "banana");
+        assert_eq!(model.complete("ban", 100).len(), 103);
+        assert_eq!(model.complete("xyz", 10), "xyz");
+    }
+}
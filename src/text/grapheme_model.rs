@@ -0,0 +1,129 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A character-level Markov model which treats each extended grapheme cluster
+/// as a single symbol.
+///
+/// Unlike a [CharModel](struct.CharModel.html), a GraphemeModel never
+/// generates text which splits emoji sequences or separates combining marks
+/// from the characters they modify.
+pub struct GraphemeModel {
+    model: crate::Model<u32>,
+    graphemes: crate::interner::Interner<String>,
+}
+
+impl GraphemeModel {
+    /// Creates an empty GraphemeModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](../struct.Model.html#method.empty).
+    pub fn new(order: usize) -> GraphemeModel {
+        GraphemeModel {
+            model: crate::Model::empty(order),
+            graphemes: crate::interner::Interner::empty(),
+        }
+    }
+
+    /// Trains the model on the grapheme clusters of a string, treating the
+    /// string as a complete sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `text` - The text to train on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::text::GraphemeModel::new(2);
+    /// model.train("Zoë");
+    /// model.train("Chloë");
+    /// ```
+    pub fn train(&mut self, text: &str) {
+        let graphemes = &mut self.graphemes;
+        let ids: Vec<u32> = text.graphemes(true).map(|g| graphemes.intern(g)).collect();
+
+        crate::Accumulator::new(&mut self.model).train_sequence(ids);
+    }
+
+    /// Generates a string from the model, stopping at the end of a sequence or
+    /// after the specified number of grapheme clusters, whichever comes first.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// `max_len` - The maximum number of grapheme clusters to generate.
+    pub fn generate_string(
+        &self,
+        rand_source: Box<dyn FnMut() -> f64 + '_>,
+        max_len: usize,
+    ) -> String {
+        crate::Generator::new(&self.model, rand_source)
+            .take(max_len)
+            .map(|id| self.grapheme(id))
+            .collect()
+    }
+
+    /// Completes a prefix with the most probable following grapheme clusters,
+    /// stopping at the end of a sequence or after the specified number of
+    /// grapheme clusters, whichever comes first.
+    ///
+    /// The returned string includes the prefix.
+    ///
+    /// # Arguments
+    ///
+    /// `prefix` - The beginning of the string to complete.
+    ///
+    /// `max_len` - The maximum number of grapheme clusters to add to the
+    /// prefix.
+    pub fn complete(&self, prefix: &str, max_len: usize) -> String {
+        let mut pre = crate::Predictor::new(&self.model);
+        let mut text = String::from(prefix);
+
+        for g in prefix.graphemes(true) {
+            match self.graphemes.get(g) {
+                Some(id) => pre.given(id),
+                None => return text,
+            }
+        }
+
+        for _ in 0..max_len {
+            match pre.next() {
+                Some(id) => text.push_str(self.grapheme(*id)),
+                None => break,
+            }
+        }
+
+        text
+    }
+
+    /// Returns the underlying model, whose symbols are grapheme cluster IDs.
+    pub fn model(&self) -> &crate::Model<u32> {
+        &self.model
+    }
+
+    fn grapheme(&self, id: u32) -> &str {
+        self.graphemes
+            .resolve(id)
+            .map(|g| g.as_str())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::grapheme_model::GraphemeModel;
+
+    #[test]
+    fn it_never_splits_grapheme_clusters() {
+        let mut model = GraphemeModel::new(1);
+        model.train("e\u{301}👩‍🔬");
+
+        assert_eq!(model.generate_string(Box::new(|| 0.0), 10), "e\u{301}👩‍🔬");
+        assert_eq!(model.generate_string(Box::new(|| 0.0), 1), "e\u{301}");
+        assert_eq!(model.complete("e\u{301}", 10), "e\u{301}👩‍🔬");
+        assert_eq!(model.complete("e", 10), "e");
+    }
+}
@@ -0,0 +1,151 @@
+/// A strategy for splitting text into sentences.
+pub trait SentenceSplitter {
+    /// Splits text into sentences, excluding any surrounding whitespace.
+    ///
+    /// # Arguments
+    ///
+    /// `text` - The text to split.
+    fn split<'t>(&self, text: &'t str) -> Vec<&'t str>;
+}
+
+/// A SentenceSplitter which ends sentences at terminal punctuation followed by
+/// whitespace, except after known abbreviations and initials.
+pub struct RuleBasedSplitter {
+    abbreviations: Vec<String>,
+}
+
+impl RuleBasedSplitter {
+    /// Creates a RuleBasedSplitter which recognises common English
+    /// abbreviations such as "Mr." and "e.g.".
+    pub fn new() -> RuleBasedSplitter {
+        RuleBasedSplitter::with_abbreviations(&[
+            "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "e.g", "i.e", "cf", "no",
+        ])
+    }
+
+    /// Creates a RuleBasedSplitter which recognises the specified
+    /// abbreviations.
+    ///
+    /// # Arguments
+    ///
+    /// `abbreviations` - Words which do not end a sentence when followed by a
+    /// full stop, without the trailing full stop. These are matched
+    /// case-insensitively.
+    pub fn with_abbreviations(abbreviations: &[&str]) -> RuleBasedSplitter {
+        RuleBasedSplitter {
+            abbreviations: abbreviations.iter().map(|a| a.to_lowercase()).collect(),
+        }
+    }
+
+    fn is_abbreviation(&self, word: &str) -> bool {
+        let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+
+        let mut chars = word.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c.is_uppercase();
+        }
+
+        let word = word.to_lowercase();
+        self.abbreviations.contains(&word)
+    }
+}
+
+impl Default for RuleBasedSplitter {
+    fn default() -> Self {
+        RuleBasedSplitter::new()
+    }
+}
+
+impl SentenceSplitter for RuleBasedSplitter {
+    /// Splits text into sentences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use markov::text::{RuleBasedSplitter, SentenceSplitter};
+    ///
+    /// let sentences = RuleBasedSplitter::new().split("Dr. Who? Yes. Fine!");
+    /// assert_eq!(sentences, vec!["Dr. Who?", "Yes.", "Fine!"]);
+    /// ```
+    fn split<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        let mut sentences = vec![];
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+
+            let mut end = i + c.len_utf8();
+            while let Some((j, next)) = chars.peek() {
+                if !matches!(next, '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’') {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+
+            match chars.peek() {
+                Some((_, next)) if !next.is_whitespace() => continue,
+                _ => {}
+            }
+
+            if c == '.' && end == i + 1 {
+                let word = text[start..i].split_whitespace().last().unwrap_or("");
+                if self.is_abbreviation(word) {
+                    continue;
+                }
+            }
+
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+
+        let sentence = text[start..].trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+
+        sentences
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::sentences::{RuleBasedSplitter, SentenceSplitter};
+
+    #[test]
+    fn it_splits_at_terminal_punctuation() {
+        let splitter = RuleBasedSplitter::new();
+
+        assert_eq!(
+            splitter.split("  One.  Two!\nThree?! \"Four.\" Five"),
+            vec!["One.", "Two!", "Three?!", "\"Four.\"", "Five"]
+        );
+        assert_eq!(
+            splitter.split("Pi is 3.14 or so."),
+            vec!["Pi is 3.14 or so."]
+        );
+        assert!(splitter.split("   ").is_empty());
+    }
+
+    #[test]
+    fn it_does_not_split_after_abbreviations_or_initials() {
+        let splitter = RuleBasedSplitter::new();
+
+        assert_eq!(
+            splitter.split("Mr. J. Smith met Dr. Jones, e.g. today. Bye."),
+            vec!["Mr. J. Smith met Dr. Jones, e.g. today.", "Bye."]
+        );
+
+        let splitter = RuleBasedSplitter::with_abbreviations(&["approx"]);
+        assert_eq!(
+            splitter.split("It is approx. ten. Mr. Smith."),
+            vec!["It is approx. ten.", "Mr.", "Smith."]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+/// A Markov model over the token IDs produced by a HuggingFace
+/// [tokenizers](https://docs.rs/tokenizers) tokenizer.
+///
+/// TokenModels are useful as cheap baselines alongside neural language models
+/// which share the same tokenizer.
+pub struct TokenModel {
+    model: crate::Model<u32>,
+    tokenizer: tokenizers::Tokenizer,
+}
+
+impl TokenModel {
+    /// Creates an empty TokenModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](../struct.Model.html#method.empty).
+    ///
+    /// `tokenizer` - The tokenizer used to encode training text and decode
+    /// generated text.
+    pub fn new(order: usize, tokenizer: tokenizers::Tokenizer) -> TokenModel {
+        TokenModel {
+            model: crate::Model::empty(order),
+            tokenizer,
+        }
+    }
+
+    /// Trains the model on the tokens of a document, treating the document as
+    /// a complete sequence.
+    ///
+    /// Special tokens are not added during encoding.
+    ///
+    /// # Arguments
+    ///
+    /// `document` - The text to train on.
+    pub fn train(&mut self, document: &str) -> tokenizers::Result<()> {
+        let encoding = self.tokenizer.encode(document, false)?;
+
+        crate::Accumulator::new(&mut self.model).train_sequence(encoding.get_ids().iter().copied());
+        Ok(())
+    }
+
+    /// Generates a document from the model, stopping at the end of a sequence
+    /// or after the specified number of tokens, whichever comes first, and
+    /// decodes it with the tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// `max_len` - The maximum number of tokens to generate.
+    pub fn generate_string(
+        &self,
+        rand_source: Box<dyn FnMut() -> f64 + '_>,
+        max_len: usize,
+    ) -> tokenizers::Result<String> {
+        let ids: Vec<u32> = crate::Generator::new(&self.model, rand_source)
+            .take(max_len)
+            .collect();
+
+        self.tokenizer.decode(&ids, true)
+    }
+
+    /// Returns the underlying model, whose symbols are token IDs.
+    pub fn model(&self) -> &crate::Model<u32> {
+        &self.model
+    }
+
+    /// Returns the tokenizer used by this model.
+    pub fn tokenizer(&self) -> &tokenizers::Tokenizer {
+        &self.tokenizer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::token_model::TokenModel;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    #[test]
+    fn it_trains_and_generates_over_token_ids() {
+        let vocab = vec!["[UNK]", "the", "quick", "fox"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32))
+            .collect();
+
+        let word_level = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+
+        let mut tokenizer = tokenizers::Tokenizer::new(word_level);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+
+        let mut model = TokenModel::new(1, tokenizer);
+        model.train("the quick fox").unwrap();
+
+        assert_eq!(crate::read_model::sample(model.model(), &[], 0.0), Some(&1));
+
+        let text = model.generate_string(Box::new(|| 0.0), 10).unwrap();
+        assert_eq!(text, "the quick fox");
+    }
+}
@@ -0,0 +1,230 @@
+/// Strategies for splitting text into words.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// Words are separated by whitespace, and punctuation remains part of the
+    /// words it is attached to.
+    Whitespace,
+
+    /// Words are separated by whitespace, and each punctuation character is
+    /// treated as a word of its own.
+    Punctuation,
+}
+
+impl Tokenizer {
+    pub(crate) fn tokenize<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        match self {
+            Tokenizer::Whitespace => text.split_whitespace().collect(),
+            Tokenizer::Punctuation => {
+                let mut words = vec![];
+
+                for word in text.split_whitespace() {
+                    let mut start = 0;
+
+                    for (i, c) in word.char_indices() {
+                        if c.is_alphanumeric() || c == '\'' {
+                            continue;
+                        }
+                        if start < i {
+                            words.push(&word[start..i]);
+                        }
+                        words.push(&word[i..(i + c.len_utf8())]);
+                        start = i + c.len_utf8();
+                    }
+
+                    if start < word.len() {
+                        words.push(&word[start..]);
+                    }
+                }
+
+                words
+            }
+        }
+    }
+}
+
+/// A word-level Markov model for natural language text.
+///
+/// WordModels take care of splitting documents into words and tracking the
+/// distinct words seen, so that text can be trained on and generated as
+/// ordinary strings.
+pub struct WordModel {
+    model: crate::Model<u32>,
+    words: crate::interner::Interner<String>,
+    tokenizer: Tokenizer,
+}
+
+impl WordModel {
+    /// Creates an empty WordModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](../struct.Model.html#method.empty).
+    ///
+    /// `tokenizer` - How documents are split into words.
+    pub fn new(order: usize, tokenizer: Tokenizer) -> WordModel {
+        WordModel {
+            model: crate::Model::empty(order),
+            words: crate::interner::Interner::empty(),
+            tokenizer,
+        }
+    }
+
+    /// Trains the model on the words of a document, treating the document as
+    /// a complete sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `document` - The text to train on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use markov::text::{Tokenizer, WordModel};
+    ///
+    /// let mut model = WordModel::new(1, Tokenizer::Punctuation);
+    /// model.train("The quick brown fox jumps over the lazy dog.");
+    /// ```
+    pub fn train(&mut self, document: &str) {
+        let words = &mut self.words;
+        let ids: Vec<u32> = self
+            .tokenizer
+            .tokenize(document)
+            .into_iter()
+            .map(|w| words.intern(w))
+            .collect();
+
+        crate::Accumulator::new(&mut self.model).train_sequence(ids);
+    }
+
+    /// Trains the model on each sentence of a document, treating each sentence
+    /// as a complete sequence so that sequence ends reflect sentence
+    /// boundaries.
+    ///
+    /// # Arguments
+    ///
+    /// `document` - The text to train on.
+    ///
+    /// `splitter` - How the document is split into sentences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use markov::text::{RuleBasedSplitter, Tokenizer, WordModel};
+    ///
+    /// let mut model = WordModel::new(1, Tokenizer::Punctuation);
+    /// model.train_sentences("Hello there. How are you?", &RuleBasedSplitter::new());
+    /// ```
+    pub fn train_sentences(
+        &mut self,
+        document: &str,
+        splitter: &dyn crate::text::SentenceSplitter,
+    ) {
+        for sentence in splitter.split(document) {
+            self.train(sentence);
+        }
+    }
+
+    /// Generates a complete document from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use markov::text::{Tokenizer, WordModel};
+    ///
+    /// let mut model = WordModel::new(1, Tokenizer::Punctuation);
+    /// model.train("The quick brown fox jumps over the lazy dog.");
+    ///
+    /// let text = model.generate(Box::new(|| 0.0));
+    /// assert_eq!(text, "The quick brown fox jumps over the lazy dog.");
+    /// ```
+    pub fn generate(&self, rand_source: Box<dyn FnMut() -> f64 + '_>) -> String {
+        let mut text = String::new();
+
+        for id in crate::Generator::new(&self.model, rand_source) {
+            let word = self.word(id).unwrap_or_default();
+            if !text.is_empty() && !self.is_attached(word) {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+
+        text
+    }
+
+    /// Returns the underlying model, whose symbols are word IDs.
+    pub fn model(&self) -> &crate::Model<u32> {
+        &self.model
+    }
+
+    /// Returns the ID of a word, if it has been seen during training.
+    pub fn id(&self, word: &str) -> Option<u32> {
+        self.words.get(word)
+    }
+
+    /// Returns the word with the specified ID.
+    pub fn word(&self, id: u32) -> Option<&str> {
+        self.words.resolve(id).map(|w| w.as_str())
+    }
+
+    fn is_attached(&self, word: &str) -> bool {
+        match self.tokenizer {
+            Tokenizer::Whitespace => false,
+            Tokenizer::Punctuation => {
+                let mut chars = word.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')'),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::sentences::RuleBasedSplitter;
+    use crate::text::word_model::{Tokenizer, WordModel};
+
+    #[test]
+    fn it_tokenizes_on_whitespace() {
+        let words = Tokenizer::Whitespace.tokenize("  Hello,  world! ");
+        assert_eq!(words, vec!["Hello,", "world!"]);
+    }
+
+    #[test]
+    fn it_tokenizes_punctuation_separately() {
+        let words = Tokenizer::Punctuation.tokenize("Hello, (don't) panic!");
+        assert_eq!(words, vec!["Hello", ",", "(", "don't", ")", "panic", "!"]);
+    }
+
+    #[test]
+    fn it_trains_each_sentence_as_a_sequence() {
+        let mut model = WordModel::new(1, Tokenizer::Punctuation);
+        model.train_sentences("Hello. Hello world.", &RuleBasedSplitter::new());
+
+        let full_stop = model.id(".").unwrap();
+        assert_eq!(model.model().end_probability(&[full_stop]), Some(1.0));
+        assert_eq!(model.generate(Box::new(|| 0.0)), "Hello.");
+        assert_eq!(model.generate(Box::new(|| 0.5)), "Hello world.");
+    }
+
+    #[test]
+    fn it_trains_and_generates_text() {
+        let mut model = WordModel::new(1, Tokenizer::Punctuation);
+        model.train("Hello, world!");
+        model.train("Hello again.");
+
+        assert_eq!(model.id("Hello"), Some(0));
+        assert_eq!(model.word(1), Some(","));
+        assert_eq!(model.id("goodbye"), None);
+
+        assert_eq!(model.generate(Box::new(|| 0.0)), "Hello, world!");
+        assert_eq!(model.generate(Box::new(|| 0.5)), "Hello again.");
+    }
+}
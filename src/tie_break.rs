@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+
+/// Policies for choosing between equally frequent symbols when a
+/// [Predictor](struct.Predictor.html) predicts the most probable next symbol.
+pub struct TieBreak<'a, TSymbol> {
+    policy: Policy<'a, TSymbol>,
+}
+
+enum Policy<'a, TSymbol> {
+    FirstSeen,
+    Ordered(fn(&Option<TSymbol>, &Option<TSymbol>) -> std::cmp::Ordering),
+    Random(RefCell<&'a mut (dyn FnMut() -> f64 + Send)>),
+}
+
+impl<'a, TSymbol> TieBreak<'a, TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Chooses whichever of the tied symbols was first observed during
+    /// training. This is the default.
+    pub fn first_seen() -> TieBreak<'a, TSymbol> {
+        TieBreak {
+            policy: Policy::FirstSeen,
+        }
+    }
+
+    /// Chooses randomly between the tied symbols.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function which returns numbers in the [0, 1) range,
+    /// such as from a seeded pseudorandom number generator. It must be `Send`
+    /// so that the Predictor using it can be moved between threads.
+    pub fn random(rand_source: &'a mut (dyn FnMut() -> f64 + Send)) -> TieBreak<'a, TSymbol> {
+        TieBreak {
+            policy: Policy::Random(RefCell::new(rand_source)),
+        }
+    }
+
+    pub(crate) fn choose<'s>(&self, ties: &[&'s Option<TSymbol>]) -> Option<&'s Option<TSymbol>> {
+        match &self.policy {
+            Policy::FirstSeen => ties.first().copied(),
+            Policy::Ordered(cmp) => ties.iter().copied().min_by(|a, b| cmp(a, b)),
+            Policy::Random(rand_source) => {
+                if ties.is_empty() {
+                    return None;
+                }
+                let r = (rand_source.borrow_mut())();
+                Some(ties[crate::random::scale(r, ties.len())])
+            }
+        }
+    }
+}
+
+impl<'a, TSymbol> TieBreak<'a, TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq + std::cmp::Ord,
+{
+    /// Chooses the lowest of the tied symbols according to their ordering,
+    /// with the end of a sequence ordered before all symbols.
+    pub fn lexicographic() -> TieBreak<'a, TSymbol> {
+        TieBreak {
+            policy: Policy::Ordered(Ord::cmp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tie_break::TieBreak;
+
+    #[test]
+    fn it_chooses_the_first_seen_tie() {
+        let t = TieBreak::<char>::first_seen();
+
+        assert_eq!(t.choose(&[&Some('c'), &Some('a')]), Some(&Some('c')));
+        assert_eq!(t.choose(&[]), None);
+    }
+
+    #[test]
+    fn it_chooses_the_lowest_tie_lexicographically() {
+        let t = TieBreak::<char>::lexicographic();
+
+        assert_eq!(t.choose(&[&Some('c'), &Some('a')]), Some(&Some('a')));
+        assert_eq!(t.choose(&[&Some('c'), &None]), Some(&None));
+    }
+
+    #[test]
+    fn it_chooses_a_random_tie() {
+        let mut values = vec![0.0, 0.5, 0.99].into_iter();
+        let mut rand_source = move || values.next().unwrap();
+        let t = TieBreak::<char>::random(&mut rand_source);
+
+        let ties = [&Some('a'), &Some('b')];
+        assert_eq!(t.choose(&ties), Some(&Some('a')));
+        assert_eq!(t.choose(&ties), Some(&Some('b')));
+        assert_eq!(t.choose(&ties), Some(&Some('b')));
+    }
+}
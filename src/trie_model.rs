@@ -0,0 +1,263 @@
+/// A Markov model which stores its contexts in a trie, as an alternative to
+/// [Model](struct.Model.html).
+///
+/// Contexts which begin with the same symbols share the nodes for those
+/// symbols, rather than each context being stored in full. As most contexts
+/// of higher order models overlap in this way, this can greatly reduce the
+/// memory needed for them.
+///
+/// TrieModels can be converted to a [Model](struct.Model.html) with
+/// [`to_model`](#method.to_model) for use with Predictors and Generators.
+pub struct TrieModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    order: usize,
+    nodes: Vec<Node<TSymbol>>,
+}
+
+// Children are kept in a list rather than a map, as most nodes have very few
+// of them
+struct Node<TSymbol> {
+    children: Vec<(TSymbol, u32)>,
+    table: Option<crate::Table<TSymbol>>,
+}
+
+impl<TSymbol> TrieModel<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    /// Creates an empty TrieModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    pub fn empty(order: usize) -> TrieModel<TSymbol> {
+        TrieModel {
+            order,
+            nodes: vec![Node {
+                children: vec![],
+                table: None,
+            }],
+        }
+    }
+
+    /// Creates a TrieModel trained on the specified sequences.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    ///
+    /// `sequences` - The training sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::TrieModel::train_from(2, vec!["abcd".chars(), "abce".chars()]);
+    ///
+    /// assert_eq!(model.probability(&['b', 'c'], Some('d')), 0.5);
+    /// ```
+    pub fn train_from<I, S>(order: usize, sequences: I) -> TrieModel<TSymbol>
+    where
+        I: IntoIterator<Item = S>,
+        S: IntoIterator<Item = TSymbol>,
+    {
+        let mut model = TrieModel::empty(order);
+        for sequence in sequences {
+            model.train_sequence(sequence);
+        }
+        model
+    }
+
+    /// Trains the model on a complete sequence, including its end.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    pub fn train_sequence<I>(&mut self, sequence: I)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut seq = crate::Sequence::empty();
+        for symbol in sequence {
+            self.table_or_insert(seq.symbols()).add_weighted_with(
+                Some(symbol),
+                1,
+                crate::CountOverflow::Saturate,
+            );
+            seq = seq.with_next(symbol, self.order);
+        }
+        self.table_or_insert(seq.symbols()).add_weighted_with(
+            None,
+            1,
+            crate::CountOverflow::Saturate,
+        );
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.nodes.iter().filter(|n| n.table.is_some()).count()
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first. Only
+    /// the last `order` symbols are considered, and an empty context refers to
+    /// the beginning of a sequence.
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        match self.table(context) {
+            Some(t) => t.probability_of(&symbol),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// `None` is returned if the context has never been observed, or if it is
+    /// most likely to be followed by the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`probability`](#method.probability).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::TrieModel::train_from(1, vec!["abc".chars()]);
+    ///
+    /// assert_eq!(model.predict(&['b']), Some(&'c'));
+    /// assert_eq!(model.predict(&['c']), None);
+    /// ```
+    pub fn predict(&self, context: &[TSymbol]) -> Option<&TSymbol> {
+        self.table(context).and_then(|t| t.most_frequent())
+    }
+
+    /// Returns a [Model](struct.Model.html) with the same contexts and counts
+    /// as this one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let model = markov::TrieModel::train_from(1, vec!["abc".chars()]).to_model();
+    ///
+    /// let mut pre = markov::Predictor::new(&model);
+    /// pre.given('a');
+    /// assert_eq!(pre.predict(), Some(&'b'));
+    /// ```
+    pub fn to_model(&self) -> crate::Model<TSymbol> {
+        let mut model = crate::Model::empty(self.order);
+
+        let mut pending = vec![(0, crate::Sequence::empty())];
+        while let Some((index, seq)) = pending.pop() {
+            let node = &self.nodes[index as usize];
+
+            if let Some(t) = &node.table {
+                for (symbol, frequency) in t.iter() {
+                    model.add_weighted(&seq, *symbol, frequency);
+                }
+            }
+            for (symbol, child) in &node.children {
+                pending.push((*child, seq.with_next(*symbol, self.order)));
+            }
+        }
+
+        model
+    }
+
+    fn table(&self, context: &[TSymbol]) -> Option<&crate::Table<TSymbol>> {
+        let context = if context.len() <= self.order {
+            context
+        } else {
+            &context[(context.len() - self.order)..]
+        };
+
+        let mut index = 0;
+        for symbol in context {
+            index = self.child(index, symbol)?;
+        }
+        self.nodes[index as usize].table.as_ref()
+    }
+
+    fn table_or_insert(&mut self, context: &[TSymbol]) -> &mut crate::Table<TSymbol> {
+        let mut index = 0;
+        for symbol in context {
+            index = match self.child(index, symbol) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes.push(Node {
+                        children: vec![],
+                        table: None,
+                    });
+                    self.nodes[index as usize].children.push((*symbol, child));
+                    child
+                }
+            };
+        }
+
+        self.nodes[index as usize]
+            .table
+            .get_or_insert_with(|| crate::Table::with_hasher(Default::default()))
+    }
+
+    fn child(&self, index: u32, symbol: &TSymbol) -> Option<u32> {
+        self.nodes[index as usize]
+            .children
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map(|(_, child)| *child)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Model;
+    use crate::trie_model::TrieModel;
+
+    #[test]
+    fn it_shares_nodes_between_overlapping_contexts() {
+        let m = TrieModel::train_from(3, vec!["abcd".chars(), "abce".chars()]);
+
+        // The contexts [], [a], [a b], [a b c], [b c d] and [b c e] share
+        // nodes for their common prefixes
+        assert_eq!(m.context_count(), 6);
+        assert_eq!(m.nodes.len(), 8);
+    }
+
+    #[test]
+    fn it_matches_an_equivalent_model() {
+        let corpus = vec!["abcab".chars(), "abd".chars(), "cab".chars()];
+        let trie = TrieModel::train_from(2, corpus.clone());
+        let model = Model::train_from(2, corpus);
+
+        assert_eq!(trie.context_count(), model.context_count());
+        assert_eq!(trie.probability(&['a', 'b'], Some('c')), 0.25);
+        assert_eq!(trie.probability(&['a', 'b'], None), 0.5);
+        assert_eq!(trie.probability(&['x', 'a', 'b'], Some('d')), 0.25);
+        assert_eq!(trie.probability(&['b', 'b'], Some('a')), 0.0);
+
+        let converted = trie.to_model();
+        assert_eq!(converted.context_count(), model.context_count());
+        for (seq, t) in model.tables() {
+            let actual = converted.table(seq).unwrap();
+            for (symbol, frequency) in t.iter() {
+                assert_eq!(actual.frequency_of(symbol), frequency);
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+/// Strategies for handling symbols given to a
+/// [Predictor](struct.Predictor.html) which its model has never observed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unknown<TSymbol> {
+    /// Unknown symbols are kept in the context, which will then never match
+    /// an observed context until they fall out of it.
+    Keep,
+
+    /// Unknown symbols are ignored, so that predictions remain based on the
+    /// known symbols given before them.
+    Skip,
+
+    /// Unknown symbols are replaced by the specified symbol, such as an
+    /// "unknown" symbol introduced via
+    /// [`Model::cap_vocabulary`](struct.Model.html#method.cap_vocabulary).
+    Replace(TSymbol),
+}
@@ -0,0 +1,71 @@
+/// A type in which a [WeightedModel](struct.WeightedModel.html) accumulates
+/// the weights of its transitions.
+///
+/// This is implemented for the unsigned integer types, whose sums saturate at
+/// their largest value, and for `f32` and `f64`, which allow fractional
+/// weights such as soft counts, but not negative, infinite or NaN ones.
+pub trait Weight: std::marker::Copy + std::cmp::PartialOrd {
+    /// Returns a weight of zero.
+    fn zero() -> Self;
+
+    /// Returns the sum of two weights.
+    fn accumulate(self, other: Self) -> Self;
+
+    /// Returns this weight as an `f64`.
+    fn to_f64(self) -> f64;
+
+    /// Returns whether this weight may be added to a model, being neither
+    /// negative nor non-finite.
+    fn is_valid(self) -> bool;
+}
+
+macro_rules! integer_weight {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn accumulate(self, other: Self) -> Self {
+                    self.saturating_add(other)
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn is_valid(self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! float_weight {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> Self {
+                    0.0
+                }
+
+                fn accumulate(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn is_valid(self) -> bool {
+                    self.is_finite() && self >= 0.0
+                }
+            }
+        )*
+    };
+}
+
+integer_weight!(u8, u16, u32, u64, usize);
+float_weight!(f32, f64);
@@ -0,0 +1,355 @@
+/// A Markov model which accumulates weights of a generic type for its
+/// transitions, as an alternative to [Model](struct.Model.html).
+///
+/// Where a [Model](struct.Model.html) counts each observation as one, a
+/// WeightedModel adds an arbitrary weight for each, which may be fractional
+/// when the [Weight](trait.Weight.html) type is `f64`. This allows soft counts
+/// from EM-style algorithms, or importance weights for training sequences, to
+/// be accumulated directly. Weights must not be negative, infinite or NaN.
+///
+/// Probabilities are the weight of a transition divided by the total weight of
+/// all transitions from the same context.
+pub struct WeightedModel<TSymbol, TWeight = f64>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TWeight: crate::Weight,
+{
+    order: usize,
+    contexts: crate::interner::Interner<crate::Sequence<TSymbol>>,
+    // The entries of each context, indexed by context ID
+    tables: Vec<WeightedTable<TSymbol, TWeight>>,
+}
+
+// The symbols following a context are interned in the order in which they were
+// first seen, and their IDs index their weights
+struct WeightedTable<TSymbol, TWeight>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    symbols: crate::interner::Interner<Option<TSymbol>>,
+    weights: Vec<TWeight>,
+}
+
+impl<TSymbol, TWeight> WeightedModel<TSymbol, TWeight>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TWeight: crate::Weight,
+{
+    /// Creates an empty WeightedModel.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The order of the model, as per
+    /// [`Model::empty`](struct.Model.html#method.empty).
+    pub fn empty(order: usize) -> WeightedModel<TSymbol, TWeight> {
+        WeightedModel {
+            order,
+            contexts: crate::interner::Interner::empty(),
+            tables: vec![],
+        }
+    }
+
+    /// Trains the model on a complete sequence, including its end, adding the
+    /// specified weight for each of its transitions.
+    ///
+    /// # Arguments
+    ///
+    /// `sequence` - The symbols of the sequence.
+    ///
+    /// `weight` - The weight of the sequence, such as its importance weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight is negative, infinite or NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::WeightedModel::empty(1);
+    /// model.train_sequence("ab".chars(), 0.75);
+    /// model.train_sequence("ac".chars(), 0.25);
+    ///
+    /// assert_eq!(model.probability(&['a'], Some('b')), 0.75);
+    /// ```
+    pub fn train_sequence<I>(&mut self, sequence: I, weight: TWeight)
+    where
+        I: IntoIterator<Item = TSymbol>,
+    {
+        let mut seq = crate::Sequence::empty();
+        for symbol in sequence {
+            self.add(seq.symbols(), Some(symbol), weight);
+            seq = seq.with_next(symbol, self.order);
+        }
+        self.add(seq.symbols(), None, weight);
+    }
+
+    /// Adds weight to a single transition, such as an expected count
+    /// computed by an EM-style algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, oldest first, as per
+    /// [`Model::end_probability`](struct.Model.html#method.end_probability).
+    ///
+    /// `symbol` - The symbol following the context, where `None` refers to the
+    /// end of a sequence.
+    ///
+    /// `weight` - The weight to add.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight is negative, infinite or NaN.
+    pub fn add(&mut self, context: &[TSymbol], symbol: Option<TSymbol>, weight: TWeight) {
+        assert!(weight.is_valid(), "weights must be finite and not negative");
+
+        let seq = crate::Sequence::from_slice(context, self.order);
+        let id = self.contexts.intern(seq.symbols()) as usize;
+        if id == self.tables.len() {
+            self.tables.push(WeightedTable {
+                symbols: crate::interner::Interner::empty(),
+                weights: vec![],
+            });
+        }
+
+        let table = &mut self.tables[id];
+        let index = table.symbols.intern_value(symbol) as usize;
+        if index == table.weights.len() {
+            table.weights.push(weight);
+        } else {
+            table.weights[index] = table.weights[index].accumulate(weight);
+        }
+    }
+
+    /// Returns the order of this model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of distinct contexts in this model.
+    pub fn context_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns the total weight of a transition, being zero if it has never
+    /// been observed.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`add`](#method.add).
+    ///
+    /// `symbol` - The symbol following the context.
+    pub fn weight(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> TWeight {
+        self.table(context)
+            .and_then(|t| t.symbols.get(&symbol).map(|i| t.weights[i as usize]))
+            .unwrap_or_else(TWeight::zero)
+    }
+
+    /// Returns the probability of the specified symbol following a context,
+    /// where `None` refers to the end of a sequence.
+    ///
+    /// Zero is returned if the context has never been observed, or has only
+    /// been given zero weight.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`add`](#method.add).
+    ///
+    /// `symbol` - The symbol whose probability to return.
+    pub fn probability(&self, context: &[TSymbol], symbol: Option<TSymbol>) -> f64 {
+        let total = match self.table(context) {
+            Some(t) => t.total(),
+            None => return 0.0,
+        };
+
+        if total > 0.0 {
+            self.weight(context, symbol).to_f64() / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the most probable symbol to follow a context.
+    ///
+    /// Ties are broken in favour of whichever symbol was first observed.
+    /// `None` is returned if the context has never been observed, or if it is
+    /// most likely to be followed by the end of a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// `context` - The most recent symbols of a sequence, as per
+    /// [`add`](#method.add).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::WeightedModel::empty(1);
+    /// model.add(&['a'], Some('b'), 0.4);
+    /// model.add(&['a'], Some('c'), 0.6);
+    ///
+    /// assert_eq!(model.predict(&['a']), Some(&'c'));
+    /// ```
+    pub fn predict(&self, context: &[TSymbol]) -> Option<&TSymbol> {
+        let mut best: Option<(&Option<TSymbol>, TWeight)> = None;
+        for (symbol, weight) in self.table(context)?.entries() {
+            if best.map_or(true, |(_, w)| weight > w) {
+                best = Some((symbol, weight));
+            }
+        }
+        best.and_then(|(s, _)| s.as_ref())
+    }
+
+    /// Generates a complete sequence from the model.
+    ///
+    /// # Arguments
+    ///
+    /// `rand_source` - A function for returning values in the [0.0, 1.0)
+    /// range, used to generate the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut model = markov::WeightedModel::<char, u32>::empty(1);
+    /// model.train_sequence("abc".chars(), 2);
+    ///
+    /// assert_eq!(model.generate(Box::new(|| 0.0)), vec!['a', 'b', 'c']);
+    /// ```
+    pub fn generate(&self, mut rand_source: Box<dyn FnMut() -> f64 + '_>) -> Vec<TSymbol> {
+        let mut sequence = vec![];
+        let mut seq = crate::Sequence::empty();
+
+        while let Some(symbol) = self.sample(seq.symbols(), rand_source()) {
+            sequence.push(symbol);
+            seq = seq.with_next(symbol, self.order);
+        }
+
+        sequence
+    }
+
+    fn sample(&self, context: &[TSymbol], sample_value: f64) -> Option<TSymbol> {
+        let table = self.table(context)?;
+        let mut remaining = sample_value * table.total();
+
+        // Rounding may leave a little weight unaccounted for, in which case the
+        // last entry with any weight is drawn
+        let mut last = None;
+        for (symbol, weight) in table.entries() {
+            let weight = weight.to_f64();
+            if weight <= 0.0 {
+                continue;
+            }
+            if remaining < weight {
+                return *symbol;
+            }
+            remaining -= weight;
+            last = *symbol;
+        }
+        last
+    }
+
+    fn table(&self, context: &[TSymbol]) -> Option<&WeightedTable<TSymbol, TWeight>> {
+        let context = &context[context.len().saturating_sub(self.order)..];
+        self.contexts
+            .get(context)
+            .map(|id| &self.tables[id as usize])
+    }
+}
+
+impl<TSymbol, TWeight> WeightedTable<TSymbol, TWeight>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+    TWeight: crate::Weight,
+{
+    // Iterates over entries in the order in which they were first seen
+    fn entries(&self) -> impl Iterator<Item = (&Option<TSymbol>, TWeight)> {
+        self.symbols.iter().zip(self.weights.iter().copied())
+    }
+
+    fn total(&self) -> f64 {
+        self.weights.iter().map(|w| w.to_f64()).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::weighted_model::WeightedModel;
+
+    #[test]
+    fn it_accumulates_fractional_weights() {
+        let mut m = WeightedModel::empty(1);
+        m.add(&['a'], Some('b'), 0.25);
+        m.add(&['x', 'a'], Some('b'), 0.5);
+        m.add(&['a'], None, 0.25);
+
+        assert_eq!(m.context_count(), 1);
+        assert_eq!(m.weight(&['a'], Some('b')), 0.75);
+        assert_eq!(m.weight(&['a'], Some('c')), 0.0);
+        assert_eq!(m.probability(&['a'], Some('b')), 0.75);
+        assert_eq!(m.probability(&['a'], None), 0.25);
+        assert_eq!(m.probability(&['b'], None), 0.0);
+        assert_eq!(m.predict(&['a']), Some(&'b'));
+    }
+
+    #[test]
+    fn it_matches_an_equivalent_model_with_integer_weights() {
+        let corpus = vec!["abcab".chars(), "abd".chars(), "cab".chars()];
+        let model = crate::Model::train_from(2, corpus.clone());
+
+        let mut m = WeightedModel::<char, u32>::empty(2);
+        for sequence in corpus {
+            m.train_sequence(sequence, 1);
+        }
+
+        assert_eq!(m.context_count(), model.context_count());
+        for (seq, t) in model.tables() {
+            for (symbol, frequency) in t.iter() {
+                assert_eq!(m.weight(seq.symbols(), *symbol), frequency as u32);
+                assert_eq!(
+                    m.probability(seq.symbols(), *symbol),
+                    t.probability_of(symbol)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_samples_in_proportion_to_weights() {
+        let mut m = WeightedModel::empty(0);
+        m.train_sequence(vec!['a'], 1.5);
+        m.train_sequence(vec!['b'], 0.5);
+
+        // "a", the end and "b" follow the empty context with weights 1.5, 2
+        // and 0.5, in the order in which they were first seen
+        assert_eq!(m.sample(&[], 0.0), Some('a'));
+        assert_eq!(m.sample(&[], 0.3), Some('a'));
+        assert_eq!(m.sample(&[], 0.5), None);
+        assert_eq!(m.sample(&[], 0.95), Some('b'));
+    }
+
+    #[test]
+    fn it_looks_up_symbols_of_contexts_with_many_entries() {
+        let mut m = WeightedModel::<u32, u32>::empty(1);
+        for symbol in 0..1000 {
+            m.add(&[0], Some(symbol), symbol);
+            m.add(&[0], Some(symbol), 1);
+        }
+
+        assert_eq!(m.context_count(), 1);
+        assert_eq!(m.weight(&[0], Some(999)), 1000);
+        assert_eq!(m.weight(&[0], Some(1000)), 0);
+        assert_eq!(m.predict(&[0]), Some(&999));
+    }
+
+    #[test]
+    #[should_panic(expected = "weights must be finite and not negative")]
+    fn it_rejects_negative_weights() {
+        WeightedModel::empty(1).add(&['a'], Some('b'), -0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "weights must be finite and not negative")]
+    fn it_rejects_non_finite_weights() {
+        WeightedModel::empty(1).train_sequence("ab".chars(), f64::NAN);
+    }
+}
@@ -0,0 +1,158 @@
+// A fixed-capacity ring buffer over the most recent symbols of a sequence,
+// used to track the current context while training, predicting and generating
+// without allocating at each step. Each symbol is written twice, `order`
+// positions apart, so that the window is always contiguous in the buffer and
+// can be hashed and looked up directly as a slice.
+#[derive(Clone, Debug)]
+pub(crate) struct Window<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    buffer: Vec<TSymbol>,
+    order: usize,
+    start: usize,
+    len: usize,
+}
+
+impl<TSymbol> std::borrow::Borrow<[TSymbol]> for Window<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn borrow(&self) -> &[TSymbol] {
+        self.symbols()
+    }
+}
+
+impl<TSymbol> PartialEq for Window<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    fn eq(&self, other: &Window<TSymbol>) -> bool {
+        self.symbols() == other.symbols()
+    }
+}
+
+impl<TSymbol> Window<TSymbol>
+where
+    TSymbol: std::marker::Copy + std::hash::Hash + std::cmp::Eq,
+{
+    pub(crate) fn empty(order: usize) -> Window<TSymbol> {
+        Window {
+            buffer: vec![],
+            order,
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn symbols(&self) -> &[TSymbol] {
+        &self.buffer[self.start..(self.start + self.len)]
+    }
+
+    pub(crate) fn push(&mut self, symbol: TSymbol) {
+        if self.order == 0 {
+            return;
+        }
+
+        // The buffer is allocated once, when there is first a symbol to fill
+        // it with
+        if self.buffer.is_empty() {
+            self.buffer = vec![symbol; self.order * 2];
+        }
+
+        let end = (self.start + self.len) % self.order;
+        self.buffer[end] = symbol;
+        self.buffer[end + self.order] = symbol;
+
+        if self.len == self.order {
+            self.start = (self.start + 1) % self.order;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    pub(crate) fn set(&mut self, symbols: &[TSymbol]) {
+        self.clear();
+        for symbol in &symbols[symbols.len().saturating_sub(self.order)..] {
+            self.push(*symbol);
+        }
+    }
+
+    // Drops the oldest symbols, keeping only the most recent `len`
+    pub(crate) fn keep_last(&mut self, len: usize) {
+        if len < self.len {
+            self.start = (self.start + self.len - len) % self.order;
+            self.len = len;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    pub(crate) fn to_sequence(&self) -> crate::Sequence<TSymbol> {
+        crate::Sequence::from(self.symbols())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::window::Window;
+
+    #[test]
+    fn it_keeps_the_most_recent_symbols() {
+        let mut w = Window::empty(3);
+        assert_eq!(w.symbols(), &[] as &[char]);
+
+        for (c, expected) in "abcdefg"
+            .chars()
+            .zip(["a", "ab", "abc", "bcd", "cde", "def", "efg"])
+        {
+            w.push(c);
+            assert_eq!(w.symbols(), expected.chars().collect::<Vec<_>>().as_slice());
+        }
+
+        w.clear();
+        w.push('x');
+        assert_eq!(w.symbols(), &['x']);
+    }
+
+    #[test]
+    fn it_sets_symbols_from_a_slice() {
+        let mut w = Window::empty(2);
+
+        w.set(&['a', 'b', 'c']);
+        assert_eq!(w.symbols(), &['b', 'c']);
+        w.push('d');
+        assert_eq!(w.symbols(), &['c', 'd']);
+
+        w.set(&['e']);
+        assert_eq!(w.symbols(), &['e']);
+    }
+
+    #[test]
+    fn it_drops_the_oldest_symbols() {
+        let mut w = Window::empty(3);
+        w.set(&['a', 'b', 'c', 'd']);
+
+        w.keep_last(2);
+        assert_eq!(w.symbols(), &['c', 'd']);
+        w.push('e');
+        assert_eq!(w.symbols(), &['c', 'd', 'e']);
+        w.push('f');
+        assert_eq!(w.symbols(), &['d', 'e', 'f']);
+
+        w.keep_last(0);
+        assert!(w.symbols().is_empty());
+        w.push('g');
+        assert_eq!(w.symbols(), &['g']);
+    }
+
+    #[test]
+    fn it_ignores_symbols_for_zero_order() {
+        let mut w = Window::empty(0);
+        w.push('a');
+        assert!(w.symbols().is_empty());
+    }
+}